@@ -0,0 +1,1065 @@
+use crate::gamification::{calculate_global_level, calculate_level};
+use crate::models::*;
+use crate::profiles::active_profile_id;
+use crate::settings::{self, Settings};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Timelike, Utc};
+use rusqlite::{Connection, Result as SqlResult};
+use std::collections::HashMap;
+
+// ============================================================================
+// DATE HELPERS
+// ============================================================================
+
+/// Parse an inclusive `YYYY-MM-DD` date range
+pub fn parse_date_range(start_date: &str, end_date: &str) -> Result<(NaiveDate, NaiveDate), String> {
+    let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid start date '{}': {}", start_date, e))?;
+    let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid end date '{}': {}", end_date, e))?;
+
+    if end < start {
+        return Err(format!("End date {} is before start date {}", end_date, start_date));
+    }
+
+    Ok((start, end))
+}
+
+/// Get the calendar date of an RFC 3339 timestamp in the configured timezone
+pub fn timestamp_date(timestamp: &str, settings: &Settings) -> Option<NaiveDate> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|parsed| settings.local_time(parsed.with_timezone(&Utc)).date())
+}
+
+/// First day of the period containing `date` (weeks start on Monday, as in ISO 8601)
+pub fn period_start(date: NaiveDate, granularity: Granularity) -> NaiveDate {
+    match granularity {
+        Granularity::Day => date,
+        Granularity::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        Granularity::Month => date.with_day(1).unwrap_or(date),
+        Granularity::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date),
+    }
+}
+
+/// First day of the period following the one that starts at `start`
+fn next_period_start(start: NaiveDate, granularity: Granularity) -> NaiveDate {
+    match granularity {
+        Granularity::Day => start + Duration::days(1),
+        Granularity::Week => start + Duration::days(7),
+        Granularity::Month => {
+            let (year, month) = if start.month() == 12 {
+                (start.year() + 1, 1)
+            } else {
+                (start.year(), start.month() + 1)
+            };
+            NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(start)
+        }
+        Granularity::Year => NaiveDate::from_ymd_opt(start.year() + 1, 1, 1).unwrap_or(start),
+    }
+}
+
+/// Split an inclusive date range into periods, clamping the first and last
+/// periods to the range boundaries
+pub fn split_into_periods(
+    start: NaiveDate,
+    end: NaiveDate,
+    granularity: Granularity,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut periods = Vec::new();
+    let mut cursor = period_start(start, granularity);
+
+    while cursor <= end {
+        let next = next_period_start(cursor, granularity);
+        periods.push((cursor.max(start), (next - Duration::days(1)).min(end)));
+        cursor = next;
+    }
+
+    periods
+}
+
+/// Index of the period containing `date`, if any
+pub fn find_period(periods: &[(NaiveDate, NaiveDate)], date: NaiveDate) -> Option<usize> {
+    let index = periods.partition_point(|(start, _)| *start <= date);
+    if index == 0 {
+        return None;
+    }
+    let (_, end) = periods[index - 1];
+    if date <= end {
+        Some(index - 1)
+    } else {
+        None
+    }
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+// ============================================================================
+// XP TIMELINE
+// ============================================================================
+
+/// Build cumulative XP series per category and globally from the XP ledger
+pub fn xp_timeline(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    granularity: Granularity,
+) -> Result<XpTimeline, String> {
+    let (start, end) = parse_date_range(start_date, end_date)?;
+    let periods = split_into_periods(start, end, granularity);
+    let settings = settings::load_settings(conn)?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, color, created_at FROM categories ORDER BY name")
+        .map_err(|e| e.to_string())?;
+
+    let categories = stmt
+        .query_map([], |row| {
+            Ok(Category {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<Category>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT category_id, xp, earned_at FROM xp_ledger WHERE earned_at IS NOT NULL AND profile_id = ?1")
+        .map_err(|e| e.to_string())?;
+
+    let entries = stmt
+        .query_map([active_profile_id(conn)?], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<(String, i64, String)>>>()
+        .map_err(|e| e.to_string())?;
+
+    // XP earned before the range starts, and XP earned inside each period
+    let mut baseline: HashMap<String, i64> = HashMap::new();
+    let mut gained: HashMap<String, Vec<i64>> = HashMap::new();
+
+    for (category_id, xp, earned_at) in entries {
+        let Some(date) = timestamp_date(&earned_at, &settings) else {
+            continue;
+        };
+
+        if date < start {
+            *baseline.entry(category_id).or_default() += xp;
+        } else if let Some(index) = find_period(&periods, date) {
+            gained.entry(category_id).or_insert_with(|| vec![0; periods.len()])[index] += xp;
+        }
+    }
+
+    let mut global_gained = vec![0; periods.len()];
+    let mut global_running: i64 = baseline.values().sum();
+    let mut category_series = Vec::new();
+
+    for category in categories {
+        let mut running = baseline.get(&category.id).copied().unwrap_or(0);
+        let category_gained = gained.get(&category.id);
+
+        let points = periods
+            .iter()
+            .enumerate()
+            .map(|(index, (period_start, period_end))| {
+                let xp_gained = category_gained.map(|g| g[index]).unwrap_or(0);
+                running += xp_gained;
+                global_gained[index] += xp_gained;
+
+                XpTimelinePoint {
+                    period_start: format_date(*period_start),
+                    period_end: format_date(*period_end),
+                    xp_gained,
+                    total_xp: running,
+                    level: calculate_level(running),
+                }
+            })
+            .collect();
+
+        category_series.push(CategoryXpSeries { category, points });
+    }
+
+    let global = periods
+        .iter()
+        .zip(global_gained)
+        .map(|((period_start, period_end), xp_gained)| {
+            global_running += xp_gained;
+            XpTimelinePoint {
+                period_start: format_date(*period_start),
+                period_end: format_date(*period_end),
+                xp_gained,
+                total_xp: global_running,
+                level: calculate_global_level(global_running),
+            }
+        })
+        .collect();
+
+    Ok(XpTimeline {
+        start_date: format_date(start),
+        end_date: format_date(end),
+        granularity,
+        global,
+        categories: category_series,
+    })
+}
+
+// ============================================================================
+// PERIOD METRICS
+// ============================================================================
+
+/// A completed subtask and the time tracked on it
+struct CompletionFact {
+    date: NaiveDate,
+    category_id: Option<String>,
+    duration_seconds: i64,
+    estimate_seconds: Option<i64>,
+    // Stored when the subtask was completed
+    points: i64,
+}
+
+/// A time session, attributed to the day it started
+struct SessionFact {
+    date: NaiveDate,
+    category_id: Option<String>,
+    duration_seconds: i64,
+}
+
+/// Everything needed to aggregate points, completions and focused time over
+/// arbitrary periods
+pub struct Activity {
+    categories: HashMap<String, Category>,
+    completions: Vec<CompletionFact>,
+    sessions: Vec<SessionFact>,
+    completed_tasks: Vec<NaiveDate>,
+    // Completion dates and stored bonuses of tasks that earned the complexity bonus
+    complexity_bonuses: Vec<(NaiveDate, i64)>,
+}
+
+/// Load the active profile's completed work and time sessions
+pub fn load_activity(conn: &Connection) -> Result<Activity, String> {
+    let profile_id = active_profile_id(conn)?;
+    let settings = settings::load_settings(conn)?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, color, created_at FROM categories")
+        .map_err(|e| e.to_string())?;
+
+    let categories = stmt
+        .query_map([], |row| {
+            Ok(Category {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<Category>>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|category| (category.id.clone(), category))
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.completed_at, s.category_id, COALESCE(SUM(ts.duration_seconds), 0) as total_time,
+                    s.estimate_seconds, COALESCE(s.points, 0)
+             FROM subtasks s
+             JOIN tasks t ON s.task_id = t.id
+             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+             WHERE s.status = 'done' AND s.completed_at IS NOT NULL AND t.profile_id = ?1
+             GROUP BY s.id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let completions = stmt
+        .query_map([&profile_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<(String, Option<String>, i64, Option<i64>, i64)>>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|(completed_at, category_id, duration_seconds, estimate_seconds, points)| {
+            Some(CompletionFact {
+                date: timestamp_date(&completed_at, &settings)?,
+                category_id,
+                duration_seconds,
+                estimate_seconds,
+                points,
+            })
+        })
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT ts.started_at, s.category_id, ts.duration_seconds
+             FROM time_sessions ts
+             JOIN subtasks s ON ts.subtask_id = s.id
+             JOIN tasks t ON s.task_id = t.id
+             WHERE t.profile_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let sessions = stmt
+        .query_map([&profile_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, i64>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<(String, Option<String>, i64)>>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|(started_at, category_id, duration_seconds)| {
+            Some(SessionFact {
+                date: timestamp_date(&started_at, &settings)?,
+                category_id,
+                duration_seconds,
+            })
+        })
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT status, completed_at, bonus_points
+             FROM tasks
+             WHERE completed_at IS NOT NULL AND profile_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let task_rows = stmt
+        .query_map([&profile_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<i64>>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<(String, String, Option<i64>)>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut completed_tasks = Vec::new();
+    let mut complexity_bonuses = Vec::new();
+
+    for (status, completed_at, bonus_points) in task_rows {
+        let Some(date) = timestamp_date(&completed_at, &settings) else {
+            continue;
+        };
+        if status == "done" {
+            completed_tasks.push(date);
+        }
+        if let Some(bonus_points) = bonus_points {
+            complexity_bonuses.push((date, bonus_points));
+        }
+    }
+
+    Ok(Activity {
+        categories,
+        completions,
+        sessions,
+        completed_tasks,
+        complexity_bonuses,
+    })
+}
+
+#[derive(Default)]
+struct PeriodTotals {
+    points: i64,
+    subtasks_completed: i64,
+    tasks_completed: i64,
+    focused_seconds: i64,
+    completion_seconds: i64,
+    categories: HashMap<Option<String>, (i64, i64, i64)>, // focused, points, completed
+}
+
+/// Aggregate activity into the given sorted, non-overlapping periods
+pub fn aggregate_periods(activity: &Activity, periods: &[(NaiveDate, NaiveDate)]) -> Vec<PeriodMetrics> {
+    let mut totals: Vec<PeriodTotals> = periods.iter().map(|_| PeriodTotals::default()).collect();
+
+    for completion in &activity.completions {
+        if let Some(index) = find_period(periods, completion.date) {
+            let points = completion.points;
+            let period = &mut totals[index];
+            period.points += points;
+            period.subtasks_completed += 1;
+            period.completion_seconds += completion.duration_seconds;
+
+            let category = period.categories.entry(completion.category_id.clone()).or_default();
+            category.1 += points;
+            category.2 += 1;
+        }
+    }
+
+    for session in &activity.sessions {
+        if let Some(index) = find_period(periods, session.date) {
+            let period = &mut totals[index];
+            period.focused_seconds += session.duration_seconds;
+            period.categories.entry(session.category_id.clone()).or_default().0 += session.duration_seconds;
+        }
+    }
+
+    for date in &activity.completed_tasks {
+        if let Some(index) = find_period(periods, *date) {
+            totals[index].tasks_completed += 1;
+        }
+    }
+
+    for (date, bonus_points) in &activity.complexity_bonuses {
+        if let Some(index) = find_period(periods, *date) {
+            totals[index].points += bonus_points;
+        }
+    }
+
+    periods
+        .iter()
+        .zip(totals)
+        .map(|((period_start, period_end), period)| {
+            let mut categories: Vec<CategoryBreakdown> = period
+                .categories
+                .into_iter()
+                .map(|(category_id, (focused_seconds, points, subtasks_completed))| CategoryBreakdown {
+                    category: category_id.and_then(|id| activity.categories.get(&id).cloned()),
+                    focused_seconds,
+                    points,
+                    subtasks_completed,
+                })
+                .collect();
+
+            categories.sort_by(|a, b| {
+                b.focused_seconds
+                    .cmp(&a.focused_seconds)
+                    .then_with(|| category_name(a).cmp(category_name(b)))
+            });
+
+            let average_completion_time_seconds = if period.subtasks_completed > 0 {
+                period.completion_seconds as f64 / period.subtasks_completed as f64
+            } else {
+                0.0
+            };
+
+            PeriodMetrics {
+                period_start: format_date(*period_start),
+                period_end: format_date(*period_end),
+                points: period.points,
+                subtasks_completed: period.subtasks_completed,
+                tasks_completed: period.tasks_completed,
+                focused_seconds: period.focused_seconds,
+                average_completion_time_seconds,
+                categories,
+            }
+        })
+        .collect()
+}
+
+fn category_name(breakdown: &CategoryBreakdown) -> &str {
+    breakdown.category.as_ref().map(|c| c.name.as_str()).unwrap_or("")
+}
+
+/// Aggregate metrics for a date range split by calendar periods
+pub fn metrics_for_range(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    granularity: Granularity,
+) -> Result<RangeMetrics, String> {
+    let (start, end) = parse_date_range(start_date, end_date)?;
+    let activity = load_activity(conn)?;
+
+    let periods = aggregate_periods(&activity, &split_into_periods(start, end, granularity));
+    let totals = aggregate_periods(&activity, &[(start, end)])
+        .pop()
+        .ok_or_else(|| "Failed to aggregate range totals".to_string())?;
+
+    Ok(RangeMetrics {
+        start_date: format_date(start),
+        end_date: format_date(end),
+        granularity,
+        periods,
+        totals,
+    })
+}
+
+// ============================================================================
+// PERIOD COMPARISON
+// ============================================================================
+
+fn metric_delta(current: f64, previous: f64) -> MetricDelta {
+    let delta = current - previous;
+    let percent_change = if previous != 0.0 {
+        Some(delta / previous * 100.0)
+    } else {
+        None
+    };
+
+    MetricDelta {
+        current,
+        previous,
+        delta,
+        percent_change,
+    }
+}
+
+/// Compare two arbitrary date ranges (e.g. this week against last week)
+pub fn compare_periods(
+    conn: &Connection,
+    current_start: &str,
+    current_end: &str,
+    previous_start: &str,
+    previous_end: &str,
+) -> Result<PeriodComparison, String> {
+    let current_range = parse_date_range(current_start, current_end)?;
+    let previous_range = parse_date_range(previous_start, previous_end)?;
+    let activity = load_activity(conn)?;
+
+    let current = aggregate_periods(&activity, &[current_range])
+        .pop()
+        .ok_or_else(|| "Failed to aggregate current period".to_string())?;
+    let previous = aggregate_periods(&activity, &[previous_range])
+        .pop()
+        .ok_or_else(|| "Failed to aggregate previous period".to_string())?;
+
+    // Union of categories seen in either period, keyed by id (None = uncategorized)
+    let mut category_times: Vec<(Option<Category>, i64, i64)> = Vec::new();
+    for (breakdowns, is_current) in [(&current.categories, true), (&previous.categories, false)] {
+        for breakdown in breakdowns {
+            let id = breakdown.category.as_ref().map(|c| &c.id);
+            let position = category_times
+                .iter()
+                .position(|(category, _, _)| category.as_ref().map(|c| &c.id) == id);
+            let index = position.unwrap_or_else(|| {
+                category_times.push((breakdown.category.clone(), 0, 0));
+                category_times.len() - 1
+            });
+
+            if is_current {
+                category_times[index].1 += breakdown.focused_seconds;
+            } else {
+                category_times[index].2 += breakdown.focused_seconds;
+            }
+        }
+    }
+
+    let categories = category_times
+        .into_iter()
+        .map(|(category, current_seconds, previous_seconds)| CategoryTimeDelta {
+            category,
+            focused_seconds: metric_delta(current_seconds as f64, previous_seconds as f64),
+        })
+        .collect();
+
+    Ok(PeriodComparison {
+        focused_seconds: metric_delta(current.focused_seconds as f64, previous.focused_seconds as f64),
+        points: metric_delta(current.points as f64, previous.points as f64),
+        subtasks_completed: metric_delta(current.subtasks_completed as f64, previous.subtasks_completed as f64),
+        tasks_completed: metric_delta(current.tasks_completed as f64, previous.tasks_completed as f64),
+        average_completion_time_seconds: metric_delta(
+            current.average_completion_time_seconds,
+            previous.average_completion_time_seconds,
+        ),
+        categories,
+        current,
+        previous,
+    })
+}
+
+// ============================================================================
+// ESTIMATION ACCURACY
+// ============================================================================
+
+#[derive(Default)]
+struct EstimationTotals {
+    samples: i64,
+    estimated_seconds: i64,
+    actual_seconds: i64,
+    underestimated: i64,
+    overestimated: i64,
+}
+
+impl EstimationTotals {
+    fn add(&mut self, estimate: i64, actual: i64) {
+        self.samples += 1;
+        self.estimated_seconds += estimate;
+        self.actual_seconds += actual;
+        if actual > estimate {
+            self.underestimated += 1;
+        } else if actual < estimate {
+            self.overestimated += 1;
+        }
+    }
+
+    fn into_stats(self) -> EstimationStats {
+        let ratio = |count: i64| {
+            if self.samples > 0 {
+                count as f64 / self.samples as f64
+            } else {
+                0.0
+            }
+        };
+
+        EstimationStats {
+            samples: self.samples,
+            estimated_seconds: self.estimated_seconds,
+            actual_seconds: self.actual_seconds,
+            actual_to_estimate_ratio: if self.estimated_seconds > 0 {
+                self.actual_seconds as f64 / self.estimated_seconds as f64
+            } else {
+                0.0
+            },
+            underestimated_ratio: ratio(self.underestimated),
+            overestimated_ratio: ratio(self.overestimated),
+        }
+    }
+}
+
+fn category_estimations(
+    activity: &Activity,
+    totals: HashMap<Option<String>, EstimationTotals>,
+) -> Vec<CategoryEstimation> {
+    let mut categories: Vec<CategoryEstimation> = totals
+        .into_iter()
+        .map(|(category_id, totals)| CategoryEstimation {
+            category: category_id.and_then(|id| activity.categories.get(&id).cloned()),
+            stats: totals.into_stats(),
+        })
+        .collect();
+
+    categories.sort_by(|a, b| {
+        let name = |c: &CategoryEstimation| c.category.as_ref().map(|c| c.name.clone()).unwrap_or_default();
+        name(a).cmp(&name(b))
+    });
+
+    categories
+}
+
+/// Compare estimated and actual time of completed subtasks, per category and period
+pub fn estimation_accuracy(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    granularity: Granularity,
+) -> Result<EstimationAccuracy, String> {
+    let (start, end) = parse_date_range(start_date, end_date)?;
+    let periods = split_into_periods(start, end, granularity);
+    let activity = load_activity(conn)?;
+
+    let mut period_totals: Vec<(EstimationTotals, HashMap<Option<String>, EstimationTotals>)> =
+        periods.iter().map(|_| Default::default()).collect();
+    let mut overall = EstimationTotals::default();
+    let mut overall_categories: HashMap<Option<String>, EstimationTotals> = HashMap::new();
+
+    for completion in &activity.completions {
+        let Some(estimate) = completion.estimate_seconds.filter(|estimate| *estimate > 0) else {
+            continue;
+        };
+        let Some(index) = find_period(&periods, completion.date) else {
+            continue;
+        };
+
+        let (period, categories) = &mut period_totals[index];
+        period.add(estimate, completion.duration_seconds);
+        categories
+            .entry(completion.category_id.clone())
+            .or_default()
+            .add(estimate, completion.duration_seconds);
+        overall.add(estimate, completion.duration_seconds);
+        overall_categories
+            .entry(completion.category_id.clone())
+            .or_default()
+            .add(estimate, completion.duration_seconds);
+    }
+
+    let periods = periods
+        .iter()
+        .zip(period_totals)
+        .map(|((period_start, period_end), (totals, categories))| EstimationPeriod {
+            period_start: format_date(*period_start),
+            period_end: format_date(*period_end),
+            overall: totals.into_stats(),
+            categories: category_estimations(&activity, categories),
+        })
+        .collect();
+
+    Ok(EstimationAccuracy {
+        start_date: format_date(start),
+        end_date: format_date(end),
+        granularity,
+        overall: overall.into_stats(),
+        categories: category_estimations(&activity, overall_categories),
+        periods,
+    })
+}
+
+// ============================================================================
+// RUNNING INTERVALS
+// ============================================================================
+
+/// A span of time during which a session's timer was actually running
+pub struct RunningInterval {
+    pub session_id: String,
+    pub subtask_id: String,
+    pub category_id: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|parsed| parsed.with_timezone(&Utc))
+}
+
+/// Load the running intervals of the active profile's time sessions. Sessions recorded before
+/// intervals were tracked are approximated as a single block of
+/// `duration_seconds` ending when the session ended or was last paused.
+pub fn load_running_intervals(conn: &Connection) -> Result<Vec<RunningInterval>, String> {
+    let now = Utc::now();
+
+    let mut stmt = conn
+        .prepare("SELECT session_id, started_at, ended_at FROM session_intervals ORDER BY started_at")
+        .map_err(|e| e.to_string())?;
+
+    let mut recorded: HashMap<String, Vec<(String, Option<String>)>> = HashMap::new();
+    for row in stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+    {
+        let (session_id, started_at, ended_at) = row.map_err(|e| e.to_string())?;
+        recorded.entry(session_id).or_default().push((started_at, ended_at));
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT ts.id, ts.subtask_id, s.category_id, ts.started_at, ts.paused_at, ts.resumed_at,
+                    ts.ended_at, ts.duration_seconds
+             FROM time_sessions ts
+             JOIN subtasks s ON ts.subtask_id = s.id
+             JOIN tasks t ON s.task_id = t.id
+             WHERE t.profile_id = ?1
+             ORDER BY ts.started_at",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let sessions = stmt
+        .query_map([active_profile_id(conn)?], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, i64>(7)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut intervals = Vec::new();
+
+    for (session_id, subtask_id, category_id, started_at, paused_at, resumed_at, ended_at, duration) in sessions {
+        let spans: Vec<(DateTime<Utc>, DateTime<Utc>)> = match recorded.get(&session_id) {
+            Some(rows) => rows
+                .iter()
+                .filter_map(|(start, end)| {
+                    let start = parse_timestamp(start)?;
+                    let end = end.as_deref().map(parse_timestamp).unwrap_or(Some(now))?;
+                    Some((start, end))
+                })
+                .collect(),
+            None => {
+                let Some(started) = parse_timestamp(&started_at) else {
+                    continue;
+                };
+                let paused = paused_at.as_deref().and_then(parse_timestamp);
+                let resumed = resumed_at.as_deref().and_then(parse_timestamp);
+
+                let end = match (ended_at.as_deref().and_then(parse_timestamp), paused) {
+                    (Some(ended), _) => ended,
+                    (None, Some(paused)) if resumed.map_or(true, |resumed| paused > resumed) => paused,
+                    _ => now,
+                };
+                let start = if duration > 0 {
+                    (end - Duration::seconds(duration)).max(started)
+                } else {
+                    started
+                };
+                vec![(start, end)]
+            }
+        };
+
+        for (start, end) in spans.into_iter().filter(|(start, end)| end > start) {
+            intervals.push(RunningInterval {
+                session_id: session_id.clone(),
+                subtask_id: subtask_id.clone(),
+                category_id: category_id.clone(),
+                start,
+                end,
+            });
+        }
+    }
+
+    Ok(intervals)
+}
+
+// ============================================================================
+// FOCUS HEATMAP
+// ============================================================================
+
+/// Add an interval of wall-clock time to a weekday x hour matrix, splitting it at hour boundaries
+fn add_to_heatmap(cells: &mut [Vec<i64>], start: NaiveDateTime, end: NaiveDateTime) {
+    let mut cursor = start;
+
+    while cursor < end {
+        let hour_start = cursor
+            .with_minute(0)
+            .and_then(|t| t.with_second(0))
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(cursor);
+        let chunk_end = (hour_start + Duration::hours(1)).min(end);
+
+        let weekday = cursor.weekday().num_days_from_monday() as usize;
+        cells[weekday][cursor.hour() as usize] += (chunk_end - cursor).num_seconds();
+        cursor = chunk_end;
+    }
+}
+
+/// Aggregate focused time into a 7x24 matrix in the configured timezone, optionally
+/// filtered by category and by an inclusive date range
+pub fn focus_heatmap(
+    conn: &Connection,
+    category_id: Option<&str>,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<FocusHeatmap, String> {
+    let parse_date = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let start = start_date.map(parse_date).transpose()?;
+    let end = end_date.map(parse_date).transpose()?;
+
+    if let (Some(start), Some(end)) = (start, end) {
+        if end < start {
+            return Err(format!("End date {} is before start date {}", end, start));
+        }
+    }
+
+    let lower_bound = start.and_then(|d| d.and_hms_opt(0, 0, 0));
+    let upper_bound = end.and_then(|d| (d + Duration::days(1)).and_hms_opt(0, 0, 0));
+
+    let settings = settings::load_settings(conn)?;
+    let mut cells = vec![vec![0i64; 24]; 7];
+
+    for interval in load_running_intervals(conn)? {
+        if category_id.is_some() && interval.category_id.as_deref() != category_id {
+            continue;
+        }
+
+        let (start, end) = (settings.local_time(interval.start), settings.local_time(interval.end));
+        let start = lower_bound.map_or(start, |bound| start.max(bound));
+        let end = upper_bound.map_or(end, |bound| end.min(bound));
+        add_to_heatmap(&mut cells, start, end);
+    }
+
+    let total_seconds = cells.iter().flatten().sum();
+
+    Ok(FocusHeatmap {
+        cells,
+        total_seconds,
+        category_id: category_id.map(str::to_string),
+        start_date: start.map(format_date),
+        end_date: end.map(format_date),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_tables, record_missing_points};
+    use crate::gamification::{record_xp_gain, ScoringRules};
+
+    #[test]
+    fn test_weekly_periods_follow_iso_weeks() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(); // Wednesday
+        let end = NaiveDate::from_ymd_opt(2025, 1, 13).unwrap(); // Monday
+        let periods = split_into_periods(start, end, Granularity::Week);
+
+        assert_eq!(
+            periods,
+            vec![
+                (start, NaiveDate::from_ymd_opt(2025, 1, 5).unwrap()),
+                (NaiveDate::from_ymd_opt(2025, 1, 6).unwrap(), NaiveDate::from_ymd_opt(2025, 1, 12).unwrap()),
+                (end, end),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xp_timeline_accumulates_from_ledger() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let backend: String = conn
+            .query_row("SELECT id FROM categories WHERE name = 'backend'", [], |row| row.get(0))
+            .unwrap();
+
+        record_xp_gain(&conn, &backend, None, 300, "2024-12-20T10:00:00+00:00").unwrap();
+        record_xp_gain(&conn, &backend, None, 200, "2025-01-15T10:00:00+00:00").unwrap();
+        record_xp_gain(&conn, &backend, None, 100, "2025-02-03T10:00:00+00:00").unwrap();
+
+        let timeline = xp_timeline(&conn, "2025-01-01", "2025-02-28", Granularity::Month).unwrap();
+        let series = timeline
+            .categories
+            .iter()
+            .find(|s| s.category.id == backend)
+            .unwrap();
+
+        let gained: Vec<i64> = series.points.iter().map(|p| p.xp_gained).collect();
+        let totals: Vec<i64> = series.points.iter().map(|p| p.total_xp).collect();
+        assert_eq!(gained, vec![200, 100]);
+        assert_eq!(totals, vec![500, 600]);
+        assert_eq!(timeline.global[1].total_xp, 600);
+        assert_eq!(series.points[1].level, calculate_level(600));
+    }
+
+    #[test]
+    fn test_metrics_for_range_buckets_by_calendar_month() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let backend: String = conn
+            .query_row("SELECT id FROM categories WHERE name = 'backend'", [], |row| row.get(0))
+            .unwrap();
+
+        conn.execute(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES ('t1', 'Task', 'todo', ?1, ?1)",
+            ["2025-01-01T09:00:00+00:00"],
+        )
+        .unwrap();
+        for (id, completed_at, duration) in [
+            ("s1", "2025-01-31T18:00:00+00:00", 600),
+            ("s2", "2025-02-01T10:00:00+00:00", 3600),
+        ] {
+            conn.execute(
+                "INSERT INTO subtasks (id, task_id, title, status, created_at, updated_at, completed_at, category_id)
+                 VALUES (?1, 't1', ?1, 'done', ?2, ?2, ?2, ?3)",
+                rusqlite::params![id, completed_at, &backend],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+                 VALUES (?1, ?1, ?2, ?2, ?3)",
+                rusqlite::params![id, completed_at, duration],
+            )
+            .unwrap();
+        }
+        record_missing_points(&conn, &ScoringRules::default()).unwrap();
+
+        // Points were stored on completion, so new scoring rules leave them alone
+        let patch = serde_json::json!({ "scoring": { "basePoints": 50 }, "timezone": "UTC" });
+        crate::settings::update_settings(&conn, patch, "2025-03-01T00:00:00+00:00").unwrap();
+        let metrics = metrics_for_range(&conn, "2025-01-15", "2025-02-28", Granularity::Month).unwrap();
+
+        assert_eq!(metrics.periods.len(), 2);
+        assert_eq!(metrics.periods[0].period_start, "2025-01-15");
+        assert_eq!(metrics.periods[0].points, 15);
+        assert_eq!(metrics.periods[1].points, 10);
+        assert_eq!(metrics.periods[1].focused_seconds, 3600);
+        assert_eq!(metrics.totals.subtasks_completed, 2);
+        assert_eq!(metrics.totals.categories[0].focused_seconds, 4200);
+        assert_eq!(metrics.totals.average_completion_time_seconds, 2100.0);
+    }
+
+    #[test]
+    fn test_range_metrics_use_the_configured_timezone() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let backend: String = conn
+            .query_row("SELECT id FROM categories WHERE name = 'backend'", [], |row| row.get(0))
+            .unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES ('t1', 'Task', 'todo', '2025-01-31', '2025-01-31');
+             INSERT INTO subtasks (id, task_id, title, status, created_at, updated_at, completed_at, points)
+                 VALUES ('s1', 't1', 'Step', 'done', '2025-01-31', '2025-01-31', '2025-02-01T02:00:00+00:00', 10);
+             INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+                 VALUES ('ts1', 's1', '2025-02-01T01:00:00+00:00', '2025-02-01T02:00:00+00:00', 3600);",
+        )
+        .unwrap();
+        record_xp_gain(&conn, &backend, None, 120, "2025-02-01T02:00:00+00:00").unwrap();
+        crate::settings::update_settings(&conn, serde_json::json!({ "timezone": "-05:00" }), "2025-02-01T03:00:00+00:00")
+            .unwrap();
+
+        // 02:00 UTC on February 1st is still January 31st five hours west
+        let metrics = metrics_for_range(&conn, "2025-01-01", "2025-02-28", Granularity::Month).unwrap();
+        assert_eq!(metrics.periods[0].points, 10);
+        assert_eq!(metrics.periods[0].focused_seconds, 3600);
+        assert_eq!(metrics.periods[1].subtasks_completed, 0);
+
+        let timeline = xp_timeline(&conn, "2025-01-01", "2025-02-28", Granularity::Month).unwrap();
+        assert_eq!(timeline.global[0].xp_gained, 120);
+        assert_eq!(timeline.global[1].xp_gained, 0);
+    }
+
+    #[test]
+    fn test_metric_delta_percent_change() {
+        let delta = metric_delta(150.0, 100.0);
+        assert_eq!(delta.delta, 50.0);
+        assert_eq!(delta.percent_change, Some(50.0));
+        assert_eq!(metric_delta(10.0, 0.0).percent_change, None);
+    }
+
+    #[test]
+    fn test_heatmap_splits_intervals_at_hour_boundaries() {
+        let mut cells = vec![vec![0i64; 24]; 7];
+        let start = parse_timestamp("2025-01-05T23:45:00+00:00").unwrap().naive_utc(); // Sunday
+        let end = parse_timestamp("2025-01-06T01:15:00+00:00").unwrap().naive_utc(); // Monday
+
+        add_to_heatmap(&mut cells, start, end);
+
+        assert_eq!(cells[6][23], 900);
+        assert_eq!(cells[0][0], 3600);
+        assert_eq!(cells[0][1], 900);
+        assert_eq!(cells.iter().flatten().sum::<i64>(), 5400);
+    }
+
+    #[test]
+    fn test_heatmap_uses_the_configured_timezone() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES ('t1', 'Task', 'todo', '2025-01-05', '2025-01-05');
+             INSERT INTO subtasks (id, task_id, title, status, created_at, updated_at) VALUES ('s1', 't1', 'Step', 'done', '2025-01-05', '2025-01-05');
+             INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+                 VALUES ('ts1', 's1', '2025-01-05T23:00:00+00:00', '2025-01-05T23:30:00+00:00', 1800);",
+        )
+        .unwrap();
+        crate::settings::update_settings(&conn, serde_json::json!({ "timezone": "+02:00" }), "2025-01-06T00:00:00+00:00")
+            .unwrap();
+
+        // Sunday 23:00 UTC is Monday 01:00 two hours east, which is also the first day of the range
+        let heatmap = focus_heatmap(&conn, None, Some("2025-01-06"), None).unwrap();
+        assert_eq!(heatmap.cells[0][1], 1800);
+        assert_eq!(heatmap.total_seconds, 1800);
+    }
+
+    #[test]
+    fn test_estimation_totals_ratios() {
+        let mut totals = EstimationTotals::default();
+        totals.add(1800, 2700); // took longer than estimated
+        totals.add(1800, 900); // finished early
+        totals.add(600, 600);
+
+        let stats = totals.into_stats();
+        assert_eq!(stats.samples, 3);
+        assert_eq!(stats.actual_to_estimate_ratio, 1.0);
+        assert!((stats.underestimated_ratio - 1.0 / 3.0).abs() < f64::EPSILON);
+        assert!((stats.overestimated_ratio - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+}
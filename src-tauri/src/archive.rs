@@ -0,0 +1,781 @@
+use crate::db::{ensure_category_experience, record_missing_points};
+use crate::gamification::{calculate_level, update_user_profile_level};
+use crate::models::*;
+use crate::profiles::active_profile_id;
+use crate::settings;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Params, Result as SqlResult, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Identifies DevFocus archives among other JSON files
+pub const ARCHIVE_FORMAT: &str = "devfocus-archive";
+
+/// Bump when the archive layout changes; older archives must keep importing
+pub const ARCHIVE_VERSION: i64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Archive {
+    pub format: String,
+    pub version: i64,
+    pub exported_at: String,
+    #[serde(default)]
+    pub categories: Vec<ArchiveCategory>,
+    #[serde(default)]
+    pub category_experience: Vec<ArchiveCategoryExperience>,
+    #[serde(default)]
+    pub tasks: Vec<ArchiveTask>,
+    #[serde(default)]
+    pub subtasks: Vec<ArchiveSubtask>,
+    #[serde(default)]
+    pub sessions: Vec<ArchiveSession>,
+    #[serde(default)]
+    pub session_intervals: Vec<ArchiveSessionInterval>,
+    #[serde(default)]
+    pub xp_ledger: Vec<ArchiveXpEntry>,
+    pub profile: Option<ArchiveProfile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveCategory {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveCategoryExperience {
+    pub category_id: String,
+    pub total_xp: i64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveTask {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+    pub estimate_seconds: Option<i64>,
+    // Stored scores; archives from before they existed are scored on import
+    #[serde(default)]
+    pub bonus_points: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSubtask {
+    pub id: String,
+    pub task_id: String,
+    pub title: String,
+    pub status: String,
+    pub category_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+    pub estimate_seconds: Option<i64>,
+    #[serde(default)]
+    pub points: Option<i64>,
+    #[serde(default)]
+    pub efficient: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSession {
+    pub id: String,
+    pub subtask_id: String,
+    pub started_at: String,
+    pub paused_at: Option<String>,
+    pub resumed_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub duration_seconds: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSessionInterval {
+    pub id: String,
+    pub session_id: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveXpEntry {
+    pub id: String,
+    pub category_id: String,
+    pub subtask_id: Option<String>,
+    pub xp: i64,
+    pub earned_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveProfile {
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    pub last_work_date: Option<String>,
+    pub created_at: String,
+}
+
+impl Archive {
+    pub fn summary(&self, path: &str) -> ExportSummary {
+        ExportSummary {
+            path: path.to_string(),
+            tasks: self.tasks.len() as i64,
+            subtasks: self.subtasks.len() as i64,
+            categories: self.categories.len() as i64,
+            sessions: self.sessions.len() as i64,
+        }
+    }
+}
+
+fn query_all<T, P, F>(conn: &Connection, sql: &str, params: P, map: F) -> Result<Vec<T>, String>
+where
+    P: Params,
+    F: FnMut(&Row<'_>) -> SqlResult<T>,
+{
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params, map)
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<T>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+// ============================================================================
+// EXPORT
+// ============================================================================
+
+/// Snapshot every table that holds the active profile's data
+pub fn export_archive(conn: &Connection, exported_at: &str) -> Result<Archive, String> {
+    let profile_id = active_profile_id(conn)?;
+    let categories = query_all(
+        conn,
+        "SELECT id, name, color, created_at FROM categories ORDER BY created_at, id",
+        [],
+        |row| {
+            Ok(ArchiveCategory {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        },
+    )?;
+
+    let category_experience = query_all(
+        conn,
+        "SELECT category_id, total_xp, updated_at FROM category_experience WHERE profile_id = ?1 ORDER BY category_id",
+        [&profile_id],
+        |row| {
+            Ok(ArchiveCategoryExperience {
+                category_id: row.get(0)?,
+                total_xp: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        },
+    )?;
+
+    let tasks = query_all(
+        conn,
+        "SELECT id, title, description, status, created_at, updated_at, completed_at, estimate_seconds, bonus_points
+         FROM tasks WHERE profile_id = ?1 ORDER BY created_at, id",
+        [&profile_id],
+        |row| {
+            Ok(ArchiveTask {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                description: row.get(2)?,
+                status: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                completed_at: row.get(6)?,
+                estimate_seconds: row.get(7)?,
+                bonus_points: row.get(8)?,
+            })
+        },
+    )?;
+
+    let subtasks = query_all(
+        conn,
+        "SELECT s.id, s.task_id, s.title, s.status, s.category_id, s.created_at, s.updated_at, s.completed_at,
+                s.estimate_seconds, s.points, s.efficient
+         FROM subtasks s
+         JOIN tasks t ON s.task_id = t.id
+         WHERE t.profile_id = ?1
+         ORDER BY s.created_at, s.id",
+        [&profile_id],
+        |row| {
+            Ok(ArchiveSubtask {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                title: row.get(2)?,
+                status: row.get(3)?,
+                category_id: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+                completed_at: row.get(7)?,
+                estimate_seconds: row.get(8)?,
+                points: row.get(9)?,
+                efficient: row.get(10)?,
+            })
+        },
+    )?;
+
+    let sessions = query_all(
+        conn,
+        "SELECT ts.id, ts.subtask_id, ts.started_at, ts.paused_at, ts.resumed_at, ts.ended_at, ts.duration_seconds
+         FROM time_sessions ts
+         JOIN subtasks s ON ts.subtask_id = s.id
+         JOIN tasks t ON s.task_id = t.id
+         WHERE t.profile_id = ?1
+         ORDER BY ts.started_at, ts.id",
+        [&profile_id],
+        |row| {
+            Ok(ArchiveSession {
+                id: row.get(0)?,
+                subtask_id: row.get(1)?,
+                started_at: row.get(2)?,
+                paused_at: row.get(3)?,
+                resumed_at: row.get(4)?,
+                ended_at: row.get(5)?,
+                duration_seconds: row.get(6)?,
+            })
+        },
+    )?;
+
+    let session_intervals = query_all(
+        conn,
+        "SELECT si.id, si.session_id, si.started_at, si.ended_at
+         FROM session_intervals si
+         JOIN time_sessions ts ON si.session_id = ts.id
+         JOIN subtasks s ON ts.subtask_id = s.id
+         JOIN tasks t ON s.task_id = t.id
+         WHERE t.profile_id = ?1
+         ORDER BY si.started_at, si.id",
+        [&profile_id],
+        |row| {
+            Ok(ArchiveSessionInterval {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                started_at: row.get(2)?,
+                ended_at: row.get(3)?,
+            })
+        },
+    )?;
+
+    let xp_ledger = query_all(
+        conn,
+        "SELECT id, category_id, subtask_id, xp, earned_at FROM xp_ledger WHERE profile_id = ?1 ORDER BY earned_at, id",
+        [&profile_id],
+        |row| {
+            Ok(ArchiveXpEntry {
+                id: row.get(0)?,
+                category_id: row.get(1)?,
+                subtask_id: row.get(2)?,
+                xp: row.get(3)?,
+                earned_at: row.get(4)?,
+            })
+        },
+    )?;
+
+    let profile = conn
+        .query_row(
+            "SELECT current_streak, longest_streak, last_work_date, created_at FROM user_profile WHERE id = ?1",
+            [&profile_id],
+            |row| {
+                Ok(ArchiveProfile {
+                    current_streak: row.get(0)?,
+                    longest_streak: row.get(1)?,
+                    last_work_date: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Archive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at: exported_at.to_string(),
+        categories,
+        category_experience,
+        tasks,
+        subtasks,
+        sessions,
+        session_intervals,
+        xp_ledger,
+        profile,
+    })
+}
+
+// ============================================================================
+// IMPORT
+// ============================================================================
+
+/// Parse an archive, rejecting other JSON files and archives from newer versions
+pub fn parse_archive(json: &str) -> Result<Archive, String> {
+    let archive: Archive = serde_json::from_str(json).map_err(|e| format!("Invalid archive: {}", e))?;
+
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(format!("Not a DevFocus archive (format '{}')", archive.format));
+    }
+    if archive.version > ARCHIVE_VERSION {
+        return Err(format!(
+            "Archive version {} is newer than this app supports ({})",
+            archive.version, ARCHIVE_VERSION
+        ));
+    }
+
+    Ok(archive)
+}
+
+/// Insert a row unless one with the same primary key exists; returns whether it was inserted
+fn insert_or_skip<P: Params>(conn: &Connection, sql: &str, params: P) -> Result<bool, String> {
+    conn.execute(sql, params)
+        .map(|changes| changes > 0)
+        .map_err(|e| e.to_string())
+}
+
+/// Import an archive into the active profile in a single transaction. `Replace` wipes the
+/// profile's current data first, along with categories nothing in any profile refers to;
+/// `Merge` keeps it, skips records whose id already exists and maps archive categories onto
+/// existing categories with the same name.
+pub fn import_archive(conn: &mut Connection, archive: &Archive, mode: ImportMode) -> Result<ImportSummary, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut summary = ImportSummary::default();
+    let profile_id = active_profile_id(&tx)?;
+
+    if mode == ImportMode::Replace {
+        for sql in [
+            "DELETE FROM tasks WHERE profile_id = ?1",
+            "DELETE FROM xp_ledger WHERE profile_id = ?1",
+            "DELETE FROM category_experience WHERE profile_id = ?1",
+        ] {
+            tx.execute(sql, [&profile_id])
+                .map_err(|e| format!("Failed to clear existing data: {}", e))?;
+        }
+        // Categories are shared and deleting one cascades to other profiles' XP, so only
+        // drop the ones no subtask, ledger entry or other profile's experience refers to
+        tx.execute(
+            "DELETE FROM categories
+             WHERE id NOT IN (SELECT category_id FROM subtasks WHERE category_id IS NOT NULL)
+               AND id NOT IN (SELECT category_id FROM xp_ledger)
+               AND id NOT IN (SELECT category_id FROM category_experience)",
+            [],
+        )
+        .map_err(|e| format!("Failed to clear existing data: {}", e))?;
+    }
+
+    // Archive category id -> local category id
+    let mut category_ids: HashMap<String, String> = HashMap::new();
+    for category in &archive.categories {
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT id FROM categories WHERE id = ?1 OR name = ?2 ORDER BY id = ?1 DESC LIMIT 1",
+                params![category.id, category.name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+
+        match existing {
+            Some(id) => {
+                if id != category.id {
+                    summary.categories_remapped += 1;
+                }
+                category_ids.insert(category.id.clone(), id);
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO categories (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![category.id, category.name, category.color, category.created_at],
+                )
+                .map_err(|e| format!("Failed to import category {}: {}", category.name, e))?;
+                summary.categories_created += 1;
+                category_ids.insert(category.id.clone(), category.id.clone());
+            }
+        }
+    }
+
+    let now = Utc::now().to_rfc3339();
+    ensure_category_experience(&tx).map_err(|e| e.to_string())?;
+
+    if mode == ImportMode::Replace {
+        // Totals come straight from the archive; merges add imported ledger entries instead
+        for experience in &archive.category_experience {
+            if let Some(category_id) = category_ids.get(&experience.category_id) {
+                tx.execute(
+                    "UPDATE category_experience SET total_xp = ?1, updated_at = ?2 WHERE profile_id = ?3 AND category_id = ?4",
+                    params![experience.total_xp, experience.updated_at, &profile_id, category_id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    for task in &archive.tasks {
+        let inserted = insert_or_skip(
+            &tx,
+            "INSERT OR IGNORE INTO tasks (id, title, description, status, created_at, updated_at, completed_at, estimate_seconds,
+                                          profile_id, bonus_points)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                task.id,
+                task.title,
+                task.description,
+                task.status,
+                task.created_at,
+                task.updated_at,
+                task.completed_at,
+                task.estimate_seconds,
+                &profile_id,
+                task.bonus_points
+            ],
+        )?;
+        if inserted {
+            summary.tasks_imported += 1;
+        } else {
+            summary.duplicates_skipped += 1;
+        }
+    }
+
+    for subtask in &archive.subtasks {
+        let category_id = subtask.category_id.as_ref().and_then(|id| category_ids.get(id));
+        let inserted = insert_or_skip(
+            &tx,
+            "INSERT OR IGNORE INTO subtasks (id, task_id, title, status, category_id, created_at, updated_at, completed_at,
+                                             estimate_seconds, points, efficient)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                subtask.id,
+                subtask.task_id,
+                subtask.title,
+                subtask.status,
+                category_id,
+                subtask.created_at,
+                subtask.updated_at,
+                subtask.completed_at,
+                subtask.estimate_seconds,
+                subtask.points,
+                subtask.efficient
+            ],
+        )?;
+        if inserted {
+            summary.subtasks_imported += 1;
+        } else {
+            summary.duplicates_skipped += 1;
+        }
+    }
+
+    for session in &archive.sessions {
+        let inserted = insert_or_skip(
+            &tx,
+            "INSERT OR IGNORE INTO time_sessions (id, subtask_id, started_at, paused_at, resumed_at, ended_at, duration_seconds)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                session.id,
+                session.subtask_id,
+                session.started_at,
+                session.paused_at,
+                session.resumed_at,
+                session.ended_at,
+                session.duration_seconds
+            ],
+        )?;
+        if inserted {
+            summary.sessions_imported += 1;
+        } else {
+            summary.duplicates_skipped += 1;
+        }
+    }
+
+    for interval in &archive.session_intervals {
+        insert_or_skip(
+            &tx,
+            "INSERT OR IGNORE INTO session_intervals (id, session_id, started_at, ended_at) VALUES (?1, ?2, ?3, ?4)",
+            params![interval.id, interval.session_id, interval.started_at, interval.ended_at],
+        )?;
+    }
+
+    for entry in &archive.xp_ledger {
+        let Some(category_id) = category_ids.get(&entry.category_id) else {
+            continue;
+        };
+        let inserted = insert_or_skip(
+            &tx,
+            "INSERT OR IGNORE INTO xp_ledger (id, category_id, subtask_id, xp, earned_at, profile_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![entry.id, category_id, entry.subtask_id, entry.xp, entry.earned_at, &profile_id],
+        )?;
+        if inserted && mode == ImportMode::Merge {
+            tx.execute(
+                "UPDATE category_experience SET total_xp = total_xp + ?1, updated_at = ?2
+                 WHERE profile_id = ?3 AND category_id = ?4",
+                params![entry.xp, now, &profile_id, category_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let rules = settings::scoring_rules(&tx)?;
+    record_missing_points(&tx, &rules).map_err(|e| e.to_string())?;
+
+    // Levels are derived from XP, so recompute them rather than trusting the archive
+    let totals = query_all(
+        &tx,
+        "SELECT category_id, total_xp FROM category_experience WHERE profile_id = ?1",
+        [&profile_id],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+    )?;
+    for (category_id, total_xp) in totals {
+        tx.execute(
+            "UPDATE category_experience SET level = ?1 WHERE profile_id = ?2 AND category_id = ?3",
+            params![calculate_level(total_xp), &profile_id, category_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(profile) = &archive.profile {
+        let result = match mode {
+            ImportMode::Replace => tx.execute(
+                "UPDATE user_profile SET current_streak = ?1, longest_streak = ?2, last_work_date = ?3, created_at = ?4
+                 WHERE id = ?5",
+                params![
+                    profile.current_streak,
+                    profile.longest_streak,
+                    profile.last_work_date,
+                    profile.created_at,
+                    &profile_id
+                ],
+            ),
+            ImportMode::Merge => tx.execute(
+                "UPDATE user_profile SET longest_streak = MAX(longest_streak, ?1) WHERE id = ?2",
+                params![profile.longest_streak, &profile_id],
+            ),
+        };
+        result.map_err(|e| format!("Failed to import profile: {}", e))?;
+    }
+    update_user_profile_level(&tx, &profile_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use crate::gamification::record_xp_gain;
+
+    const EXPORTED_AT: &str = "2025-06-01T12:00:00+00:00";
+
+    fn new_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn category_id(conn: &Connection, name: &str) -> String {
+        conn.query_row("SELECT id FROM categories WHERE name = ?1", [name], |row| row.get(0))
+            .unwrap()
+    }
+
+    /// A task with two subtasks, sessions, intervals and XP in a custom and a default category
+    fn seed(conn: &Connection) {
+        let backend = category_id(conn, "backend");
+        conn.execute(
+            "INSERT INTO categories (id, name, color, created_at) VALUES ('cat-design', 'design', '#ff00ff', ?1)",
+            [EXPORTED_AT],
+        )
+        .unwrap();
+        ensure_category_experience(conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO tasks (id, title, description, status, created_at, updated_at, completed_at, estimate_seconds)
+             VALUES ('t1', 'Checkout', 'New flow', 'done', '2025-05-01T09:00:00+00:00',
+                     '2025-05-02T18:00:00+00:00', '2025-05-02T18:00:00+00:00', 7200)",
+            [],
+        )
+        .unwrap();
+        for (id, title, category, completed_at) in [
+            ("s1", "API", backend.as_str(), "2025-05-01T11:00:00+00:00"),
+            ("s2", "Mockups", "cat-design", "2025-05-02T12:00:00+00:00"),
+        ] {
+            conn.execute(
+                "INSERT INTO subtasks (id, task_id, title, status, category_id, created_at, updated_at, completed_at)
+                 VALUES (?1, 't1', ?2, 'done', ?3, '2025-05-01T09:00:00+00:00', ?4, ?4)",
+                params![id, title, category, completed_at],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+                 VALUES (?1, ?2, '2025-05-01T10:00:00+00:00', ?3, 3600)",
+                params![format!("ts-{}", id), id, completed_at],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO session_intervals (id, session_id, started_at, ended_at)
+                 VALUES (?1, ?2, '2025-05-01T10:00:00+00:00', ?3)",
+                params![format!("si-{}", id), format!("ts-{}", id), completed_at],
+            )
+            .unwrap();
+            record_xp_gain(conn, category, Some(id), 3600, completed_at).unwrap();
+            conn.execute(
+                "UPDATE category_experience SET total_xp = total_xp + 3600, level = 6 WHERE category_id = ?1",
+                [category],
+            )
+            .unwrap();
+        }
+
+        conn.execute(
+            "UPDATE user_profile SET current_streak = 3, longest_streak = 9, last_work_date = '2025-05-02'",
+            [],
+        )
+        .unwrap();
+        update_user_profile_level(conn, &active_profile_id(conn).unwrap()).unwrap();
+        record_missing_points(conn, &settings::scoring_rules(conn).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_replace_import_round_trips() {
+        let source = new_connection();
+        seed(&source);
+        let archive = export_archive(&source, EXPORTED_AT).unwrap();
+        let json = serde_json::to_string(&archive).unwrap();
+
+        let mut target = new_connection();
+        let summary = import_archive(&mut target, &parse_archive(&json).unwrap(), ImportMode::Replace).unwrap();
+        assert_eq!(summary.tasks_imported, 1);
+        assert_eq!(summary.subtasks_imported, 2);
+        assert_eq!(summary.sessions_imported, 2);
+        assert_eq!(summary.duplicates_skipped, 0);
+
+        assert_eq!(export_archive(&target, EXPORTED_AT).unwrap(), archive);
+    }
+
+    #[test]
+    fn test_replace_import_keeps_other_profiles_categories_and_xp() {
+        let source = new_connection();
+        seed(&source);
+        let archive = export_archive(&source, EXPORTED_AT).unwrap();
+
+        // The owner has XP in a category of their own and in a default one, and none in the rest
+        let mut conn = new_connection();
+        let owner = active_profile_id(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO categories (id, name, color, created_at) VALUES ('cat-notes', 'notes', '#00ffff', ?1)",
+            [EXPORTED_AT],
+        )
+        .unwrap();
+        ensure_category_experience(&conn).unwrap();
+        for category in ["cat-notes".to_string(), category_id(&conn, "backend")] {
+            conn.execute(
+                "INSERT INTO xp_ledger (id, category_id, xp, earned_at, profile_id) VALUES (?1, ?2, 500, ?3, ?4)",
+                params![format!("xp-{}", category), &category, EXPORTED_AT, &owner],
+            )
+            .unwrap();
+            conn.execute(
+                "UPDATE category_experience SET total_xp = 500 WHERE category_id = ?1 AND profile_id = ?2",
+                params![&category, &owner],
+            )
+            .unwrap();
+        }
+
+        let snapshot = |conn: &Connection| -> (i64, i64, i64, i64) {
+            conn.query_row(
+                "SELECT (SELECT COUNT(*) FROM categories),
+                        (SELECT COUNT(*) FROM category_experience WHERE profile_id = ?1),
+                        (SELECT COALESCE(SUM(total_xp), 0) FROM category_experience WHERE profile_id = ?1),
+                        (SELECT COALESCE(SUM(xp), 0) FROM xp_ledger WHERE profile_id = ?1)",
+                [&owner],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap()
+        };
+        let before = snapshot(&conn);
+        assert_eq!((before.2, before.3), (1000, 1000));
+
+        let other = crate::profiles::create_profile(&conn, "Side project").unwrap();
+        crate::profiles::switch_profile(&conn, &other.id).unwrap();
+        let summary = import_archive(&mut conn, &archive, ImportMode::Replace).unwrap();
+        assert_eq!(summary.tasks_imported, 1);
+        assert_eq!(summary.categories_created, 1);
+
+        // Only the archive's new category was added; the owner's categories and XP are untouched
+        let after = snapshot(&conn);
+        assert_eq!(after, (before.0 + 1, before.1 + 1, 1000, 1000));
+    }
+
+    #[test]
+    fn test_merge_dedupes_ids_and_remaps_categories_by_name() {
+        let source = new_connection();
+        seed(&source);
+        let archive = export_archive(&source, EXPORTED_AT).unwrap();
+
+        // Importing into itself only finds duplicates
+        let mut same = new_connection();
+        seed(&same);
+        let own_archive = export_archive(&same, EXPORTED_AT).unwrap();
+        let summary = import_archive(&mut same, &own_archive, ImportMode::Merge).unwrap();
+        assert_eq!(summary.tasks_imported + summary.subtasks_imported + summary.sessions_imported, 0);
+        assert_eq!(summary.duplicates_skipped, 5);
+
+        // A colleague's database has its own ids for the default categories
+        let mut target = new_connection();
+        let local_backend = category_id(&target, "backend");
+        assert_ne!(local_backend, category_id(&source, "backend"));
+
+        let summary = import_archive(&mut target, &archive, ImportMode::Merge).unwrap();
+        assert_eq!(summary.categories_created, 1);
+        assert_eq!(summary.categories_remapped, archive.categories.len() as i64 - 1);
+        assert_eq!(summary.subtasks_imported, 2);
+
+        let api_category: String = target
+            .query_row("SELECT category_id FROM subtasks WHERE id = 's1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(api_category, local_backend);
+
+        let (backend_xp, level): (i64, i64) = target
+            .query_row(
+                "SELECT total_xp, level FROM category_experience WHERE category_id = ?1",
+                [&local_backend],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(backend_xp, 3600);
+        assert_eq!(level, calculate_level(3600));
+
+        let (profile_xp, longest_streak): (i64, i64) = target
+            .query_row("SELECT total_xp, longest_streak FROM user_profile", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(profile_xp, 7200);
+        assert_eq!(longest_streak, 9);
+    }
+
+    #[test]
+    fn test_parse_archive_rejects_newer_versions() {
+        let archive = export_archive(&new_connection(), EXPORTED_AT).unwrap();
+        let mut value = serde_json::to_value(&archive).unwrap();
+        value["version"] = serde_json::json!(ARCHIVE_VERSION + 1);
+
+        let error = parse_archive(&value.to_string()).unwrap_err();
+        assert!(error.contains("newer"), "{}", error);
+        assert!(parse_archive(r#"{"format": "other", "version": 1, "exportedAt": ""}"#).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::db::{create_tables, SCHEMA_VERSION};
+use crate::encryption;
+use crate::models::*;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const FILE_PREFIX: &str = "devfocus-";
+const FILE_EXTENSION: &str = ".db";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const TIMESTAMP_LEN: usize = "YYYYMMDD-HHMMSS".len();
+
+/// Tables every restorable database must have
+const REQUIRED_TABLES: [&str; 4] = ["tasks", "subtasks", "time_sessions", "categories"];
+
+/// How often automatic backups are taken and how many are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct BackupSchedule {
+    pub enabled: bool,
+    pub interval_hours: i64,
+    pub retention: usize,
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        BackupSchedule {
+            enabled: true,
+            interval_hours: 24,
+            retention: 7,
+        }
+    }
+}
+
+/// Backups live in a `backups` folder next to the database file
+pub fn backups_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .map(|parent| parent.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+fn kind_slug(kind: BackupKind) -> &'static str {
+    match kind {
+        BackupKind::Manual => "manual",
+        BackupKind::Automatic => "auto",
+        BackupKind::PreRestore => "pre-restore",
+    }
+}
+
+/// Parse `devfocus-<kind>-<YYYYMMDD-HHMMSS>.db`, or `..._<n>.db` for later backups in the same second
+fn parse_file_name(file_name: &str) -> Option<(BackupKind, DateTime<Utc>)> {
+    let stem = file_name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_EXTENSION)?;
+    let stem = match stem.rsplit_once('_') {
+        Some((stem, counter)) if counter.parse::<u32>().is_ok() => stem,
+        _ => stem,
+    };
+    let (slug, timestamp) = stem.split_at(stem.len().checked_sub(TIMESTAMP_LEN)?);
+    let kind = match slug.strip_suffix('-')? {
+        "manual" => BackupKind::Manual,
+        "auto" => BackupKind::Automatic,
+        "pre-restore" => BackupKind::PreRestore,
+        _ => return None,
+    };
+    let created_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?.and_utc();
+    Some((kind, created_at))
+}
+
+/// Read the schema version of a database file without modifying it
+fn read_schema_version(path: &Path) -> Result<i64, String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|e| e.to_string())?;
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn backup_info(path: &Path) -> Option<BackupInfo> {
+    let file_name = path.file_name()?.to_str()?.to_string();
+    let (kind, created_at) = parse_file_name(&file_name)?;
+    let size_bytes = std::fs::metadata(path).ok()?.len();
+
+    Some(BackupInfo {
+        path: path.to_string_lossy().to_string(),
+        kind,
+        created_at: created_at.to_rfc3339(),
+        size_bytes,
+        schema_version: read_schema_version(path).ok(),
+        file_name,
+    })
+}
+
+/// Copy the live database into the backups folder using SQLite's online backup API,
+/// so it is consistent even while the app keeps writing. Encrypted databases are
+/// copied with `VACUUM INTO`, which keeps the copy encrypted with the same key.
+pub fn create_backup(conn: &Connection, dir: &Path, kind: BackupKind, now: DateTime<Utc>) -> Result<BackupInfo, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create backups folder: {}", e))?;
+
+    let stem = format!("{}{}-{}", FILE_PREFIX, kind_slug(kind), now.format(TIMESTAMP_FORMAT));
+    let mut path = dir.join(format!("{}{}", stem, FILE_EXTENSION));
+    // Names have one-second resolution; number later backups of the same second
+    let mut counter = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}{}", stem, counter, FILE_EXTENSION));
+        counter += 1;
+    }
+
+    if encryption::connection_is_encrypted(conn) {
+        let target = path.to_str().ok_or_else(|| format!("Invalid backup path: {}", path.display()))?;
+        conn.execute("VACUUM INTO ?1", [target])
+            .map_err(|e| format!("Backup failed: {}", e))?;
+    } else {
+        conn.backup(DatabaseName::Main, &path, None)
+            .map_err(|e| format!("Backup failed: {}", e))?;
+    }
+
+    backup_info(&path).ok_or_else(|| "Backup was written but could not be read back".to_string())
+}
+
+/// Backups in the folder, newest first
+pub fn list_backups(dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<BackupInfo> = std::fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| backup_info(&entry.path()))
+        .collect();
+
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.file_name.cmp(&b.file_name)));
+    Ok(backups)
+}
+
+/// Delete the oldest automatic backups beyond `retention`
+pub fn prune_backups(dir: &Path, retention: usize) -> Result<usize, String> {
+    let stale: Vec<BackupInfo> = list_backups(dir)?
+        .into_iter()
+        .filter(|backup| backup.kind == BackupKind::Automatic)
+        .skip(retention)
+        .collect();
+
+    for backup in &stale {
+        std::fs::remove_file(&backup.path).map_err(|e| format!("Failed to delete {}: {}", backup.file_name, e))?;
+    }
+
+    Ok(stale.len())
+}
+
+/// Take an automatic backup if the latest one is older than the schedule interval
+pub fn run_scheduled_backup(
+    conn: &Connection,
+    dir: &Path,
+    schedule: &BackupSchedule,
+    now: DateTime<Utc>,
+) -> Result<Option<BackupInfo>, String> {
+    if !schedule.enabled {
+        return Ok(None);
+    }
+
+    let latest = list_backups(dir)?
+        .into_iter()
+        .find(|backup| backup.kind == BackupKind::Automatic)
+        .and_then(|backup| DateTime::parse_from_rfc3339(&backup.created_at).ok());
+
+    if latest.is_some_and(|latest| now - latest.with_timezone(&Utc) < Duration::hours(schedule.interval_hours)) {
+        return Ok(None);
+    }
+
+    let backup = create_backup(conn, dir, BackupKind::Automatic, now)?;
+    prune_backups(dir, schedule.retention)?;
+    Ok(Some(backup))
+}
+
+/// Check that a file is an intact DevFocus database this version can open.
+/// Encrypted backups need the key of the live database.
+pub fn validate_backup(path: &Path, key: Option<&str>) -> Result<i64, String> {
+    if !path.is_file() {
+        return Err(format!("Backup not found: {}", path.display()));
+    }
+
+    let conn = encryption::open_read_only(path, key).map_err(|e| format!("Cannot open backup: {}", e))?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("Backup is not a valid database: {}", e))?;
+    if integrity != "ok" {
+        return Err(format!("Backup failed integrity check: {}", integrity));
+    }
+
+    let version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Backup schema version {} is newer than this app supports ({})",
+            version, SCHEMA_VERSION
+        ));
+    }
+
+    for table in REQUIRED_TABLES {
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(|e| e.to_string())?
+            > 0;
+        if !exists {
+            return Err(format!("Backup is missing the {} table", table));
+        }
+    }
+
+    Ok(version)
+}
+
+/// Replace the live database with a validated backup. The current data is saved
+/// as a pre-restore backup first, and older schemas are migrated afterwards.
+/// `key` is the passphrase of an encrypted live database.
+pub fn restore_backup(
+    conn: &mut Connection,
+    backup_path: &Path,
+    dir: &Path,
+    key: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<BackupInfo, String> {
+    validate_backup(backup_path, key)?;
+
+    let safety = create_backup(conn, dir, BackupKind::PreRestore, now)?;
+
+    match key {
+        Some(key) => encryption::replace_from(conn, backup_path, key),
+        None => conn
+            .restore(DatabaseName::Main, backup_path, None::<fn(Progress)>)
+            .map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("Restore failed: {}", e))?;
+    create_tables(conn).map_err(|e| format!("Failed to migrate restored database: {}", e))?;
+
+    Ok(safety)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    fn task_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap()
+    }
+
+    fn insert_task(conn: &Connection, id: &str) {
+        conn.execute(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES (?1, 'Task', 'todo', ?2, ?2)",
+            [id, "2025-05-01T08:00:00+00:00"],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_backup_and_restore_round_trip() {
+        let dir = TempDir::new("backup");
+        let mut conn = Connection::open(dir.path().join("live.db")).unwrap();
+        create_tables(&conn).unwrap();
+        insert_task(&conn, "t1");
+
+        let backup = create_backup(&conn, dir.path(), BackupKind::Manual, at(9)).unwrap();
+        assert_eq!(backup.file_name, "devfocus-manual-20250501-090000.db");
+        assert_eq!(backup.schema_version, Some(SCHEMA_VERSION));
+
+        insert_task(&conn, "t2");
+        assert_eq!(task_count(&conn), 2);
+
+        let safety = restore_backup(&mut conn, Path::new(&backup.path), dir.path(), None, at(10)).unwrap();
+        assert_eq!(safety.kind, BackupKind::PreRestore);
+        assert_eq!(task_count(&conn), 1);
+
+        let kinds: Vec<BackupKind> = list_backups(dir.path()).unwrap().iter().map(|b| b.kind).collect();
+        assert_eq!(kinds, vec![BackupKind::PreRestore, BackupKind::Manual]);
+    }
+
+    #[test]
+    fn test_backups_in_the_same_second_get_distinct_names() {
+        let dir = TempDir::new("backup");
+        let conn = Connection::open(dir.path().join("live.db")).unwrap();
+        create_tables(&conn).unwrap();
+
+        let first = create_backup(&conn, dir.path(), BackupKind::Manual, at(9)).unwrap();
+        let second = create_backup(&conn, dir.path(), BackupKind::Manual, at(9)).unwrap();
+        assert_eq!(first.file_name, "devfocus-manual-20250501-090000.db");
+        assert_eq!(second.file_name, "devfocus-manual-20250501-090000_2.db");
+        assert_eq!(second.created_at, first.created_at);
+        assert_eq!(list_backups(dir.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_backups() {
+        let dir = TempDir::new("backup");
+        let mut conn = Connection::open(dir.path().join("live.db")).unwrap();
+        create_tables(&conn).unwrap();
+
+        let garbage = dir.path().join("devfocus-manual-20250501-090000.db");
+        std::fs::write(&garbage, b"definitely not sqlite").unwrap();
+        assert!(restore_backup(&mut conn, &garbage, dir.path(), None, at(10)).is_err());
+
+        let newer = dir.path().join("newer.db");
+        let other = Connection::open(&newer).unwrap();
+        create_tables(&other).unwrap();
+        other.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        drop(other);
+        let error = validate_backup(&newer, None).unwrap_err();
+        assert!(error.contains("newer"), "{}", error);
+
+        // Nothing was replaced, so no pre-restore backup was taken
+        assert!(list_backups(dir.path())
+            .unwrap()
+            .iter()
+            .all(|b| b.kind != BackupKind::PreRestore));
+    }
+
+    #[test]
+    fn test_scheduled_backups_respect_interval_and_retention() {
+        let dir = TempDir::new("backup");
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let schedule = BackupSchedule {
+            enabled: true,
+            interval_hours: 2,
+            retention: 2,
+        };
+
+        assert!(run_scheduled_backup(&conn, dir.path(), &schedule, at(8)).unwrap().is_some());
+        assert!(run_scheduled_backup(&conn, dir.path(), &schedule, at(9)).unwrap().is_none());
+        assert!(run_scheduled_backup(&conn, dir.path(), &schedule, at(10)).unwrap().is_some());
+        create_backup(&conn, dir.path(), BackupKind::Manual, at(11)).unwrap();
+        assert!(run_scheduled_backup(&conn, dir.path(), &schedule, at(12)).unwrap().is_some());
+
+        let names: Vec<String> = list_backups(dir.path()).unwrap().into_iter().map(|b| b.file_name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "devfocus-auto-20250501-120000.db",
+                "devfocus-manual-20250501-110000.db",
+                "devfocus-auto-20250501-100000.db",
+            ]
+        );
+    }
+}
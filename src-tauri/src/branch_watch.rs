@@ -0,0 +1,445 @@
+use crate::git;
+use crate::models::*;
+use crate::profiles::active_profile_id;
+use crate::timer;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Long-lived branches that never get a task of their own
+const BASE_BRANCHES: [&str; 5] = ["main", "master", "develop", "development", "trunk"];
+
+/// Prefixes dropped from branch names when deriving a task title
+const BRANCH_PREFIXES: [&str; 7] = ["feature", "feat", "bugfix", "fix", "hotfix", "chore", "refactor"];
+
+/// Branch checked out according to the contents of a `HEAD` file; None when detached
+pub fn parse_head(content: &str) -> Option<String> {
+    content
+        .trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(str::to_string)
+}
+
+/// `HEAD` of the work tree at `repo_path`, following the `gitdir:` file used by linked worktrees
+fn head_file(repo_path: &Path) -> Result<PathBuf, String> {
+    let dot_git = repo_path.join(".git");
+    if dot_git.is_dir() {
+        return Ok(dot_git.join("HEAD"));
+    }
+
+    let content = std::fs::read_to_string(&dot_git)
+        .map_err(|e| format!("{} is not a git work tree: {}", repo_path.display(), e))?;
+    let git_dir = content
+        .trim()
+        .strip_prefix("gitdir: ")
+        .ok_or_else(|| format!("Unrecognized .git file in {}", repo_path.display()))?;
+    Ok(repo_path.join(git_dir).join("HEAD"))
+}
+
+pub fn read_current_branch(repo_path: &Path) -> Result<Option<String>, String> {
+    let path = head_file(repo_path)?;
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(parse_head(&content))
+}
+
+/// `feature/ABC-123-login-form` → `ABC-123: Login form`
+pub fn task_title_for_branch(branch: &str) -> String {
+    let mut name = branch;
+    if let Some((prefix, rest)) = branch.split_once('/') {
+        if BRANCH_PREFIXES.contains(&prefix.to_ascii_lowercase().as_str()) {
+            name = rest;
+        }
+    }
+
+    let words: Vec<&str> = name
+        .split(['-', '_', '/'])
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    // A leading ticket key such as ABC-123
+    let (ticket, words) = match words.as_slice() {
+        [project, number, rest @ ..]
+            if project.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                && project.starts_with(|c: char| c.is_ascii_uppercase())
+                && number.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            (Some(format!("{}-{}", project, number)), rest)
+        }
+        _ => (None, words.as_slice()),
+    };
+
+    let mut description = words.join(" ");
+    if let Some(first) = description.chars().next() {
+        description.replace_range(..first.len_utf8(), &first.to_uppercase().to_string());
+    }
+
+    match (ticket, description.is_empty()) {
+        (Some(ticket), true) => ticket,
+        (Some(ticket), false) => format!("{}: {}", ticket, description),
+        (None, false) => description,
+        (None, true) => branch.to_string(),
+    }
+}
+
+fn watcher_from_row(row: &rusqlite::Row) -> SqlResult<BranchWatcher> {
+    Ok(BranchWatcher {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        switch_timer: row.get(2)?,
+        last_branch: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// Start watching a repository; its current branch is recorded without creating a task
+pub fn add_watcher(conn: &Connection, path: &Path, switch_timer: bool) -> Result<BranchWatcher, String> {
+    let root = git::repository_root(path)?;
+    let watcher = BranchWatcher {
+        id: Uuid::new_v4().to_string(),
+        last_branch: read_current_branch(Path::new(&root))?,
+        path: root,
+        switch_timer,
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    conn.execute(
+        "INSERT INTO branch_watchers (id, path, switch_timer, last_branch, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![&watcher.id, &watcher.path, watcher.switch_timer, &watcher.last_branch, &watcher.created_at],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("{} is already watched", watcher.path)
+        }
+        e => e.to_string(),
+    })?;
+
+    Ok(watcher)
+}
+
+pub fn list_watchers(conn: &Connection) -> Result<Vec<BranchWatcher>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, path, switch_timer, last_branch, created_at FROM branch_watchers ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+    let watchers = stmt
+        .query_map([], watcher_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(watchers)
+}
+
+pub fn set_switch_timer(conn: &Connection, watcher_id: &str, switch_timer: bool) -> Result<BranchWatcher, String> {
+    conn.execute(
+        "UPDATE branch_watchers SET switch_timer = ?1 WHERE id = ?2",
+        params![switch_timer, watcher_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT id, path, switch_timer, last_branch, created_at FROM branch_watchers WHERE id = ?1",
+        [watcher_id],
+        watcher_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Branch watcher not found: {}", watcher_id))
+}
+
+/// The active profile's task for a branch, creating it (and linking the repository for commit
+/// scanning) if needed. Returns the task id and whether it was created.
+fn find_or_create_task(conn: &Connection, watcher: &BranchWatcher, branch: &str) -> Result<(String, bool), String> {
+    let profile_id = active_profile_id(conn)?;
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT task_id FROM branch_tasks WHERE watcher_id = ?1 AND branch = ?2 AND profile_id = ?3",
+            params![&watcher.id, branch, &profile_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(task_id) = existing {
+        return Ok((task_id, false));
+    }
+
+    let task = timer::create_task(
+        conn,
+        task_title_for_branch(branch),
+        Some(format!("Branch {} in {}", branch, watcher.path)),
+        None,
+    )?;
+    conn.execute(
+        "INSERT INTO branch_tasks (id, watcher_id, branch, task_id, profile_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![Uuid::new_v4().to_string(), &watcher.id, branch, &task.id, &profile_id, &task.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR IGNORE INTO git_repositories (id, task_id, path, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![Uuid::new_v4().to_string(), &task.id, &watcher.path, &task.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok((task.id, true))
+}
+
+/// Pause sessions running on other tasks and run the task's most recent unfinished subtask,
+/// creating one named after the branch if it has none. Returns the subtask now running.
+fn switch_timer_to(conn: &Connection, task_id: &str, branch: &str, now: DateTime<Utc>) -> Result<String, String> {
+    let sessions = timer::list_active_sessions(conn, now)?;
+    for session in sessions.iter().filter(|s| s.running && s.task_id != task_id) {
+        timer::pause_subtask(conn, &session.subtask_id, session.tracked_seconds)?;
+    }
+
+    if let Some(session) = sessions.iter().find(|s| s.running && s.task_id == task_id) {
+        return Ok(session.subtask_id.clone());
+    }
+
+    let subtask_id: Option<String> = conn
+        .query_row(
+            "SELECT id FROM subtasks WHERE task_id = ?1 AND status != 'done'
+             ORDER BY updated_at DESC, id LIMIT 1",
+            [task_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let subtask_id = match subtask_id {
+        Some(id) => id,
+        None => timer::create_subtask(conn, task_id.to_string(), branch.to_string(), None, None)?.id,
+    };
+
+    if timer::active_session(conn, &subtask_id)?.is_some() {
+        timer::resume_subtask(conn, &subtask_id)?;
+    } else {
+        timer::start_subtask(conn, &subtask_id)?;
+    }
+    Ok(subtask_id)
+}
+
+/// React to a checkout of `branch` in the watched repository
+pub fn handle_branch_change(
+    conn: &Connection,
+    watcher: &BranchWatcher,
+    branch: &str,
+    now: DateTime<Utc>,
+) -> Result<BranchSwitch, String> {
+    let (task_id, task_created) = find_or_create_task(conn, watcher, branch)?;
+    let running_subtask_id = if watcher.switch_timer {
+        Some(switch_timer_to(conn, &task_id, branch, now)?)
+    } else {
+        None
+    };
+
+    Ok(BranchSwitch {
+        watcher_id: watcher.id.clone(),
+        branch: branch.to_string(),
+        task_id,
+        task_created,
+        running_subtask_id,
+    })
+}
+
+pub type WatcherPollResult = (BranchWatcher, Result<BranchSwitch, String>);
+
+/// Record a checkout and create or switch to its task. Runs in its own transaction so a
+/// failure leaves `last_branch` unchanged and the change is retried on the next poll.
+fn apply_branch_change(
+    conn: &Connection,
+    watcher: &BranchWatcher,
+    branch: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<BranchSwitch>, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let switch = if BASE_BRANCHES.contains(&branch) {
+        None
+    } else {
+        Some(handle_branch_change(&tx, watcher, branch, now)?)
+    };
+    tx.execute(
+        "UPDATE branch_watchers SET last_branch = ?1 WHERE id = ?2",
+        params![branch, &watcher.id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(switch)
+}
+
+/// Check every watched repository for a branch change since the last poll. Unreadable
+/// repositories are skipped and a failed switch is reported per watcher, so one bad
+/// repository doesn't stop the others.
+pub fn poll_watchers(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<WatcherPollResult>, String> {
+    let mut results = Vec::new();
+
+    for watcher in list_watchers(conn)? {
+        let Ok(Some(branch)) = read_current_branch(Path::new(&watcher.path)) else {
+            continue;
+        };
+        if watcher.last_branch.as_deref() == Some(branch.as_str()) {
+            continue;
+        }
+
+        match apply_branch_change(conn, &watcher, &branch, now) {
+            Ok(Some(switch)) => results.push((watcher, Ok(switch))),
+            Ok(None) => {}
+            Err(e) => results.push((watcher, Err(e))),
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use crate::test_support::TempDir;
+
+    fn fake_repo(branch: &str) -> TempDir {
+        let dir = TempDir::new("branch");
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        checkout(dir.path(), branch);
+        dir
+    }
+
+    fn checkout(dir: &Path, branch: &str) {
+        std::fs::write(dir.join(".git").join("HEAD"), format!("ref: refs/heads/{}\n", branch)).unwrap();
+    }
+
+    fn watch(conn: &Connection, id: &str, dir: &Path, switch_timer: bool) {
+        conn.execute(
+            "INSERT INTO branch_watchers (id, path, switch_timer, last_branch, created_at)
+             VALUES (?1, ?2, ?3, 'main', '2025-08-01T08:00:00+00:00')",
+            params![id, dir.to_string_lossy(), switch_timer],
+        )
+        .unwrap();
+    }
+
+    fn switches(conn: &Connection) -> Vec<BranchSwitch> {
+        poll_watchers(conn, Utc::now())
+            .unwrap()
+            .into_iter()
+            .map(|(_, switch)| switch.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_branch_names() {
+        assert_eq!(parse_head("ref: refs/heads/feature/x\n"), Some("feature/x".to_string()));
+        assert_eq!(parse_head("3f2a9c0d4b1e\n"), None);
+
+        assert_eq!(task_title_for_branch("feature/ABC-123-login-form"), "ABC-123: Login form");
+        assert_eq!(task_title_for_branch("fix/ABC-9"), "ABC-9");
+        assert_eq!(task_title_for_branch("spike_dark_mode"), "Spike dark mode");
+        assert_eq!(task_title_for_branch("users/ana/cache"), "Users ana cache");
+    }
+
+    #[test]
+    fn test_checkout_creates_task_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let dir = fake_repo("main");
+        watch(&conn, "w1", dir.path(), false);
+
+        assert!(switches(&conn).is_empty());
+
+        checkout(dir.path(), "feature/ABC-123-login-form");
+        let switched = switches(&conn);
+        assert_eq!(switched.len(), 1);
+        assert!(switched[0].task_created);
+        assert_eq!(switched[0].running_subtask_id, None);
+        // Unchanged HEAD doesn't trigger again
+        assert!(switches(&conn).is_empty());
+
+        let (title, repositories): (String, i64) = conn
+            .query_row(
+                "SELECT t.title, (SELECT COUNT(*) FROM git_repositories r WHERE r.task_id = t.id)
+                 FROM tasks t WHERE t.id = ?1",
+                [&switched[0].task_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(title, "ABC-123: Login form");
+        assert_eq!(repositories, 1);
+
+        // Going back to main and returning reuses the task
+        checkout(dir.path(), "main");
+        assert!(switches(&conn).is_empty());
+        checkout(dir.path(), "feature/ABC-123-login-form");
+        let again = switches(&conn);
+        assert_eq!(again[0].task_id, switched[0].task_id);
+        assert!(!again[0].task_created);
+
+        // Another profile gets its own task for the same branch
+        let other = crate::profiles::create_profile(&conn, "Side project").unwrap();
+        crate::profiles::switch_profile(&conn, &other.id).unwrap();
+        checkout(dir.path(), "main");
+        switches(&conn);
+        checkout(dir.path(), "feature/ABC-123-login-form");
+        let other_switch = switches(&conn).remove(0);
+        assert!(other_switch.task_created);
+        assert_ne!(other_switch.task_id, switched[0].task_id);
+    }
+
+    #[test]
+    fn test_switch_timer_pauses_other_tasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let dir = fake_repo("main");
+        watch(&conn, "w1", dir.path(), true);
+
+        let other = timer::create_task(&conn, "Support".to_string(), None, None).unwrap();
+        let other_subtask = timer::create_subtask(&conn, other.id, "Triage".to_string(), None, None).unwrap();
+        timer::start_subtask(&conn, &other_subtask.id).unwrap();
+
+        checkout(dir.path(), "feature/search");
+        let switch = switches(&conn).remove(0);
+        let running = switch.running_subtask_id.unwrap();
+
+        let sessions = timer::list_active_sessions(&conn, Utc::now()).unwrap();
+        assert_eq!(sessions.len(), 2);
+        for session in sessions {
+            assert_eq!(session.running, session.subtask_id == running);
+        }
+        let subtask_title: String = conn
+            .query_row("SELECT title FROM subtasks WHERE id = ?1", [&running], |row| row.get(0))
+            .unwrap();
+        assert_eq!(subtask_title, "feature/search");
+    }
+
+    #[test]
+    fn test_failed_switch_is_retried_without_blocking_other_watchers() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let broken = fake_repo("main");
+        let healthy = fake_repo("main");
+        watch(&conn, "w1", broken.path(), false);
+        watch(&conn, "w2", healthy.path(), false);
+        conn.execute_batch(
+            "CREATE TRIGGER fail_branch BEFORE INSERT ON branch_tasks WHEN NEW.branch = 'feature/broken'
+             BEGIN SELECT RAISE(ABORT, 'boom'); END;",
+        )
+        .unwrap();
+
+        checkout(broken.path(), "feature/broken");
+        checkout(healthy.path(), "feature/healthy");
+        let results = poll_watchers(&conn, Utc::now()).unwrap();
+        assert_eq!(results.len(), 2);
+        let result = |id: &str| &results.iter().find(|(watcher, _)| watcher.id == id).unwrap().1;
+        assert!(result("w1").as_ref().unwrap_err().contains("boom"));
+        assert_eq!(result("w2").as_ref().unwrap().branch, "feature/healthy");
+
+        // The failed switch left nothing behind and is retried
+        let (last_branch, tasks): (String, i64) = conn
+            .query_row(
+                "SELECT last_branch, (SELECT COUNT(*) FROM tasks) FROM branch_watchers WHERE id = 'w1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((last_branch.as_str(), tasks), ("main", 1));
+        conn.execute_batch("DROP TRIGGER fail_branch;").unwrap();
+        let retried = switches(&conn);
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].watcher_id, "w1");
+    }
+}
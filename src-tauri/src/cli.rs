@@ -0,0 +1,438 @@
+use crate::analytics::period_start;
+use crate::db;
+use crate::db_location;
+use crate::models::*;
+use crate::profiles;
+use crate::report;
+use crate::settings;
+use crate::timer;
+use chrono::{Duration, NaiveDateTime, Utc};
+use rusqlite::{params, Connection, Result as SqlResult};
+use std::collections::HashMap;
+
+/// Ids are shown by their first 8 characters; any unique prefix is accepted
+const SHORT_ID_LEN: usize = 8;
+
+const USAGE: &str = "Usage: devfocus <command>
+
+Commands:
+  task add <title> [--description <text>] [--estimate <minutes>]
+  task list [--status todo|in_progress|done]
+  subtask add <task> <title> [--category <name>] [--estimate <minutes>]
+  start <subtask>
+  pause [<subtask>]
+  resume [<subtask>]
+  done [<subtask>]
+  status
+  report (--week | --from <YYYY-MM-DD> --to <YYYY-MM-DD>) [--output <file>]
+
+Options:
+  --db <path>  use the database at <path> instead of the configured one
+
+Tasks and subtasks can be referred to by any unique prefix of their id.
+pause, resume and done default to the only session they apply to.";
+
+/// Entry point of the `devfocus` binary; returns the process exit code
+pub fn main() -> i32 {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = db_location::take_db_flag(&mut args)
+        .and_then(|flag| {
+            if let Some(path) = flag {
+                db_location::set_flag_path(path);
+            }
+            db::init_db().map_err(|e| format!("Failed to open database: {}", e))
+        })
+        .and_then(|conn| run(&conn, &args, settings::local_now(&conn)));
+
+    match result {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("devfocus: {}", e);
+            1
+        }
+    }
+}
+
+struct ParsedArgs {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl ParsedArgs {
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn has_flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn minutes(&self, name: &str) -> Result<Option<i64>, String> {
+        self.option(name)
+            .map(|value| match value.parse::<i64>() {
+                Ok(minutes) if minutes > 0 => Ok(minutes * 60),
+                _ => Err(format!("--{} must be a positive number of minutes", name)),
+            })
+            .transpose()
+    }
+}
+
+/// Split arguments into positionals, `--name value` options and boolean `--flag`s
+fn parse_args(args: &[String], boolean_flags: &[&str]) -> Result<ParsedArgs, String> {
+    let mut parsed = ParsedArgs {
+        positional: Vec::new(),
+        options: HashMap::new(),
+        flags: Vec::new(),
+    };
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.strip_prefix("--") {
+            Some(name) if boolean_flags.contains(&name) => parsed.flags.push(name.to_string()),
+            Some(name) => {
+                let value = iter.next().ok_or_else(|| format!("Missing value for --{}", name))?;
+                parsed.options.insert(name.to_string(), value.clone());
+            }
+            None => parsed.positional.push(arg.clone()),
+        }
+    }
+    Ok(parsed)
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(SHORT_ID_LEN)]
+}
+
+fn format_duration(seconds: i64) -> String {
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+/// Resolve a full id or unique id prefix in `table`
+fn resolve_id(conn: &Connection, table: &str, reference: &str) -> Result<String, String> {
+    // Only the active profile's tasks and subtasks can be addressed
+    let sql = match table {
+        "subtasks" => {
+            "SELECT s.id FROM subtasks s JOIN tasks t ON t.id = s.task_id
+             WHERE substr(s.id, 1, length(?1)) = ?1 AND t.profile_id = ?2 LIMIT 2"
+        }
+        _ => "SELECT id FROM tasks WHERE substr(id, 1, length(?1)) = ?1 AND profile_id = ?2 LIMIT 2",
+    };
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let matches = stmt
+        .query_map(params![reference, profiles::active_profile_id(conn)?], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+
+    let kind = table.trim_end_matches('s');
+    match matches.as_slice() {
+        [id] => Ok(id.clone()),
+        [] => Err(format!("No {} matches '{}'", kind, reference)),
+        _ => Err(format!("'{}' matches more than one {}; use a longer prefix", reference, kind)),
+    }
+}
+
+/// The active session a pause/resume/done applies to
+fn select_session(
+    conn: &Connection,
+    reference: Option<&String>,
+    running: Option<bool>,
+) -> Result<ActiveSession, String> {
+    let subtask_id = reference.map(|r| resolve_id(conn, "subtasks", r)).transpose()?;
+    let mut sessions: Vec<ActiveSession> = timer::list_active_sessions(conn, Utc::now())?
+        .into_iter()
+        .filter(|session| subtask_id.as_ref().map_or(true, |id| &session.subtask_id == id))
+        .collect();
+
+    if let Some(id) = &subtask_id {
+        if sessions.is_empty() {
+            return Err(format!("Subtask {} has no active session", short_id(id)));
+        }
+    }
+    sessions.retain(|session| running.map_or(true, |running| session.running == running));
+
+    match sessions.len() {
+        1 => Ok(sessions.remove(0)),
+        0 => Err(match running {
+            Some(true) => "No running session".to_string(),
+            Some(false) => "No paused session".to_string(),
+            None => "No active session".to_string(),
+        }),
+        _ => Err("More than one active session; pass a subtask id".to_string()),
+    }
+}
+
+fn task_command(conn: &Connection, args: &[String]) -> Result<String, String> {
+    let parsed = parse_args(args.get(1..).unwrap_or_default(), &[])?;
+
+    match args.first().map(String::as_str) {
+        Some("add") => {
+            let title = parsed.positional.join(" ");
+            if title.trim().is_empty() {
+                return Err("Usage: devfocus task add <title>".to_string());
+            }
+            let task = timer::create_task(
+                conn,
+                title,
+                parsed.option("description").map(str::to_string),
+                parsed.minutes("estimate")?,
+            )?;
+            Ok(format!("Created task {} {}", short_id(&task.id), task.title))
+        }
+        Some("list") => {
+            let status = parsed.option("status");
+            let mut stmt = conn
+                .prepare(
+                    "SELECT t.id, t.title, t.status, s.id, s.title, s.status
+                     FROM tasks t
+                     LEFT JOIN subtasks s ON s.task_id = t.id
+                     WHERE (?1 IS NULL OR t.status = ?1) AND t.profile_id = ?2
+                     ORDER BY t.created_at, t.id, s.created_at, s.id",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![status, profiles::active_profile_id(conn)?], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                    ))
+                })
+                .map_err(|e| e.to_string())?
+                .collect::<SqlResult<Vec<_>>>()
+                .map_err(|e| e.to_string())?;
+
+            let mut lines = Vec::new();
+            let mut current_task: Option<String> = None;
+            for (task_id, task_title, task_status, subtask_id, subtask_title, subtask_status) in rows {
+                if current_task.as_ref() != Some(&task_id) {
+                    lines.push(format!("{} [{}] {}", short_id(&task_id), task_status, task_title));
+                    current_task = Some(task_id);
+                }
+                if let (Some(id), Some(title), Some(status)) = (subtask_id, subtask_title, subtask_status) {
+                    lines.push(format!("  {} [{}] {}", short_id(&id), status, title));
+                }
+            }
+            Ok(lines.join("\n"))
+        }
+        _ => Err("Usage: devfocus task (add|list) ...".to_string()),
+    }
+}
+
+fn subtask_command(conn: &Connection, args: &[String]) -> Result<String, String> {
+    if args.first().map(String::as_str) != Some("add") {
+        return Err("Usage: devfocus subtask add <task> <title>".to_string());
+    }
+    let parsed = parse_args(&args[1..], &[])?;
+    let (task, title) = match parsed.positional.split_first() {
+        Some((task, title)) if !title.is_empty() => (task, title.join(" ")),
+        _ => return Err("Usage: devfocus subtask add <task> <title>".to_string()),
+    };
+
+    let task_id = resolve_id(conn, "tasks", task)?;
+    let category_id = match parsed.option("category") {
+        Some(name) => Some(
+            conn.query_row("SELECT id FROM categories WHERE name = ?1 COLLATE NOCASE", [name], |row| row.get(0))
+                .map_err(|_| format!("No category named '{}'", name))?,
+        ),
+        None => None,
+    };
+
+    let subtask = timer::create_subtask(conn, task_id, title, category_id, parsed.minutes("estimate")?)?;
+    Ok(format!("Created subtask {} {}", short_id(&subtask.id), subtask.title))
+}
+
+fn status_command(conn: &Connection) -> Result<String, String> {
+    let sessions = timer::list_active_sessions(conn, Utc::now())?;
+    if sessions.is_empty() {
+        return Ok("No active sessions".to_string());
+    }
+
+    Ok(sessions
+        .iter()
+        .map(|session| {
+            format!(
+                "{} {} {} — {} ({})",
+                if session.running { "running" } else { "paused " },
+                format_duration(session.tracked_seconds),
+                session.task_title,
+                session.subtask_title,
+                short_id(&session.subtask_id),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn report_command(conn: &Connection, args: &[String], now: NaiveDateTime) -> Result<String, String> {
+    let parsed = parse_args(args, &["week"])?;
+
+    let (start_date, end_date) = if parsed.has_flag("week") {
+        let monday = period_start(now.date(), Granularity::Week);
+        (
+            monday.format("%Y-%m-%d").to_string(),
+            (monday + Duration::days(6)).format("%Y-%m-%d").to_string(),
+        )
+    } else {
+        match (parsed.option("from"), parsed.option("to")) {
+            (Some(from), Some(to)) => (from.to_string(), to.to_string()),
+            _ => return Err("Usage: devfocus report (--week | --from <date> --to <date>)".to_string()),
+        }
+    };
+
+    let markdown = report::render_markdown(&report::build_report(conn, &start_date, &end_date, now)?);
+    match parsed.option("output") {
+        Some(path) => {
+            std::fs::write(path, &markdown).map_err(|e| format!("Failed to write report to {}: {}", path, e))?;
+            Ok(format!("Report written to {}", path))
+        }
+        None => Ok(markdown),
+    }
+}
+
+/// Run one CLI invocation against `conn` and return what it prints
+pub fn run(conn: &Connection, args: &[String], now: NaiveDateTime) -> Result<String, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(USAGE.to_string());
+    };
+
+    match command.as_str() {
+        "task" => task_command(conn, rest),
+        "subtask" => subtask_command(conn, rest),
+        "start" => {
+            let reference = rest.first().ok_or_else(|| "Usage: devfocus start <subtask>".to_string())?;
+            let subtask_id = resolve_id(conn, "subtasks", reference)?;
+            if timer::active_session(conn, &subtask_id)?.is_some() {
+                return Err(format!("Subtask {} is already started; use resume", short_id(&subtask_id)));
+            }
+            timer::start_subtask(conn, &subtask_id)?;
+            Ok(format!("Started {}", short_id(&subtask_id)))
+        }
+        "pause" => {
+            let session = select_session(conn, rest.first(), Some(true))?;
+            timer::pause_subtask(conn, &session.subtask_id, session.tracked_seconds)?;
+            Ok(format!("Paused {} at {}", session.subtask_title, format_duration(session.tracked_seconds)))
+        }
+        "resume" => {
+            let session = select_session(conn, rest.first(), Some(false))?;
+            timer::resume_subtask(conn, &session.subtask_id)?;
+            Ok(format!("Resumed {}", session.subtask_title))
+        }
+        "done" => {
+            let session = select_session(conn, rest.first(), None)?;
+            let completion = timer::complete_subtask(conn, &session.subtask_id, session.tracked_seconds)?;
+            Ok(format!(
+                "Completed {} in {}: +{} points, +{} XP",
+                completion.subtask.title,
+                format_duration(completion.time_spent_seconds),
+                completion.points_earned,
+                completion.xp_gained,
+            ))
+        }
+        "status" => status_command(conn),
+        "report" => report_command(conn, rest, now),
+        "help" | "--help" | "-h" => Ok(USAGE.to_string()),
+        other => Err(format!("Unknown command '{}'\n\n{}", other, USAGE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn now() -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2025, 6, 12)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap()
+    }
+
+    fn cli(conn: &Connection, line: &str) -> Result<String, String> {
+        let args: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        run(conn, &args, now())
+    }
+
+    fn created_id(output: &str) -> String {
+        output.split_whitespace().nth(2).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_timer_workflow() {
+        let conn = setup();
+        let task = created_id(&cli(&conn, "task add Release notes --estimate 30").unwrap());
+        let subtask = created_id(&cli(&conn, &format!("subtask add {} Draft changelog --category backend", task)).unwrap());
+
+        assert_eq!(cli(&conn, "status").unwrap(), "No active sessions");
+        assert!(cli(&conn, "pause").is_err());
+
+        cli(&conn, &format!("start {}", subtask)).unwrap();
+        assert!(cli(&conn, &format!("start {}", subtask)).is_err());
+        assert!(cli(&conn, "status").unwrap().starts_with("running 00:00:0"));
+
+        cli(&conn, "pause").unwrap();
+        assert!(cli(&conn, "status").unwrap().starts_with("paused "));
+        cli(&conn, "resume").unwrap();
+
+        let done = cli(&conn, "done").unwrap();
+        assert!(done.starts_with("Completed Draft changelog"), "{}", done);
+        assert_eq!(cli(&conn, "status").unwrap(), "No active sessions");
+
+        let list = cli(&conn, "task list").unwrap();
+        assert!(list.contains("[todo] Release notes"));
+        assert!(list.contains(&format!("  {} [done] Draft changelog", subtask)));
+    }
+
+    #[test]
+    fn test_resolves_unique_prefixes() {
+        let conn = setup();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at)
+             VALUES ('abc123', 'One', 'todo', '2025-06-01T08:00:00+00:00', '2025-06-01T08:00:00+00:00'),
+                    ('abd456', 'Two', 'todo', '2025-06-01T08:00:00+00:00', '2025-06-01T08:00:00+00:00');",
+        )
+        .unwrap();
+
+        assert_eq!(resolve_id(&conn, "tasks", "abc").unwrap(), "abc123");
+        assert!(resolve_id(&conn, "tasks", "ab").unwrap_err().contains("more than one task"));
+        assert!(resolve_id(&conn, "tasks", "zz").unwrap_err().contains("No task matches"));
+        // Tasks of other profiles are out of reach
+        let other = profiles::create_profile(&conn, "Side project").unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at, profile_id)
+             VALUES ('abe789', 'Three', 'todo', '2025-06-01T08:00:00+00:00', '2025-06-01T08:00:00+00:00', ?1)",
+            [&other.id],
+        )
+        .unwrap();
+        assert!(resolve_id(&conn, "tasks", "abe").unwrap_err().contains("No task matches"));
+        assert!(cli(&conn, "subtask add abd Write --category nope").unwrap_err().contains("No category"));
+    }
+
+    #[test]
+    fn test_report_week_and_usage_errors() {
+        let conn = setup();
+        let report = cli(&conn, "report --week").unwrap();
+        assert!(report.contains("**Período:** 09/06/2025 - 15/06/2025"));
+
+        assert!(cli(&conn, "report").is_err());
+        assert!(cli(&conn, "task add --estimate").is_err());
+        assert!(cli(&conn, "frobnicate").unwrap_err().starts_with("Unknown command"));
+        assert!(cli(&conn, "").unwrap().starts_with("Usage: devfocus"));
+    }
+}
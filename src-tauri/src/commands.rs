@@ -1,170 +1,72 @@
+use crate::analytics;
+use crate::archive;
+use crate::backup;
+use crate::branch_watch;
+use crate::db;
+use crate::db_location;
+use crate::encryption;
+use crate::gamification::*;
+use crate::git;
+use crate::http_api::{self, ApiResponse, HttpApiConfig, HttpApiServer};
+use crate::ics;
+use crate::importers;
+use crate::leaderboard;
 use crate::models::*;
+use crate::outbox;
+use crate::plugins;
+use crate::prediction::{self, DurationPredictor};
+use crate::profiles;
+use crate::report;
+use crate::settings;
+use crate::team_sync;
+use crate::timer;
+use crate::timesheet::{self, TimesheetFilter};
+use crate::todo_sync;
 use chrono::{Duration, NaiveDate, Utc};
-use rusqlite::{params, Result as SqlResult};
+use rusqlite::{params, OptionalExtension, Result as SqlResult};
 use std::collections::HashMap;
-use std::sync::Mutex;
-use tauri::Manager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{Emitter, Manager};
 use tauri::State;
 use uuid::Uuid;
 
 pub struct AppState {
     pub db: Mutex<rusqlite::Connection>,
+    // Passphrase of the encrypted database; None while it is plaintext or locked
+    pub db_key: Mutex<Option<String>>,
+    // Encrypted database waiting for `unlock_database`; `db` is an empty placeholder until then
+    pub db_locked: AtomicBool,
+    // Running local HTTP API server, if enabled
+    pub http_api: Mutex<Option<HttpApiServer>>,
 }
 
-// ============================================================================
-// HELPER FUNCTIONS
-// ============================================================================
-
-/// Calculate level from total XP
-/// Formula: level = floor(sqrt(xp / 100)) + 1
-fn calculate_level(xp: i64) -> i64 {
-    if xp <= 0 {
-        return 1;
-    }
-    let level = ((xp as f64 / 100.0).sqrt().floor() as i64) + 1;
-    level.max(1)
-}
-
-/// Calculate XP required for a specific level
-/// Formula: xp = (level - 1)^2 * 100
-fn calculate_xp_for_level(level: i64) -> i64 {
-    ((level - 1).pow(2)) * 100
-}
-
-/// Get XP needed for next level
-fn get_xp_for_next_level(current_level: i64) -> i64 {
-    calculate_xp_for_level(current_level + 1)
-}
-
-/// Calculate progress percentage to next level
-fn calculate_progress_percentage(total_xp: i64, current_level: i64) -> f64 {
-    let xp_current_level = calculate_xp_for_level(current_level);
-    let xp_next_level = get_xp_for_next_level(current_level);
-    let xp_in_current_level = total_xp - xp_current_level;
-    let xp_needed_for_next = xp_next_level - xp_current_level;
-
-    if xp_needed_for_next <= 0 {
-        return 100.0;
-    }
-
-    ((xp_in_current_level as f64 / xp_needed_for_next as f64) * 100.0).min(100.0).max(0.0)
-}
-
-/// Calculate global level from total XP (uses different formula than categories)
-/// Formula: level = floor(sqrt(xp / 500)) + 1
-fn calculate_global_level(xp: i64) -> i64 {
-    if xp <= 0 {
-        return 1;
-    }
-    let level = ((xp as f64 / 500.0).sqrt().floor() as i64) + 1;
-    level.max(1)
-}
-
-/// Calculate XP required for a specific global level
-/// Formula: xp = (level - 1)^2 * 500
-fn calculate_xp_for_global_level(level: i64) -> i64 {
-    ((level - 1).pow(2)) * 500
-}
-
-/// Get XP needed for next global level
-fn get_xp_for_next_global_level(current_level: i64) -> i64 {
-    calculate_xp_for_global_level(current_level + 1)
-}
-
-/// Get title based on global level
-fn get_title_for_level(level: i64) -> String {
-    match level {
-        1..=4 => "novice".to_string(),
-        5..=9 => "junior".to_string(),
-        10..=14 => "mid".to_string(),
-        15..=19 => "senior".to_string(),
-        20..=24 => "expert".to_string(),
-        25..=29 => "master".to_string(),
-        _ => "legend".to_string(),
+impl AppState {
+    /// The database connection; fails while an encrypted database waits for its passphrase
+    pub fn conn(&self) -> Result<MutexGuard<'_, rusqlite::Connection>, String> {
+        if self.db_locked.load(Ordering::SeqCst) {
+            return Err("The database is locked".to_string());
+        }
+        self.db.lock().map_err(|e| e.to_string())
     }
 }
 
-/// Update user profile with latest global level and XP
-fn update_user_profile_level(conn: &rusqlite::Connection) -> Result<(), String> {
-    let now = Utc::now().to_rfc3339();
-
-    // Calculate total XP from all categories
-    let total_xp: i64 = conn
-        .query_row(
-            "SELECT COALESCE(SUM(total_xp), 0) FROM category_experience",
-            [],
-            |row| row.get(0),
-        )
-        .map_err(|e| format!("Failed to calculate total XP: {}", e))?;
-
-    // Calculate global level
-    let level = calculate_global_level(total_xp);
-    let title = get_title_for_level(level);
-
-    // Update user profile
-    conn.execute(
-        "UPDATE user_profile SET level = ?1, total_xp = ?2, current_title = ?3, updated_at = ?4",
-        params![level, total_xp, &title, &now],
-    )
-    .map_err(|e| format!("Failed to update user profile: {}", e))?;
-
-    Ok(())
-}
-
-/// Calculate streak bonus percentage
-/// +5% per week, maximum 50%
-fn calculate_streak_bonus(streak_days: i64) -> f64 {
-    let weeks = (streak_days / 7) as f64;
-    let bonus_percentage = (weeks * 0.05).min(0.50);
-    bonus_percentage
-}
-
-/// Update user streak when completing a subtask
-fn update_user_streak(conn: &rusqlite::Connection) -> Result<i64, String> {
-    let today = chrono::Utc::now().date_naive().format("%Y-%m-%d").to_string();
-
-    // Get current profile data
-    let (current_streak, longest_streak, last_work_date): (i64, i64, Option<String>) = conn
-        .query_row(
-            "SELECT current_streak, longest_streak, last_work_date FROM user_profile LIMIT 1",
-            [],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-        )
-        .map_err(|e| format!("Failed to get streak data: {}", e))?;
-
-    let new_streak = if let Some(last_date) = last_work_date {
-        if last_date == today {
-            // Same day, maintain streak
-            current_streak
-        } else if let Ok(last_parsed) = chrono::NaiveDate::parse_from_str(&last_date, "%Y-%m-%d") {
-            let today_parsed = chrono::NaiveDate::parse_from_str(&today, "%Y-%m-%d").unwrap();
-            let diff = today_parsed.signed_duration_since(last_parsed).num_days();
-
-            if diff == 1 {
-                // Consecutive day
-                current_streak + 1
-            } else {
-                // Streak broken
-                1
-            }
-        } else {
-            1
-        }
-    } else {
-        // First time
-        1
-    };
-
-    let new_longest = new_streak.max(longest_streak);
-
-    // Update in database
-    conn.execute(
-        "UPDATE user_profile SET current_streak = ?1, longest_streak = ?2, last_work_date = ?3, updated_at = ?4",
-        params![new_streak, new_longest, &today, &chrono::Utc::now().to_rfc3339()],
-    )
-    .map_err(|e| format!("Failed to update streak: {}", e))?;
-
-    Ok(new_streak)
+/// Start the threads that work on the database. Called at launch, or by `unlock_database`
+/// when an encrypted database starts locked.
+pub fn start_background_workers(app: &tauri::AppHandle) {
+    // Take automatic backups in the background
+    start_backup_scheduler(app.clone());
+
+    // Refresh windows when the devfocus CLI changes the database
+    start_external_change_watcher(app.clone());
+    start_branch_watcher(app.clone());
+    start_todo_sync(app.clone());
+    start_outbox_worker(app.clone());
+    start_plugin_notifier(app.clone());
+    start_team_sync(app.clone());
+
+    // Serve the local HTTP API if the user enabled it
+    start_http_api_if_enabled(app);
 }
 
 // ============================================================================
@@ -175,36 +77,11 @@ fn update_user_streak(conn: &rusqlite::Connection) -> Result<i64, String> {
 pub fn create_task(
     title: String,
     description: Option<String>,
+    estimate_seconds: Option<i64>,
     state: State<AppState>,
 ) -> Result<Task, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-    let task = Task {
-        id: Uuid::new_v4().to_string(),
-        title,
-        description,
-        status: TaskStatus::Todo,
-        created_at: now.clone(),
-        updated_at: now,
-        completed_at: None,
-    };
-
-    conn.execute(
-        "INSERT INTO tasks (id, title, description, status, created_at, updated_at, completed_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        params![
-            &task.id,
-            &task.title,
-            &task.description,
-            task.status.as_str(),
-            &task.created_at,
-            &task.updated_at,
-            &task.completed_at,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(task)
+    let conn = state.conn()?;
+    timer::create_task(&conn, title, description, estimate_seconds)
 }
 
 #[tauri::command]
@@ -212,17 +89,18 @@ pub fn list_tasks_with_active_subtasks(
     status_filter: Option<String>,
     state: State<AppState>,
 ) -> Result<Vec<TaskWithActiveSubtask>, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
+    let profile_id = profiles::active_profile_id(&conn)?;
 
     let query = if let Some(status) = status_filter {
-        format!("SELECT id, title, description, status, created_at, updated_at, completed_at FROM tasks WHERE status = '{}' ORDER BY created_at DESC", status)
+        format!("SELECT id, title, description, status, created_at, updated_at, completed_at FROM tasks WHERE profile_id = ?1 AND status = '{}' ORDER BY created_at DESC", status)
     } else {
-        "SELECT id, title, description, status, created_at, updated_at, completed_at FROM tasks ORDER BY created_at DESC".to_string()
+        "SELECT id, title, description, status, created_at, updated_at, completed_at FROM tasks WHERE profile_id = ?1 ORDER BY created_at DESC".to_string()
     };
 
     let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
     let task_rows = stmt
-        .query_map([], |row| {
+        .query_map([&profile_id], |row| {
             Ok((
                 row.get::<_, String>(0)?, // id
                 row.get::<_, String>(1)?, // title
@@ -290,83 +168,8 @@ pub fn get_task_with_subtasks(
     task_id: String,
     state: State<AppState>,
 ) -> Result<TaskWithSubtasks, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-
-    // Get task
-    let mut stmt = conn
-        .prepare("SELECT id, title, description, status, created_at, updated_at, completed_at FROM tasks WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-
-    let task = stmt
-        .query_row([&task_id], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                status: TaskStatus::from_str(&row.get::<_, String>(3)?),
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                completed_at: row.get(6)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-
-    // Get subtasks with total time (excluding active sessions)
-    let mut stmt = conn
-        .prepare(
-            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
-                    COALESCE(SUM(CASE WHEN ts.ended_at IS NOT NULL THEN ts.duration_seconds ELSE 0 END), 0) as total_time,
-                    s.category_id, c.id, c.name, c.color, c.created_at
-             FROM subtasks s
-             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
-             LEFT JOIN categories c ON s.category_id = c.id
-             WHERE s.task_id = ?1
-             GROUP BY s.id
-             ORDER BY s.created_at"
-        )
-        .map_err(|e| e.to_string())?;
-
-    let subtasks = stmt
-        .query_map([&task_id], |row| {
-            let category_id: Option<String> = row.get(8)?;
-            let category = if row.get::<_, Option<String>>(9)?.is_some() {
-                Some(Category {
-                    id: row.get(9)?,
-                    name: row.get(10)?,
-                    color: row.get(11)?,
-                    created_at: row.get(12)?,
-                })
-            } else {
-                None
-            };
-
-            Ok(Subtask {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                title: row.get(2)?,
-                status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                completed_at: row.get(6)?,
-                total_time_seconds: Some(row.get(7)?),
-                category_id,
-                category,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<SqlResult<Vec<Subtask>>>()
-        .map_err(|e| e.to_string())?;
-
-    Ok(TaskWithSubtasks {
-        id: task.id,
-        title: task.title,
-        description: task.description,
-        status: task.status,
-        created_at: task.created_at,
-        updated_at: task.updated_at,
-        completed_at: task.completed_at,
-        subtasks,
-    })
+    let conn = state.conn()?;
+    timer::get_task_with_subtasks(&conn, &task_id)
 }
 
 #[tauri::command]
@@ -375,41 +178,24 @@ pub fn update_task_status(
     status: String,
     state: State<AppState>,
 ) -> Result<Task, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-    let completed_at = if status == "done" { Some(now.clone()) } else { None };
-
-    conn.execute(
-        "UPDATE tasks SET status = ?1, updated_at = ?2, completed_at = ?3 WHERE id = ?4",
-        params![&status, &now, &completed_at, &task_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Query task in same scope
-    let mut stmt = conn
-        .prepare("SELECT id, title, description, status, created_at, updated_at, completed_at FROM tasks WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
+    timer::update_task_status(&conn, &task_id, &status)
+}
 
-    stmt.query_row([&task_id], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            status: TaskStatus::from_str(&row.get::<_, String>(3)?),
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            completed_at: row.get(6)?,
-        })
-    })
-    .map_err(|e| e.to_string())
+#[tauri::command]
+pub fn update_task_estimate(
+    task_id: String,
+    estimate_seconds: Option<i64>,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let conn = state.conn()?;
+    timer::update_task_estimate(&conn, &task_id, estimate_seconds)
 }
 
 #[tauri::command]
 pub fn delete_task(task_id: String, state: State<AppState>) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM tasks WHERE id = ?1", [&task_id])
-        .map_err(|e| e.to_string())?;
-    Ok(())
+    let conn = state.conn()?;
+    timer::delete_task(&conn, &task_id)
 }
 
 
@@ -423,7 +209,7 @@ pub fn create_category(
     color: String,
     state: State<AppState>,
 ) -> Result<Category, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
     let now = Utc::now().to_rfc3339();
 
     let category = Category {
@@ -439,21 +225,15 @@ pub fn create_category(
     )
     .map_err(|e| e.to_string())?;
 
-    // Create initial experience entry
-    let exp_id = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT INTO category_experience (id, category_id, total_xp, level, updated_at)
-         VALUES (?1, ?2, 0, 1, ?3)",
-        params![exp_id, &category.id, &now],
-    )
-    .map_err(|e| e.to_string())?;
+    // Create initial experience entries for every profile
+    db::ensure_category_experience(&conn).map_err(|e| e.to_string())?;
 
     Ok(category)
 }
 
 #[tauri::command]
 pub fn list_categories(state: State<AppState>) -> Result<Vec<Category>, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
 
     let mut stmt = conn
         .prepare("SELECT id, name, color, created_at FROM categories ORDER BY name")
@@ -480,13 +260,14 @@ pub fn get_category_experience(
     category_id: String,
     state: State<AppState>,
 ) -> Result<CategoryExperience, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
+    let profile_id = profiles::active_profile_id(&conn)?;
 
     let mut stmt = conn
-        .prepare("SELECT id, category_id, total_xp, level, updated_at FROM category_experience WHERE category_id = ?1")
+        .prepare("SELECT id, category_id, total_xp, level, updated_at FROM category_experience WHERE profile_id = ?1 AND category_id = ?2")
         .map_err(|e| e.to_string())?;
 
-    stmt.query_row([&category_id], |row| {
+    stmt.query_row(params![&profile_id, &category_id], |row| {
         Ok(CategoryExperience {
             id: row.get(0)?,
             category_id: row.get(1)?,
@@ -500,19 +281,20 @@ pub fn get_category_experience(
 
 #[tauri::command]
 pub fn get_all_category_stats(state: State<AppState>) -> Result<Vec<CategoryStats>, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
+    let profile_id = profiles::active_profile_id(&conn)?;
 
     let mut stmt = conn
         .prepare(
             "SELECT c.id, c.name, c.color, c.created_at, ce.total_xp, ce.level
              FROM categories c
-             LEFT JOIN category_experience ce ON c.id = ce.category_id
+             LEFT JOIN category_experience ce ON c.id = ce.category_id AND ce.profile_id = ?1
              ORDER BY ce.level DESC, ce.total_xp DESC, c.name"
         )
         .map_err(|e| e.to_string())?;
 
     let stats = stmt
-        .query_map([], |row| {
+        .query_map([&profile_id], |row| {
             let category = Category {
                 id: row.get(0)?,
                 name: row.get(1)?,
@@ -548,81 +330,79 @@ pub fn create_subtask(
     task_id: String,
     title: String,
     category_id: Option<String>,
+    estimate_seconds: Option<i64>,
     state: State<AppState>,
 ) -> Result<Subtask, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-
-    let subtask_id = Uuid::new_v4().to_string();
+    let conn = state.conn()?;
+    timer::create_subtask(&conn, task_id, title, category_id, estimate_seconds)
+}
 
-    conn.execute(
-        "INSERT INTO subtasks (id, task_id, title, status, created_at, updated_at, completed_at, category_id)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![
-            &subtask_id,
-            &task_id,
-            &title,
-            SubtaskStatus::Todo.as_str(),
-            &now,
-            &now,
-            None::<String>,
-            &category_id,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn update_subtask_estimate(
+    subtask_id: String,
+    estimate_seconds: Option<i64>,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let conn = state.conn()?;
+    timer::update_subtask_estimate(&conn, &subtask_id, estimate_seconds)
+}
 
-    // Fetch category if exists
-    let category = if let Some(ref cat_id) = category_id {
-        conn.query_row(
-            "SELECT id, name, color, created_at FROM categories WHERE id = ?1",
-            [cat_id],
-            |row| {
-                Ok(Category {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    color: row.get(2)?,
-                    created_at: row.get(3)?,
-                })
-            },
-        )
-        .ok()
-    } else {
-        None
-    };
+#[tauri::command]
+pub fn predict_subtask_duration(
+    title: String,
+    category_id: Option<String>,
+    state: State<AppState>,
+) -> Result<Option<DurationPrediction>, String> {
+    let conn = state.conn()?;
+    let samples = prediction::load_samples(&conn)?;
 
-    Ok(Subtask {
-        id: subtask_id,
-        task_id,
-        title,
-        status: SubtaskStatus::Todo,
-        created_at: now.clone(),
-        updated_at: now,
-        completed_at: None,
-        total_time_seconds: Some(0),
-        category_id,
-        category,
-    })
+    Ok(DurationPredictor::new(samples).predict(&title, category_id.as_deref()))
 }
 
 #[tauri::command]
 pub fn delete_subtask(subtask_id: String, state: State<AppState>) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM subtasks WHERE id = ?1", [&subtask_id])
-        .map_err(|e| e.to_string())?;
-    Ok(())
+    let conn = state.conn()?;
+    timer::delete_subtask(&conn, &subtask_id)
 }
 
 // ============================================================================
 // STATE MANAGEMENT COMMANDS
 // ============================================================================
 
+/// How often the app checks whether another process (the `devfocus` CLI) wrote to the database
+const EXTERNAL_CHANGE_POLL_MILLISECONDS: u64 = 2000;
+
+/// Emit `timer-changed` when another connection commits, so windows refresh state changed from the CLI.
+/// `PRAGMA data_version` only changes for commits made by other connections.
+pub fn start_external_change_watcher(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_version: Option<i64> = None;
+        loop {
+            let version = {
+                let state = app.state::<AppState>();
+                let conn = match state.conn() {
+                    Ok(conn) => conn,
+                    Err(_) => break,
+                };
+                conn.query_row("PRAGMA data_version", [], |row| row.get::<_, i64>(0)).ok()
+            };
+
+            if version.is_some() && last_version.is_some() && version != last_version {
+                let _ = app.emit("timer-changed", ());
+            }
+            last_version = version.or(last_version);
+            std::thread::sleep(std::time::Duration::from_millis(EXTERNAL_CHANGE_POLL_MILLISECONDS));
+        }
+    });
+}
+
 #[tauri::command]
 pub fn update_session_duration(
     subtask_id: String,
     duration_seconds: i64,
     state: State<AppState>,
 ) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
     conn.execute(
         "UPDATE time_sessions SET duration_seconds = ?1
          WHERE subtask_id = ?2 AND ended_at IS NULL",
@@ -637,43 +417,8 @@ pub fn start_subtask(
     subtask_id: String,
     state: State<AppState>,
 ) -> Result<TimeSession, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-
-    // Update subtask status
-    conn.execute(
-        "UPDATE subtasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
-        params!["in_progress", &now, &subtask_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Create time session
-    let session = TimeSession {
-        id: Uuid::new_v4().to_string(),
-        subtask_id: subtask_id.clone(),
-        started_at: now,
-        paused_at: None,
-        resumed_at: None,
-        ended_at: None,
-        duration_seconds: 0,
-    };
-
-    conn.execute(
-        "INSERT INTO time_sessions (id, subtask_id, started_at, paused_at, resumed_at, ended_at, duration_seconds)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        params![
-            &session.id,
-            &session.subtask_id,
-            &session.started_at,
-            &session.paused_at,
-            &session.resumed_at,
-            &session.ended_at,
-            &session.duration_seconds,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(session)
+    let conn = state.conn()?;
+    timer::start_subtask(&conn, &subtask_id)
 }
 
 #[tauri::command]
@@ -682,41 +427,8 @@ pub fn pause_subtask(
     duration_seconds: i64,
     state: State<AppState>,
 ) -> Result<TimeSession, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-
-    // Update subtask status
-    conn.execute(
-        "UPDATE subtasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
-        params!["paused", &now, &subtask_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Update time session
-    conn.execute(
-        "UPDATE time_sessions SET paused_at = ?1, duration_seconds = ?2
-         WHERE subtask_id = ?3 AND ended_at IS NULL",
-        params![&now, duration_seconds, &subtask_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Get active session in same scope
-    let mut stmt = conn
-        .prepare("SELECT id, subtask_id, started_at, paused_at, resumed_at, ended_at, duration_seconds FROM time_sessions WHERE subtask_id = ?1 AND ended_at IS NULL")
-        .map_err(|e| e.to_string())?;
-
-    stmt.query_row([&subtask_id], |row| {
-        Ok(TimeSession {
-            id: row.get(0)?,
-            subtask_id: row.get(1)?,
-            started_at: row.get(2)?,
-            paused_at: row.get(3)?,
-            resumed_at: row.get(4)?,
-            ended_at: row.get(5)?,
-            duration_seconds: row.get(6)?,
-        })
-    })
-    .map_err(|e| e.to_string())
+    let conn = state.conn()?;
+    timer::pause_subtask(&conn, &subtask_id, duration_seconds)
 }
 
 #[tauri::command]
@@ -724,41 +436,8 @@ pub fn resume_subtask(
     subtask_id: String,
     state: State<AppState>,
 ) -> Result<TimeSession, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-
-    // Update subtask status
-    conn.execute(
-        "UPDATE subtasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
-        params!["in_progress", &now, &subtask_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Update time session
-    conn.execute(
-        "UPDATE time_sessions SET resumed_at = ?1
-         WHERE subtask_id = ?2 AND ended_at IS NULL",
-        params![&now, &subtask_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Get active session in same scope
-    let mut stmt = conn
-        .prepare("SELECT id, subtask_id, started_at, paused_at, resumed_at, ended_at, duration_seconds FROM time_sessions WHERE subtask_id = ?1 AND ended_at IS NULL")
-        .map_err(|e| e.to_string())?;
-
-    stmt.query_row([&subtask_id], |row| {
-        Ok(TimeSession {
-            id: row.get(0)?,
-            subtask_id: row.get(1)?,
-            started_at: row.get(2)?,
-            paused_at: row.get(3)?,
-            resumed_at: row.get(4)?,
-            ended_at: row.get(5)?,
-            duration_seconds: row.get(6)?,
-        })
-    })
-    .map_err(|e| e.to_string())
+    let conn = state.conn()?;
+    timer::resume_subtask(&conn, &subtask_id)
 }
 
 #[tauri::command]
@@ -767,158 +446,43 @@ pub fn complete_subtask(
     duration_seconds: i64,
     state: State<AppState>,
 ) -> Result<SubtaskCompletion, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-
-    // Get category_id before updating subtask
-    let category_id: Option<String> = conn
-        .query_row(
-            "SELECT category_id FROM subtasks WHERE id = ?1",
-            [&subtask_id],
-            |row| row.get(0),
-        )
-        .ok();
-
-    // Update streak when completing subtask
-    let current_streak = update_user_streak(&conn)?;
-
-    // Update subtask status
-    conn.execute(
-        "UPDATE subtasks SET status = ?1, updated_at = ?2, completed_at = ?3 WHERE id = ?4",
-        params!["done", &now, &now, &subtask_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Update time session
-    conn.execute(
-        "UPDATE time_sessions SET ended_at = ?1, duration_seconds = ?2
-         WHERE subtask_id = ?3 AND ended_at IS NULL",
-        params![&now, duration_seconds, &subtask_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
+    timer::complete_subtask(&conn, &subtask_id, duration_seconds)
+}
 
-    // Calculate points
-    let base_points = 10;
-    let efficiency_bonus = if duration_seconds < 1500 { 5 } else { 0 }; // < 25 min
-    let points = base_points + efficiency_bonus;
-
-    // Calculate XP with streak bonus
-    let base_xp = duration_seconds;
-    let streak_bonus = calculate_streak_bonus(current_streak);
-    let bonus_xp = (base_xp as f64 * streak_bonus) as i64;
-    let xp_gained = base_xp + bonus_xp;
-
-    // Update category experience if category exists
-    let category = if let Some(cat_id) = category_id {
-        // Get current XP
-        let current_xp: i64 = conn
-            .query_row(
-                "SELECT total_xp FROM category_experience WHERE category_id = ?1",
-                [&cat_id],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
+// ============================================================================
+// METRICS COMMANDS
+// ============================================================================
 
-        let new_xp = current_xp + xp_gained;
-        let new_level = calculate_level(new_xp);
+#[tauri::command]
+pub fn get_task_metrics(
+    task_id: String,
+    state: State<AppState>,
+) -> Result<TaskMetrics, String> {
+    let conn = state.conn()?;
 
-        // Update experience
-        conn.execute(
-            "UPDATE category_experience SET total_xp = ?1, level = ?2, updated_at = ?3
-             WHERE category_id = ?4",
-            params![new_xp, new_level, &now, &cat_id],
+    // Get task
+    let mut stmt = conn
+        .prepare(
+            "SELECT title, completed_at, estimate_seconds, COALESCE(bonus_points, 0) FROM tasks
+             WHERE id = ?1 AND profile_id = ?2",
         )
         .map_err(|e| e.to_string())?;
 
-        // Update global level after category XP change
-        update_user_profile_level(&conn)?;
-
-        // Fetch category info
-        conn.query_row(
-            "SELECT id, name, color, created_at FROM categories WHERE id = ?1",
-            [&cat_id],
-            |row| {
-                Ok(Category {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    color: row.get(2)?,
-                    created_at: row.get(3)?,
-                })
-            },
-        )
-        .ok()
-    } else {
-        None
-    };
-
-    // Get subtask with total time and category
-    let mut stmt = conn
-        .prepare(
-            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
-                    COALESCE(SUM(ts.duration_seconds), 0) as total_time, s.category_id
-             FROM subtasks s
-             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
-             WHERE s.id = ?1
-             GROUP BY s.id"
-        )
-        .map_err(|e| e.to_string())?;
-
-    let subtask = stmt
-        .query_row([&subtask_id], |row| {
-            Ok(Subtask {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                title: row.get(2)?,
-                status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                completed_at: row.get(6)?,
-                total_time_seconds: Some(row.get(7)?),
-                category_id: row.get(8)?,
-                category: category.clone(),
-            })
-        })
-        .map_err(|e| e.to_string())?;
-
-    Ok(SubtaskCompletion {
-        subtask,
-        points_earned: points,
-        time_spent_seconds: duration_seconds,
-        xp_gained,
-        category,
-        current_streak,
-        streak_bonus_percentage: streak_bonus,
-        bonus_xp,
-    })
-}
-
-
-// ============================================================================
-// METRICS COMMANDS
-// ============================================================================
-
-#[tauri::command]
-pub fn get_task_metrics(
-    task_id: String,
-    state: State<AppState>,
-) -> Result<TaskMetrics, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
-
-    // Get task
-    let mut stmt = conn
-        .prepare("SELECT title, completed_at FROM tasks WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-
-    let (task_title, completed_at): (String, Option<String>) = stmt
-        .query_row([&task_id], |row| Ok((row.get(0)?, row.get(1)?)))
-        .map_err(|e| e.to_string())?;
+    let (task_title, completed_at, estimate_seconds, bonus_points): (String, Option<String>, Option<i64>, i64) = stmt
+        .query_row(params![&task_id, profiles::active_profile_id(&conn)?], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task not found: {}", task_id))?;
 
     // Get subtasks with time
     let mut stmt = conn
         .prepare(
             "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
                     COALESCE(SUM(ts.duration_seconds), 0) as total_time,
-                    s.category_id, c.id, c.name, c.color, c.created_at
+                    s.category_id, c.id, c.name, c.color, c.created_at, s.estimate_seconds
              FROM subtasks s
              LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
              LEFT JOIN categories c ON s.category_id = c.id
@@ -954,6 +518,7 @@ pub fn get_task_metrics(
                     total_time_seconds: Some(row.get(7)?),
                     category_id,
                     category,
+                    estimate_seconds: row.get(13)?,
                 },
                 total_time_seconds: row.get(7)?,
             })
@@ -966,24 +531,16 @@ pub fn get_task_metrics(
     let subtasks_completed = subtasks_with_time.iter().filter(|s| matches!(s.subtask.status, SubtaskStatus::Done)).count() as i64;
     let total_time_seconds: i64 = subtasks_with_time.iter().map(|s| s.total_time_seconds).sum();
 
-    // Calculate points
-    let mut total_points: i64 = 0;
-    let mut efficient_count = 0;
-
-    for subtask in &subtasks_with_time {
-        if matches!(subtask.subtask.status, SubtaskStatus::Done) {
-            total_points += 10; // Base points
-            if subtask.total_time_seconds < 1500 {
-                total_points += 5; // Efficiency bonus
-                efficient_count += 1;
-            }
-        }
-    }
-
-    // Complexity bonus
-    if subtasks_total >= 5 {
-        total_points += 20;
-    }
+    // Points and efficiency stored when each subtask was completed, plus the complexity bonus
+    let (subtask_points, efficient_count): (i64, i64) = conn
+        .query_row(
+            "SELECT COALESCE(SUM(points), 0), COALESCE(SUM(efficient), 0)
+             FROM subtasks WHERE task_id = ?1 AND status = 'done'",
+            [&task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let total_points = subtask_points + bonus_points;
 
     let average_time_per_subtask = if subtasks_completed > 0 {
         total_time_seconds as f64 / subtasks_completed as f64
@@ -1001,6 +558,7 @@ pub fn get_task_metrics(
         task_id,
         task_title,
         total_time_seconds,
+        estimate_seconds,
         total_points,
         subtasks_completed,
         subtasks_total,
@@ -1013,30 +571,35 @@ pub fn get_task_metrics(
 
 #[tauri::command]
 pub fn get_general_metrics(state: State<AppState>) -> Result<GeneralMetrics, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
+    let profile_id = profiles::active_profile_id(&conn)?;
 
     #[derive(Debug)]
     struct CompletedSubtaskData {
         completed_at: Option<String>,
         total_time: i64,
+        points: i64,
     }
 
     let mut stmt = conn
         .prepare(
             "SELECT s.completed_at,
-                    COALESCE(SUM(ts.duration_seconds), 0) as total_time
+                    COALESCE(SUM(ts.duration_seconds), 0) as total_time,
+                    COALESCE(s.points, 0)
              FROM subtasks s
+             JOIN tasks t ON s.task_id = t.id
              LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
-             WHERE s.status = 'done'
+             WHERE s.status = 'done' AND t.profile_id = ?1
              GROUP BY s.id",
         )
         .map_err(|e| e.to_string())?;
 
     let completed_subtasks = stmt
-        .query_map([], |row| {
+        .query_map([&profile_id], |row| {
             Ok(CompletedSubtaskData {
                 completed_at: row.get(0)?,
                 total_time: row.get(1)?,
+                points: row.get(2)?,
             })
         })
         .map_err(|e| e.to_string())?
@@ -1049,77 +612,60 @@ pub fn get_general_metrics(state: State<AppState>) -> Result<GeneralMetrics, Str
         subtasks: i64,
     }
 
+    // Days follow the user's timezone
+    let settings = settings::load_settings(&conn)?;
     let mut daily_totals: HashMap<NaiveDate, DailyAggregate> = HashMap::new();
     let mut total_points: i64 = 0;
     let mut total_duration: i64 = 0;
 
     for data in &completed_subtasks {
         let duration = data.total_time;
-        let base_points: i64 = 10;
-        let efficiency_bonus: i64 = if duration < 1_500 { 5 } else { 0 };
-        let points = base_points + efficiency_bonus;
+        let points = data.points;
 
         total_points += points;
         total_duration += duration;
 
-        if let Some(completed_at) = &data.completed_at {
-            if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(completed_at) {
-                let date = parsed.with_timezone(&Utc).date_naive();
-                let entry = daily_totals.entry(date).or_insert_with(DailyAggregate::default);
-                entry.points += points;
-                entry.subtasks += 1;
-            }
+        if let Some(date) = data.completed_at.as_deref().and_then(|at| analytics::timestamp_date(at, &settings)) {
+            let entry = daily_totals.entry(date).or_default();
+            entry.points += points;
+            entry.subtasks += 1;
         }
     }
 
     let mut task_stmt = conn
         .prepare(
-            "SELECT t.completed_at,
-                    COUNT(s.id) as total_subtasks,
-                    SUM(CASE WHEN s.status = 'done' THEN 1 ELSE 0 END) as done_subtasks
-             FROM tasks t
-             LEFT JOIN subtasks s ON t.id = s.task_id
-             GROUP BY t.id",
+            "SELECT completed_at, bonus_points FROM tasks
+             WHERE profile_id = ?1 AND bonus_points IS NOT NULL",
         )
         .map_err(|e| e.to_string())?;
 
     let task_rows = task_stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, Option<String>>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, Option<i64>>(2)?,
-            ))
-        })
+        .query_map([&profile_id], |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, i64>(1)?)))
         .map_err(|e| e.to_string())?
-        .collect::<SqlResult<Vec<(Option<String>, i64, Option<i64>)>>>()
+        .collect::<SqlResult<Vec<(Option<String>, i64)>>>()
         .map_err(|e| e.to_string())?;
 
-    for (completed_at, total_subtasks, done_subtasks_opt) in task_rows {
-        let done_subtasks = done_subtasks_opt.unwrap_or(0);
-        if total_subtasks >= 5 && total_subtasks > 0 && done_subtasks == total_subtasks {
-            total_points += 20;
-            if let Some(completed_at) = completed_at {
-                if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(&completed_at) {
-                    let date = parsed.with_timezone(&Utc).date_naive();
-                    let entry = daily_totals.entry(date).or_insert_with(DailyAggregate::default);
-                    entry.points += 20;
-                }
-            }
+    for (completed_at, bonus_points) in task_rows {
+        total_points += bonus_points;
+        if let Some(date) = completed_at.as_deref().and_then(|at| analytics::timestamp_date(at, &settings)) {
+            daily_totals.entry(date).or_default().points += bonus_points;
         }
     }
 
     let total_tasks_completed: i64 = conn
-        .query_row("SELECT COUNT(*) FROM tasks WHERE status = 'done'", [], |row| row.get(0))
+        .query_row(
+            "SELECT COUNT(*) FROM tasks WHERE status = 'done' AND profile_id = ?1",
+            [&profile_id],
+            |row| row.get(0),
+        )
         .unwrap_or(0);
 
     let total_subtasks_completed = completed_subtasks.len() as i64;
 
-    let today = Utc::now().date_naive();
+    let today = settings.local_time(Utc::now()).date();
     let start_date = today - Duration::days(6);
 
     let mut points_last_7_days = Vec::new();
-    let mut points_this_week: i64 = 0;
     let mut best_day: Option<DailyPoints> = None;
 
     for offset in 0..7 {
@@ -1132,8 +678,6 @@ pub fn get_general_metrics(state: State<AppState>) -> Result<GeneralMetrics, Str
             subtasks_completed: entry.subtasks,
         };
 
-        points_this_week += entry.points;
-
         if entry.points > 0 {
             match &best_day {
                 Some(current_best) if current_best.points >= entry.points => {}
@@ -1146,6 +690,14 @@ pub fn get_general_metrics(state: State<AppState>) -> Result<GeneralMetrics, Str
 
     let points_today = daily_totals.get(&today).map(|d| d.points).unwrap_or(0);
 
+    // Calendar week (ISO, starting Monday) rather than the rolling 7-day window
+    let week_start = analytics::period_start(today, Granularity::Week);
+    let points_this_week: i64 = daily_totals
+        .iter()
+        .filter(|(date, _)| **date >= week_start && **date <= today)
+        .map(|(_, entry)| entry.points)
+        .sum();
+
     let average_completion_time_seconds = if total_subtasks_completed > 0 {
         total_duration as f64 / total_subtasks_completed as f64
     } else {
@@ -1169,226 +721,1065 @@ pub fn get_subtask_with_session(
     subtask_id: String,
     state: State<AppState>,
 ) -> Result<(Subtask, Option<TimeSession>), String> {
+    let conn = state.conn()?;
+    timer::get_subtask_with_session(&conn, &subtask_id)
+}
+
+#[tauri::command]
+pub fn get_task_with_subtasks_and_sessions(
+    task_id: String,
+    state: State<AppState>,
+) -> Result<TaskWithSubtasksAndSessions, String> {
+    let conn = state.conn()?;
+    timer::get_task_with_subtasks_and_sessions(&conn, &task_id)
+}
+
+// ============================================================================
+// ANALYTICS COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_xp_timeline(
+    start_date: String,
+    end_date: String,
+    granularity: String,
+    state: State<AppState>,
+) -> Result<XpTimeline, String> {
+    let conn = state.conn()?;
+    let granularity = Granularity::from_str(&granularity)
+        .ok_or_else(|| format!("Invalid granularity: {}", granularity))?;
+
+    analytics::xp_timeline(&conn, &start_date, &end_date, granularity)
+}
+
+#[tauri::command]
+pub fn get_metrics_for_range(
+    start_date: String,
+    end_date: String,
+    granularity: String,
+    state: State<AppState>,
+) -> Result<RangeMetrics, String> {
+    let conn = state.conn()?;
+    let granularity = Granularity::from_str(&granularity)
+        .ok_or_else(|| format!("Invalid granularity: {}", granularity))?;
+
+    analytics::metrics_for_range(&conn, &start_date, &end_date, granularity)
+}
+
+#[tauri::command]
+pub fn compare_periods(
+    current_start: String,
+    current_end: String,
+    previous_start: String,
+    previous_end: String,
+    state: State<AppState>,
+) -> Result<PeriodComparison, String> {
+    let conn = state.conn()?;
+    analytics::compare_periods(&conn, &current_start, &current_end, &previous_start, &previous_end)
+}
+
+#[tauri::command]
+pub fn get_estimation_accuracy(
+    start_date: String,
+    end_date: String,
+    granularity: String,
+    state: State<AppState>,
+) -> Result<EstimationAccuracy, String> {
+    let conn = state.conn()?;
+    let granularity = Granularity::from_str(&granularity)
+        .ok_or_else(|| format!("Invalid granularity: {}", granularity))?;
+
+    analytics::estimation_accuracy(&conn, &start_date, &end_date, granularity)
+}
+
+#[tauri::command]
+pub fn get_focus_heatmap(
+    category_id: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    state: State<AppState>,
+) -> Result<FocusHeatmap, String> {
+    let conn = state.conn()?;
+    analytics::focus_heatmap(
+        &conn,
+        category_id.as_deref(),
+        start_date.as_deref(),
+        end_date.as_deref(),
+    )
+}
+
+/// Render the Markdown productivity report for a date range and write it to `output_path`
+#[tauri::command]
+pub fn export_report(
+    start_date: String,
+    end_date: String,
+    output_path: String,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let conn = state.conn()?;
+    let report = report::build_report(&conn, &start_date, &end_date, settings::local_now(&conn))?;
+    let markdown = report::render_markdown(&report);
+
+    std::fs::write(&output_path, &markdown)
+        .map_err(|e| format!("Failed to write report to {}: {}", output_path, e))?;
+
+    Ok(markdown)
+}
+
+/// Export time sessions as CSV or JSON timesheet rows, optionally written to `output_path`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn export_timesheet(
+    format: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    category_id: Option<String>,
+    task_id: Option<String>,
+    rounding_minutes: Option<i64>,
+    daily_totals: bool,
+    output_path: Option<String>,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let format = TimesheetFormat::from_str(&format).ok_or_else(|| format!("Invalid timesheet format: {}", format))?;
+    let conn = state.conn()?;
+
+    let filter = TimesheetFilter {
+        start_date,
+        end_date,
+        category_id,
+        task_id,
+    };
+    let timesheet = timesheet::build_timesheet(&conn, &filter, rounding_minutes, daily_totals)?;
+
+    let content = match format {
+        TimesheetFormat::Csv => timesheet::to_csv(&timesheet)?,
+        TimesheetFormat::Json => serde_json::to_string_pretty(&timesheet).map_err(|e| e.to_string())?,
+    };
+
+    if let Some(path) = output_path {
+        std::fs::write(&path, &content).map_err(|e| format!("Failed to write timesheet to {}: {}", path, e))?;
+    }
+
+    Ok(content)
+}
+
+/// Write the focus sessions of a date range as an iCalendar file, one event per running interval
+#[tauri::command]
+pub fn export_ics(
+    start_date: String,
+    end_date: String,
+    output_path: String,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let conn = state.conn()?;
+    let calendar = ics::export_ics(&conn, &start_date, &end_date, Utc::now())?;
+
+    std::fs::write(&output_path, &calendar)
+        .map_err(|e| format!("Failed to write calendar to {}: {}", output_path, e))?;
+
+    Ok(calendar)
+}
+
+// ============================================================================
+// BACKUP COMMANDS
+// ============================================================================
+
+/// How often the scheduler wakes up to check whether an automatic backup is due
+const BACKUP_CHECK_INTERVAL_SECONDS: u64 = 60 * 60;
+
+fn backups_dir() -> std::path::PathBuf {
+    backup::backups_dir(&db::get_db_path())
+}
+
+/// Check for a due automatic backup at startup and then periodically
+pub fn start_backup_scheduler(app: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        let state = app.state::<AppState>();
+        if let Ok(conn) = state.conn() {
+            let schedule = settings::load_settings(&conn).map(|settings| settings.backup).unwrap_or_default();
+            if let Err(e) = backup::run_scheduled_backup(&conn, &backups_dir(), &schedule, Utc::now()) {
+                eprintln!("Automatic backup failed: {}", e);
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_secs(BACKUP_CHECK_INTERVAL_SECONDS));
+    });
+}
+
+#[tauri::command]
+pub fn create_backup(state: State<AppState>) -> Result<BackupInfo, String> {
+    let conn = state.conn()?;
+    backup::create_backup(&conn, &backups_dir(), BackupKind::Manual, Utc::now())
+}
+
+#[tauri::command]
+pub fn list_backups() -> Result<Vec<BackupInfo>, String> {
+    backup::list_backups(&backups_dir())
+}
+
+/// Restore a backup given its file name in the backups folder or an absolute path.
+/// Returns the backup taken of the data that was replaced.
+#[tauri::command]
+pub fn restore_backup(path: String, state: State<AppState>) -> Result<BackupInfo, String> {
+    let dir = backups_dir();
+    let backup_path = if std::path::Path::new(&path).is_absolute() {
+        std::path::PathBuf::from(&path)
+    } else {
+        dir.join(&path)
+    };
+
+    let mut conn = state.conn()?;
+    let key = state.db_key.lock().map_err(|e| e.to_string())?.clone();
+    backup::restore_backup(&mut conn, &backup_path, &dir, key.as_deref(), Utc::now())
+}
+
+// ============================================================================
+// DATABASE LOCATION COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_database_location() -> DatabaseLocation {
+    db_location::current()
+}
+
+/// Move the database (with its key file and backups) to `target_path`, a file or an
+/// existing folder, and remember the new location for the next start
+#[tauri::command]
+pub fn move_database(target_path: String, state: State<AppState>) -> Result<DatabaseLocation, String> {
+    let location = db_location::current();
+    let set_by = match location.source {
+        DatabaseLocationSource::CliFlag => Some("the --db flag"),
+        DatabaseLocationSource::Env => Some(db_location::DB_PATH_ENV),
+        DatabaseLocationSource::Portable => Some("portable mode"),
+        DatabaseLocationSource::SettingsFile | DatabaseLocationSource::Default => None,
+    };
+    if let Some(set_by) = set_by {
+        return Err(format!("The database location is set by {}; change it there", set_by));
+    }
+
+    let mut conn = state.conn()?;
+    let key = state.db_key.lock().map_err(|e| e.to_string())?.clone();
+    let moved = db_location::move_database(
+        &mut conn,
+        std::path::Path::new(&target_path),
+        key.as_deref(),
+        std::path::Path::new(&location.location_file),
+    )?;
+    Ok(DatabaseLocation {
+        path: moved.path.to_string_lossy().to_string(),
+        warnings: moved.warnings,
+        ..db_location::current()
+    })
+}
+
+// ============================================================================
+// DATABASE ENCRYPTION COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_database_encryption_status(state: State<AppState>) -> Result<DatabaseEncryptionStatus, String> {
+    // Reads the placeholder while locked; the status only needs the file on disk then
     let conn = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(encryption::get_status(&conn, &db::get_db_path(), state.db_locked.load(Ordering::SeqCst)))
+}
 
-    // Get subtask with total time
-    let mut stmt = conn
-        .prepare(
-            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
-                    COALESCE(SUM(CASE WHEN ts.ended_at IS NOT NULL THEN ts.duration_seconds ELSE 0 END), 0) as total_time,
-                    s.category_id, c.id, c.name, c.color, c.created_at
-             FROM subtasks s
-             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
-             LEFT JOIN categories c ON s.category_id = c.id
-             WHERE s.id = ?1
-             GROUP BY s.id"
-        )
+/// Open the encrypted database with its passphrase, optionally saving it to the key
+/// file so later starts unlock on their own
+#[tauri::command]
+pub fn unlock_database(
+    passphrase: String,
+    remember: bool,
+    app: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<DatabaseEncryptionStatus, String> {
+    if !state.db_locked.load(Ordering::SeqCst) {
+        return Err("The database is not locked".to_string());
+    }
+
+    let db_path = db::get_db_path();
+    let unlocked = encryption::unlock(&db_path, &passphrase)?;
+    if remember {
+        encryption::save_key_file(&db_path, &passphrase)?;
+    }
+
+    let status = {
+        let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+        *conn = unlocked;
+        *state.db_key.lock().map_err(|e| e.to_string())? = Some(passphrase);
+        state.db_locked.store(false, Ordering::SeqCst);
+        encryption::get_status(&conn, &db_path, false)
+    };
+
+    start_background_workers(&app);
+    let _ = app.emit("database-unlocked", ());
+    let _ = app.emit("timer-changed", ());
+    Ok(status)
+}
+
+/// Encrypt the plaintext database in place
+#[tauri::command]
+pub fn encrypt_database(passphrase: String, remember: bool, state: State<AppState>) -> Result<DatabaseEncryptionStatus, String> {
+    let db_path = db::get_db_path();
+    let mut conn = state.conn()?;
+    encryption::encrypt_in_place(&mut conn, &passphrase)?;
+    if remember {
+        encryption::save_key_file(&db_path, &passphrase)?;
+    }
+    *state.db_key.lock().map_err(|e| e.to_string())? = Some(passphrase);
+    Ok(encryption::get_status(&conn, &db_path, false))
+}
+
+/// Change the passphrase; an existing key file is rewritten with the new one
+#[tauri::command]
+pub fn rekey_database(passphrase: String, state: State<AppState>) -> Result<DatabaseEncryptionStatus, String> {
+    let db_path = db::get_db_path();
+    let conn = state.conn()?;
+    encryption::rekey(&conn, &passphrase)?;
+    if encryption::key_file_path(&db_path).exists() {
+        encryption::save_key_file(&db_path, &passphrase)?;
+    }
+    *state.db_key.lock().map_err(|e| e.to_string())? = Some(passphrase);
+    Ok(encryption::get_status(&conn, &db_path, false))
+}
+
+/// Store the database in plaintext again and delete the key file
+#[tauri::command]
+pub fn decrypt_database(state: State<AppState>) -> Result<DatabaseEncryptionStatus, String> {
+    let db_path = db::get_db_path();
+    let mut conn = state.conn()?;
+    let mut key = state.db_key.lock().map_err(|e| e.to_string())?;
+    let current = key.clone().ok_or_else(|| "The database is not encrypted".to_string())?;
+    encryption::decrypt_in_place(&mut conn, &current)?;
+    *key = None;
+    encryption::remove_key_file(&db_path)?;
+    Ok(encryption::get_status(&conn, &db_path, false))
+}
+
+// ============================================================================
+// IMPORT / EXPORT COMMANDS
+// ============================================================================
+
+/// Write every task, subtask, category, session and the profile to a versioned JSON archive
+#[tauri::command]
+pub fn export_data(output_path: String, state: State<AppState>) -> Result<ExportSummary, String> {
+    let conn = state.conn()?;
+    let archive = archive::export_archive(&conn, &Utc::now().to_rfc3339())?;
+
+    let json = serde_json::to_string_pretty(&archive).map_err(|e| e.to_string())?;
+    std::fs::write(&output_path, json).map_err(|e| format!("Failed to write archive to {}: {}", output_path, e))?;
+
+    Ok(archive.summary(&output_path))
+}
+
+/// Import a JSON archive, replacing (after a safety backup) or merging into the current data
+#[tauri::command]
+pub fn import_data(input_path: String, mode: String, state: State<AppState>) -> Result<ImportSummary, String> {
+    let mode = ImportMode::from_str(&mode).ok_or_else(|| format!("Invalid import mode: {}", mode))?;
+    let json = std::fs::read_to_string(&input_path)
+        .map_err(|e| format!("Failed to read archive {}: {}", input_path, e))?;
+    let archive = archive::parse_archive(&json)?;
+
+    let mut conn = state.conn()?;
+    if mode == ImportMode::Replace {
+        backup::create_backup(&conn, &backups_dir(), BackupKind::PreRestore, Utc::now())?;
+    }
+
+    archive::import_archive(&mut conn, &archive, mode)
+}
+
+/// Import a Toggl/Clockify CSV export or a todo.txt file. With `dry_run` nothing is
+/// written and the report describes what would be created.
+#[tauri::command]
+pub fn import_from_tracker(
+    source: String,
+    input_path: String,
+    dry_run: bool,
+    state: State<AppState>,
+) -> Result<TrackerImportReport, String> {
+    let source = ImportSource::from_str(&source).ok_or_else(|| format!("Invalid import source: {}", source))?;
+    let content = std::fs::read_to_string(&input_path)
+        .map_err(|e| format!("Failed to read {}: {}", input_path, e))?;
+
+    let mut conn = state.conn()?;
+
+    // Exported times carry no zone; they are in the user's local time
+    let offset = settings::load_settings(&conn)?.utc_offset(Utc::now());
+    let parsed = importers::parse(source, &content, offset)?;
+
+    importers::import_entries(&mut conn, source, parsed, dry_run, Utc::now())
+}
+
+// ============================================================================
+// GIT INTEGRATION COMMANDS
+// ============================================================================
+
+/// Link a local git repository (any folder inside its work tree) to a task
+#[tauri::command]
+pub fn add_git_repository(task_id: String, path: String, state: State<AppState>) -> Result<GitRepository, String> {
+    let conn = state.conn()?;
+    git::add_repository(&conn, &task_id, std::path::Path::new(&path))
+}
+
+#[tauri::command]
+pub fn list_git_repositories(task_id: Option<String>, state: State<AppState>) -> Result<Vec<GitRepository>, String> {
+    let conn = state.conn()?;
+    git::list_repositories(&conn, task_id.as_deref())
+}
+
+/// Unlink a repository; its commit links are removed with it
+#[tauri::command]
+pub fn remove_git_repository(repository_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    conn.execute("DELETE FROM git_repositories WHERE id = ?1", [&repository_id])
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let subtask = stmt
-        .query_row([&subtask_id], |row| {
-            let category_id: Option<String> = row.get(8)?;
-            let category = if row.get::<_, Option<String>>(9)?.is_some() {
-                Some(Category {
-                    id: row.get(9)?,
-                    name: row.get(10)?,
-                    color: row.get(11)?,
-                    created_at: row.get(12)?,
-                })
-            } else {
-                None
-            };
+/// Attribute commits of the task's repositories (or of every task's) to subtasks.
+/// The database stays unlocked while git runs.
+#[tauri::command]
+pub fn scan_git_repositories(task_id: Option<String>, state: State<AppState>) -> Result<GitScanSummary, String> {
+    let targets = {
+        let conn = state.conn()?;
+        git::scan_targets(&conn, task_id.as_deref())?
+    };
+    let results = git::read_targets(targets);
+    let conn = state.conn()?;
+    git::store_scan_results(&conn, results, Utc::now())
+}
 
-            Ok(Subtask {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                title: row.get(2)?,
-                status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                completed_at: row.get(6)?,
-                total_time_seconds: Some(row.get(7)?),
-                category_id,
-                category,
-            })
-        })
+/// Watch a repository's checked-out branch and keep a task per feature branch
+#[tauri::command]
+pub fn add_branch_watcher(path: String, switch_timer: bool, state: State<AppState>) -> Result<BranchWatcher, String> {
+    let conn = state.conn()?;
+    branch_watch::add_watcher(&conn, std::path::Path::new(&path), switch_timer)
+}
+
+#[tauri::command]
+pub fn list_branch_watchers(state: State<AppState>) -> Result<Vec<BranchWatcher>, String> {
+    let conn = state.conn()?;
+    branch_watch::list_watchers(&conn)
+}
+
+#[tauri::command]
+pub fn set_branch_watcher_switch_timer(
+    watcher_id: String,
+    switch_timer: bool,
+    state: State<AppState>,
+) -> Result<BranchWatcher, String> {
+    let conn = state.conn()?;
+    branch_watch::set_switch_timer(&conn, &watcher_id, switch_timer)
+}
+
+/// Stop watching; tasks created from its branches are kept
+#[tauri::command]
+pub fn remove_branch_watcher(watcher_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    conn.execute("DELETE FROM branch_watchers WHERE id = ?1", [&watcher_id])
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+const BRANCH_WATCH_POLL_MILLISECONDS: u64 = 2000;
+
+/// Poll watched repositories' HEAD and emit `branch-switched` for each checkout acted on.
+/// A watcher whose switch keeps failing is retried every poll but reported once per error.
+pub fn start_branch_watcher(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut reported: HashMap<String, String> = HashMap::new();
+        loop {
+            let results = {
+                let state = app.state::<AppState>();
+                let conn = match state.conn() {
+                    Ok(conn) => conn,
+                    Err(_) => break,
+                };
+                branch_watch::poll_watchers(&conn, Utc::now())
+            };
+
+            let mut switched = false;
+            for (watcher, result) in results.unwrap_or_else(|e| {
+                eprintln!("Branch watcher failed: {}", e);
+                Vec::new()
+            }) {
+                match result {
+                    Ok(switch) => {
+                        reported.remove(&watcher.id);
+                        let _ = app.emit("branch-switched", &switch);
+                        switched = true;
+                    }
+                    Err(e) => {
+                        if reported.get(&watcher.id) != Some(&e) {
+                            eprintln!("Branch watcher failed for {}: {}", watcher.path, e);
+                            reported.insert(watcher.id, e);
+                        }
+                    }
+                }
+            }
+            if switched {
+                let _ = app.emit("timer-changed", ());
+            }
+            std::thread::sleep(std::time::Duration::from_millis(BRANCH_WATCH_POLL_MILLISECONDS));
+        }
+    });
+}
+
+// ============================================================================
+// TODO FILE SYNC COMMANDS
+// ============================================================================
+
+/// Keep a markdown checklist (e.g. a repo's TODO.md) in sync with a task's subtasks
+#[tauri::command]
+pub fn link_todo_file(task_id: String, path: String, state: State<AppState>) -> Result<TodoFile, String> {
+    let conn = state.conn()?;
+    todo_sync::link_todo_file(&conn, &task_id, std::path::Path::new(&path), Utc::now())
+}
 
-    // Get active session if exists
-    let mut session_stmt = conn
-        .prepare("SELECT id, subtask_id, started_at, paused_at, resumed_at, ended_at, duration_seconds FROM time_sessions WHERE subtask_id = ?1 AND ended_at IS NULL")
+#[tauri::command]
+pub fn list_todo_files(task_id: Option<String>, state: State<AppState>) -> Result<Vec<TodoFile>, String> {
+    let conn = state.conn()?;
+    todo_sync::list_todo_files(&conn, task_id.as_deref())
+}
+
+/// Stop syncing; the file and the subtasks are left as they are
+#[tauri::command]
+pub fn unlink_todo_file(file_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    conn.execute("DELETE FROM todo_files WHERE id = ?1", [&file_id])
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let session = session_stmt
-        .query_row([&subtask_id], |row| {
-            Ok(TimeSession {
-                id: row.get(0)?,
-                subtask_id: row.get(1)?,
-                started_at: row.get(2)?,
-                paused_at: row.get(3)?,
-                resumed_at: row.get(4)?,
-                ended_at: row.get(5)?,
-                duration_seconds: row.get(6)?,
-            })
-        })
-        .ok(); // Convert to None if no active session
+/// Sync now; `prefer` ("file" or "app") resolves the file's conflicts in favour of that side
+#[tauri::command]
+pub fn sync_todo_file(file_id: String, prefer: Option<String>, state: State<AppState>) -> Result<TodoSyncReport, String> {
+    let prefer = prefer
+        .map(|side| TodoSyncSide::from_str(&side).ok_or_else(|| format!("Invalid sync side: {}", side)))
+        .transpose()?;
+    let conn = state.conn()?;
+    todo_sync::sync_todo_file(&conn, &file_id, prefer, Utc::now())
+}
+
+const TODO_SYNC_POLL_MILLISECONDS: u64 = 5000;
+
+/// Sync linked files in the background; emits `timer-changed` when subtasks changed and
+/// `todo-conflicts` (the `TodoFile`) when a file's set of conflicts changes.
+/// A file that keeps failing is retried every round but reported once per error.
+pub fn start_todo_sync(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut reported: HashMap<String, String> = HashMap::new();
+        loop {
+            let results = {
+                let state = app.state::<AppState>();
+                let conn = match state.conn() {
+                    Ok(conn) => conn,
+                    Err(_) => break,
+                };
+                todo_sync::sync_all(&conn, Utc::now())
+            };
 
-    Ok((subtask, session))
+            let mut changed_app = false;
+            for (mut file, report) in results.unwrap_or_default() {
+                match report {
+                    Ok(report) => {
+                        reported.remove(&file.id);
+                        changed_app |= report.changed_app();
+                        if report.conflicts != file.conflicts {
+                            file.conflicts = report.conflicts;
+                            let _ = app.emit("todo-conflicts", &file);
+                        }
+                    }
+                    Err(e) => {
+                        if reported.get(&file.id) != Some(&e) {
+                            eprintln!("TODO sync failed for {}: {}", file.path, e);
+                            reported.insert(file.id, e);
+                        }
+                    }
+                }
+            }
+            if changed_app {
+                let _ = app.emit("timer-changed", ());
+            }
+            std::thread::sleep(std::time::Duration::from_millis(TODO_SYNC_POLL_MILLISECONDS));
+        }
+    });
 }
 
+// ============================================================================
+// WEBHOOK OUTBOX COMMANDS
+// ============================================================================
+
+/// Register a URL to receive completion, level-up and streak events; `event_types` empty means all
 #[tauri::command]
-pub fn get_task_with_subtasks_and_sessions(
-    task_id: String,
+pub fn add_webhook_endpoint(
+    url: String,
+    event_types: Vec<String>,
     state: State<AppState>,
-) -> Result<TaskWithSubtasksAndSessions, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<WebhookEndpoint, String> {
+    let event_types = event_types
+        .iter()
+        .map(|t| OutboxEventType::from_str(t).ok_or_else(|| format!("Invalid event type: {}", t)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let conn = state.conn()?;
+    outbox::add_endpoint(&conn, &url, &event_types)
+}
 
-    // Get task
-    let mut stmt = conn
-        .prepare("SELECT id, title, description, status, created_at, updated_at, completed_at FROM tasks WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn list_webhook_endpoints(state: State<AppState>) -> Result<Vec<WebhookEndpoint>, String> {
+    let conn = state.conn()?;
+    outbox::list_endpoints(&conn)
+}
 
-    let task = stmt
-        .query_row([&task_id], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                status: TaskStatus::from_str(&row.get::<_, String>(3)?),
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                completed_at: row.get(6)?,
-            })
-        })
+/// Disabled endpoints keep their queued events until re-enabled
+#[tauri::command]
+pub fn set_webhook_endpoint_enabled(
+    endpoint_id: String,
+    enabled: bool,
+    state: State<AppState>,
+) -> Result<WebhookEndpoint, String> {
+    let conn = state.conn()?;
+    outbox::set_endpoint_enabled(&conn, &endpoint_id, enabled)
+}
+
+/// Remove an endpoint and its queued and delivered events
+#[tauri::command]
+pub fn remove_webhook_endpoint(endpoint_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    conn.execute("DELETE FROM webhook_endpoints WHERE id = ?1", [&endpoint_id])
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Get subtasks with total time and active sessions
-    let mut stmt = conn
-        .prepare(
-            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
-                    COALESCE(SUM(CASE WHEN ts.ended_at IS NOT NULL THEN ts.duration_seconds ELSE 0 END), 0) as total_time,
-                    sess.id as session_id, sess.started_at, sess.paused_at, sess.resumed_at, sess.ended_at, sess.duration_seconds,
-                    s.category_id, c.id, c.name, c.color, c.created_at
-             FROM subtasks s
-             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
-             LEFT JOIN time_sessions sess ON s.id = sess.subtask_id AND sess.ended_at IS NULL
-             LEFT JOIN categories c ON s.category_id = c.id
-             WHERE s.task_id = ?1
-             GROUP BY s.id, sess.id, sess.started_at, sess.paused_at, sess.resumed_at, sess.ended_at, sess.duration_seconds
-             ORDER BY s.created_at"
-        )
+#[tauri::command]
+pub fn list_outbox(limit: Option<i64>, state: State<AppState>) -> Result<Vec<OutboxEntry>, String> {
+    let conn = state.conn()?;
+    outbox::list_outbox(&conn, limit.unwrap_or(100))
+}
+
+#[tauri::command]
+pub fn retry_outbox_entry(entry_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    outbox::retry_entry(&conn, &entry_id, Utc::now())
+}
+
+const OUTBOX_POLL_MILLISECONDS: u64 = 5000;
+
+/// Deliver queued events in the background
+pub fn start_outbox_worker(app: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        let state = app.state::<AppState>();
+        if let Err(e) = outbox::process_due(&state.db, Utc::now()) {
+            eprintln!("Outbox delivery failed: {}", e);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(OUTBOX_POLL_MILLISECONDS));
+    });
+}
+
+// ============================================================================
+// PLUGIN COMMANDS
+// ============================================================================
+
+/// Install a Rhai script defining any of the hooks in `plugins::HOOKS`
+#[tauri::command]
+pub fn add_plugin(name: String, source: String, state: State<AppState>) -> Result<Plugin, String> {
+    let conn = state.conn()?;
+    plugins::add_plugin(&conn, &name, &source)
+}
+
+#[tauri::command]
+pub fn list_plugins(state: State<AppState>) -> Result<Vec<Plugin>, String> {
+    let conn = state.conn()?;
+    plugins::list_plugins(&conn)
+}
+
+#[tauri::command]
+pub fn update_plugin_source(plugin_id: String, source: String, state: State<AppState>) -> Result<Plugin, String> {
+    let conn = state.conn()?;
+    plugins::update_plugin_source(&conn, &plugin_id, &source)
+}
+
+#[tauri::command]
+pub fn set_plugin_enabled(plugin_id: String, enabled: bool, state: State<AppState>) -> Result<Plugin, String> {
+    let conn = state.conn()?;
+    plugins::set_plugin_enabled(&conn, &plugin_id, enabled)
+}
+
+#[tauri::command]
+pub fn remove_plugin(plugin_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    conn.execute("DELETE FROM plugins WHERE id = ?1", [&plugin_id])
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let subtasks_with_sessions = stmt
-        .query_map([&task_id], |row| {
-            let session = if row.get::<_, Option<String>>(8)?.is_some() {
-                Some(TimeSession {
-                    id: row.get(8)?,
-                    subtask_id: row.get(0)?,
-                    started_at: row.get(9)?,
-                    paused_at: row.get(10)?,
-                    resumed_at: row.get(11)?,
-                    ended_at: row.get(12)?,
-                    duration_seconds: row.get(13)?,
-                })
-            } else {
-                None
+const PLUGIN_NOTIFICATION_POLL_MILLISECONDS: u64 = 1000;
+
+/// Emit `plugin-notification` for notifications raised by hooks, including those run by the CLI
+/// or the HTTP API
+pub fn start_plugin_notifier(app: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        let notifications = {
+            let state = app.state::<AppState>();
+            let conn = match state.conn() {
+                Ok(conn) => conn,
+                Err(_) => break,
             };
+            plugins::take_notifications(&conn)
+        };
 
-            let category_id: Option<String> = row.get(14)?;
-            let category = if row.get::<_, Option<String>>(15)?.is_some() {
-                Some(Category {
-                    id: row.get(15)?,
-                    name: row.get(16)?,
-                    color: row.get(17)?,
-                    created_at: row.get(18)?,
-                })
-            } else {
-                None
+        match notifications {
+            Ok(notifications) => {
+                for notification in notifications {
+                    let _ = app.emit("plugin-notification", &notification);
+                }
+            }
+            Err(e) => eprintln!("Plugin notifications failed: {}", e),
+        }
+        std::thread::sleep(std::time::Duration::from_millis(PLUGIN_NOTIFICATION_POLL_MILLISECONDS));
+    });
+}
+
+// ============================================================================
+// TEAM SYNC COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_team_sync_status(state: State<AppState>) -> Result<TeamSyncStatus, String> {
+    let conn = state.conn()?;
+    team_sync::get_status(&conn)
+}
+
+/// Share the active profile through `folder`, or stop sharing when it's None
+#[tauri::command]
+pub fn set_team_sync_folder(folder: Option<String>, state: State<AppState>) -> Result<TeamSyncStatus, String> {
+    let conn = state.conn()?;
+    team_sync::configure(&conn, folder.as_deref(), Utc::now())
+}
+
+#[tauri::command]
+pub fn sync_team_now(app: tauri::AppHandle, state: State<AppState>) -> Result<TeamSyncSummary, String> {
+    let summary = {
+        let conn = state.conn()?;
+        team_sync::sync(&conn, Utc::now())?
+    };
+    if summary.changes_merged > 0 {
+        let _ = app.emit("team-synced", &summary);
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn list_team_members(state: State<AppState>) -> Result<Vec<TeamMember>, String> {
+    let conn = state.conn()?;
+    team_sync::list_members(&conn, Utc::now())
+}
+
+#[tauri::command]
+pub fn get_team_leaderboard(state: State<AppState>) -> Result<Vec<TeamLeaderboardEntry>, String> {
+    let conn = state.conn()?;
+    team_sync::leaderboard(&conn, Utc::now())
+}
+
+const TEAM_SYNC_INTERVAL_SECONDS: u64 = 30;
+
+/// Publish and merge team logs periodically while team sync is configured, emitting
+/// `team-synced` when other members' changes arrive
+pub fn start_team_sync(app: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_secs(TEAM_SYNC_INTERVAL_SECONDS));
+        let result = {
+            let state = app.state::<AppState>();
+            let conn = match state.conn() {
+                Ok(conn) => conn,
+                Err(_) => break,
             };
+            match team_sync::get_status(&conn) {
+                Ok(status) if status.folder.is_some() => team_sync::sync(&conn, Utc::now()).map(Some),
+                Ok(_) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
 
-            Ok(SubtaskWithSession {
-                subtask: Subtask {
-                    id: row.get(0)?,
-                    task_id: row.get(1)?,
-                    title: row.get(2)?,
-                    status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
-                    created_at: row.get(4)?,
-                    updated_at: row.get(5)?,
-                    completed_at: row.get(6)?,
-                    total_time_seconds: Some(row.get(7)?),
-                    category_id,
-                    category,
-                },
-                session,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<SqlResult<Vec<SubtaskWithSession>>>()
+        match result {
+            Ok(Some(summary)) => {
+                for error in &summary.errors {
+                    eprintln!("Team sync: {}", error);
+                }
+                if summary.changes_merged > 0 {
+                    let _ = app.emit("team-synced", &summary);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Team sync failed: {}", e),
+        }
+    });
+}
+
+// ============================================================================
+// LEADERBOARD & CHALLENGE COMMANDS
+// ============================================================================
+
+fn parse_leaderboard_source(source: &str) -> Result<LeaderboardSource, String> {
+    LeaderboardSource::from_str(source).ok_or_else(|| format!("Invalid leaderboard source: {}", source))
+}
+
+/// Rank this database's profiles (`profiles`) or the team (`team`) by `weekly_points`,
+/// `category_xp` (needs `category`) or `streak`
+#[tauri::command]
+pub fn get_leaderboard(
+    source: String,
+    metric: String,
+    category: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<LeaderboardEntry>, String> {
+    let source = parse_leaderboard_source(&source)?;
+    let metric = LeaderboardMetric::from_str(&metric).ok_or_else(|| format!("Invalid leaderboard metric: {}", metric))?;
+    let conn = state.conn()?;
+    leaderboard::leaderboard(&conn, source, metric, category.as_deref(), Utc::now())
+}
+
+#[tauri::command]
+pub fn create_challenge(
+    name: String,
+    metric: String,
+    category: Option<String>,
+    source: String,
+    start_date: String,
+    end_date: String,
+    state: State<AppState>,
+) -> Result<Challenge, String> {
+    let metric = ChallengeMetric::from_str(&metric).ok_or_else(|| format!("Invalid challenge metric: {}", metric))?;
+    let source = parse_leaderboard_source(&source)?;
+    let conn = state.conn()?;
+    leaderboard::create_challenge(&conn, &name, metric, category.as_deref(), source, &start_date, &end_date)
+}
+
+#[tauri::command]
+pub fn list_challenges(state: State<AppState>) -> Result<Vec<Challenge>, String> {
+    let conn = state.conn()?;
+    leaderboard::list_challenges(&conn)
+}
+
+#[tauri::command]
+pub fn get_challenge_standings(challenge_id: String, state: State<AppState>) -> Result<ChallengeStandings, String> {
+    let conn = state.conn()?;
+    leaderboard::challenge_standings(&conn, &challenge_id, Utc::now())
+}
+
+#[tauri::command]
+pub fn delete_challenge(challenge_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    conn.execute("DELETE FROM challenges WHERE id = ?1", [&challenge_id])
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    Ok(TaskWithSubtasksAndSessions {
-        id: task.id,
-        title: task.title,
-        description: task.description,
-        status: task.status,
-        created_at: task.created_at,
-        updated_at: task.updated_at,
-        completed_at: task.completed_at,
-        subtasks_with_sessions,
+// ============================================================================
+// HTTP API COMMANDS
+// ============================================================================
+
+fn http_api_status(config: &HttpApiConfig, server: Option<&HttpApiServer>) -> HttpApiStatus {
+    HttpApiStatus {
+        enabled: config.enabled,
+        running: server.is_some(),
+        port: server.map_or(config.port, |server| server.port),
+        token: config.token.clone(),
+    }
+}
+
+/// Serve the local HTTP API against the app's database, notifying windows of timer changes
+fn spawn_http_api(app: &tauri::AppHandle, config: &HttpApiConfig) -> Result<HttpApiServer, String> {
+    let app = app.clone();
+    let token = config.token.clone();
+
+    http_api::start_server(config.port, move |request| {
+        let state = app.state::<AppState>();
+        let response = match state.conn() {
+            Ok(conn) => http_api::handle_request(&conn, &token, request),
+            Err(e) => ApiResponse::error(500, e),
+        };
+        if response.mutated {
+            let _ = app.emit("timer-changed", ());
+        }
+        response
     })
 }
 
+/// Stop the running server, if any, and start it again when the config is enabled.
+/// Must not be called while holding the database lock: stopping waits for in-flight requests.
+fn restart_http_api(app: &tauri::AppHandle, config: &HttpApiConfig) -> Result<HttpApiStatus, String> {
+    let state = app.state::<AppState>();
+    let mut server = state.http_api.lock().map_err(|e| e.to_string())?;
+
+    if let Some(running) = server.take() {
+        running.stop();
+    }
+    if config.enabled {
+        *server = Some(spawn_http_api(app, config)?);
+    }
+
+    Ok(http_api_status(config, server.as_ref()))
+}
+
+/// Start the HTTP API at launch if it was left enabled
+pub fn start_http_api_if_enabled(app: &tauri::AppHandle) {
+    let config = {
+        let state = app.state::<AppState>();
+        let conn = match state.conn() {
+            Ok(conn) => conn,
+            Err(_) => return,
+        };
+        http_api::load_config(&conn)
+    };
+
+    match config {
+        Ok(config) if config.enabled => {
+            if let Err(e) = restart_http_api(app, &config) {
+                eprintln!("HTTP API not started: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to load HTTP API config: {}", e),
+    }
+}
+
+#[tauri::command]
+pub fn get_http_api_status(state: State<AppState>) -> Result<HttpApiStatus, String> {
+    let config = {
+        let conn = state.conn()?;
+        http_api::load_config(&conn)?
+    };
+    let server = state.http_api.lock().map_err(|e| e.to_string())?;
+    Ok(http_api_status(&config, server.as_ref()))
+}
+
+/// Enable or disable the server (always bound to 127.0.0.1), optionally changing its port
+#[tauri::command]
+pub fn set_http_api_enabled(
+    enabled: bool,
+    port: Option<u16>,
+    app: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<HttpApiStatus, String> {
+    if port == Some(0) {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+
+    let config = {
+        let conn = state.conn()?;
+        let mut config = http_api::load_config(&conn)?;
+        config.enabled = enabled;
+        config.port = port.unwrap_or(config.port);
+        http_api::save_config(&conn, &config)?;
+        config
+    };
+
+    restart_http_api(&app, &config)
+}
+
+/// Replace the bearer token; clients using the old one are rejected from now on
+#[tauri::command]
+pub fn regenerate_http_api_token(app: tauri::AppHandle, state: State<AppState>) -> Result<HttpApiStatus, String> {
+    let config = {
+        let conn = state.conn()?;
+        let mut config = http_api::load_config(&conn)?;
+        config.token = http_api::generate_token();
+        http_api::save_config(&conn, &config)?;
+        config
+    };
+
+    restart_http_api(&app, &config)
+}
+
 // ============================================================================
 // USER PROFILE COMMANDS
 // ============================================================================
 
 #[tauri::command]
 pub fn get_user_profile(state: State<AppState>) -> Result<UserProfile, String> {
-    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = state.conn()?;
+    profiles::active_profile(&conn)
+}
 
-    // Fetch user profile
-    let mut stmt = conn
-        .prepare("SELECT id, level, total_xp, current_title, current_streak, longest_streak, last_work_date, created_at, updated_at FROM user_profile LIMIT 1")
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn list_profiles(state: State<AppState>) -> Result<Vec<UserProfile>, String> {
+    let conn = state.conn()?;
+    profiles::list_profiles(&conn)
+}
 
-    let profile = stmt
-        .query_row([], |row| {
-            let level: i64 = row.get(1)?;
-            let total_xp: i64 = row.get(2)?;
-            let xp_for_next = get_xp_for_next_global_level(level);
-            let xp_current_level = calculate_xp_for_global_level(level);
-            let xp_in_current = total_xp - xp_current_level;
-            let xp_needed = xp_for_next - xp_current_level;
-            let progress = if xp_needed > 0 {
-                ((xp_in_current as f64 / xp_needed as f64) * 100.0).min(100.0).max(0.0)
-            } else {
-                100.0
-            };
+#[tauri::command]
+pub fn create_profile(name: String, state: State<AppState>) -> Result<UserProfile, String> {
+    let conn = state.conn()?;
+    profiles::create_profile(&conn, &name)
+}
 
-            Ok(UserProfile {
-                id: row.get(0)?,
-                level,
-                total_xp,
-                current_title: row.get(3)?,
-                current_streak: row.get(4)?,
-                longest_streak: row.get(5)?,
-                last_work_date: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-                xp_for_next_level: xp_for_next,
-                progress_percentage: progress,
-            })
-        })
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn rename_profile(profile_id: String, name: String, state: State<AppState>) -> Result<UserProfile, String> {
+    let conn = state.conn()?;
+    profiles::rename_profile(&conn, &profile_id, &name)
+}
 
+/// Switch the active profile and tell every window to reload its data
+#[tauri::command]
+pub fn switch_profile(
+    profile_id: String,
+    app: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<UserProfile, String> {
+    let profile = {
+        let conn = state.conn()?;
+        profiles::switch_profile(&conn, &profile_id)?
+    };
+    let _ = app.emit("profile-switched", &profile);
+    let _ = app.emit("timer-changed", ());
     Ok(profile)
 }
 
+#[tauri::command]
+pub fn delete_profile(profile_id: String, state: State<AppState>) -> Result<(), String> {
+    let conn = state.conn()?;
+    profiles::delete_profile(&conn, &profile_id)
+}
+
+// ============================================================================
+// SETTINGS COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub fn get_settings(state: State<AppState>) -> Result<settings::Settings, String> {
+    let conn = state.conn()?;
+    settings::load_settings(&conn)
+}
+
+/// Apply a partial update (e.g. `{ "pomodoro": { "focusMinutes": 50 } }`) and broadcast
+/// the full settings to every window as `settings-changed`
+#[tauri::command]
+pub fn update_settings(
+    patch: serde_json::Value,
+    app: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<settings::Settings, String> {
+    let settings = {
+        let conn = state.conn()?;
+        settings::update_settings(&conn, patch, &Utc::now().to_rfc3339())?
+    };
+    let _ = app.emit("settings-changed", &settings);
+    Ok(settings)
+}
+
 // ============================================================================
 // TRAY ICON COMMANDS
 // ============================================================================
 
+/// Tray preferences, or the defaults if the settings can't be read
+pub fn tray_settings(app: &tauri::AppHandle) -> settings::TraySettings {
+    let state = app.state::<AppState>();
+    let conn = match state.conn() {
+        Ok(conn) => conn,
+        Err(_) => return settings::TraySettings::default(),
+    };
+    settings::load_settings(&conn).map(|settings| settings.tray).unwrap_or_default()
+}
+
 #[tauri::command]
 pub fn minimize_to_tray(app: tauri::AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("main") {
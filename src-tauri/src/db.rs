@@ -1,24 +1,62 @@
+use crate::db_location;
+use crate::encryption;
+use crate::gamification::{calculate_subtask_points, is_efficient, ScoringRules};
+use crate::settings;
 use rusqlite::{Connection, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+/// Stored in `PRAGMA user_version`; bump when `create_tables` changes the schema
+pub const SCHEMA_VERSION: i64 = 14;
+
+/// The database file chosen by `db_location::current()`; its folder is created if missing
 pub fn get_db_path() -> PathBuf {
-    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
-    path.push("devfocus");
-    std::fs::create_dir_all(&path).ok();
-    path.push("devfocus.db");
+    let path = PathBuf::from(db_location::current().path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
     path
 }
 
+/// How long a connection waits for another process (the GUI or the CLI) to release a lock
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Open the database at `get_db_path()`, unlocking it with the stored key when it is encrypted
 pub fn init_db() -> Result<Connection> {
     let db_path = get_db_path();
-    let conn = Connection::open(db_path)?;
+    let key = encryption::startup_key(&db_path);
+    open_db(&db_path, key.as_deref())
+}
 
+/// Open and migrate the database file at `path`; `key` is required when it is encrypted
+pub fn open_db(path: &Path, key: Option<&str>) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    if let Some(key) = key {
+        encryption::apply_key(&conn, key)?;
+    }
+
+    configure_connection(&conn)?;
     create_tables(&conn)?;
 
     Ok(conn)
 }
 
-fn create_tables(conn: &Connection) -> Result<()> {
+/// Empty in-memory stand-in used while an encrypted database waits to be unlocked,
+/// so background tasks keep running against no data instead of failing
+pub fn locked_placeholder() -> Result<Connection> {
+    let conn = Connection::open_in_memory()?;
+    create_tables(&conn)?;
+    Ok(conn)
+}
+
+/// WAL lets the desktop app and the `devfocus` CLI read while the other writes
+fn configure_connection(conn: &Connection) -> Result<()> {
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(())
+}
+
+pub(crate) fn create_tables(conn: &Connection) -> Result<()> {
     // Tasks table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS tasks (
@@ -44,14 +82,17 @@ fn create_tables(conn: &Connection) -> Result<()> {
         [],
     )?;
 
-    // Category experience table
+    // Category experience table (per profile)
     conn.execute(
         "CREATE TABLE IF NOT EXISTS category_experience (
             id TEXT PRIMARY KEY,
-            category_id TEXT UNIQUE NOT NULL,
+            profile_id TEXT NOT NULL,
+            category_id TEXT NOT NULL,
             total_xp INTEGER DEFAULT 0,
             level INTEGER DEFAULT 1,
             updated_at TEXT NOT NULL,
+            UNIQUE(profile_id, category_id),
+            FOREIGN KEY(profile_id) REFERENCES user_profile(id) ON DELETE CASCADE,
             FOREIGN KEY(category_id) REFERENCES categories(id) ON DELETE CASCADE
         )",
         [],
@@ -77,6 +118,9 @@ fn create_tables(conn: &Connection) -> Result<()> {
     // Migrate existing subtasks table to add category_id if it doesn't exist
     migrate_add_category_to_subtasks(conn)?;
 
+    // Migrate tasks and subtasks to add optional time estimates
+    migrate_add_estimate_seconds(conn)?;
+
     // Time sessions table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS time_sessions (
@@ -92,6 +136,18 @@ fn create_tables(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    // Running intervals of each time session (one row per start/resume until pause/complete)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_intervals (
+            id TEXT PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            FOREIGN KEY(session_id) REFERENCES time_sessions(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
     // Create indices for better performance
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_subtasks_task_id ON subtasks(task_id)",
@@ -108,6 +164,11 @@ fn create_tables(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_session_intervals_session_id ON session_intervals(session_id)",
+        [],
+    )?;
+
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_category_experience_category_id ON category_experience(category_id)",
         [],
@@ -117,6 +178,8 @@ fn create_tables(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS user_profile (
             id TEXT PRIMARY KEY,
+            name TEXT NOT NULL DEFAULT 'Default',
+            is_active INTEGER NOT NULL DEFAULT 0,
             level INTEGER DEFAULT 1,
             total_xp INTEGER DEFAULT 0,
             current_title TEXT DEFAULT 'novice',
@@ -140,12 +203,282 @@ fn create_tables(conn: &Connection) -> Result<()> {
         [],
     )?;
 
-    // Insert default categories if they don't exist
-    seed_default_categories(conn)?;
+    // XP ledger table (one row per XP gain, used for historical timelines)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS xp_ledger (
+            id TEXT PRIMARY KEY,
+            category_id TEXT NOT NULL,
+            subtask_id TEXT,
+            xp INTEGER NOT NULL,
+            earned_at TEXT NOT NULL,
+            FOREIGN KEY(category_id) REFERENCES categories(id) ON DELETE CASCADE,
+            FOREIGN KEY(subtask_id) REFERENCES subtasks(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_xp_ledger_category_id ON xp_ledger(category_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_xp_ledger_earned_at ON xp_ledger(earned_at)",
+        [],
+    )?;
+
+    // Local HTTP API settings (single row, created on first use)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS http_api_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled INTEGER NOT NULL DEFAULT 0,
+            port INTEGER NOT NULL,
+            token TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Local git repositories scanned for commits made during a task's sessions
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS git_repositories (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            last_scanned_at TEXT,
+            UNIQUE(task_id, path),
+            FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS commit_links (
+            id TEXT PRIMARY KEY,
+            subtask_id TEXT NOT NULL,
+            repository_id TEXT NOT NULL,
+            commit_hash TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            author TEXT NOT NULL,
+            committed_at TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            UNIQUE(subtask_id, commit_hash),
+            FOREIGN KEY(subtask_id) REFERENCES subtasks(id) ON DELETE CASCADE,
+            FOREIGN KEY(repository_id) REFERENCES git_repositories(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_commit_links_subtask_id ON commit_links(subtask_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS branch_watchers (
+            id TEXT PRIMARY KEY,
+            path TEXT NOT NULL UNIQUE,
+            switch_timer INTEGER NOT NULL DEFAULT 0,
+            last_branch TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS branch_tasks (
+            id TEXT PRIMARY KEY,
+            watcher_id TEXT NOT NULL,
+            branch TEXT NOT NULL,
+            task_id TEXT NOT NULL,
+            profile_id TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE(watcher_id, branch, profile_id),
+            FOREIGN KEY(watcher_id) REFERENCES branch_watchers(id) ON DELETE CASCADE,
+            FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS todo_files (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            path TEXT NOT NULL UNIQUE,
+            last_synced_at TEXT,
+            conflicts TEXT NOT NULL DEFAULT '[]',
+            FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Checklist state at the last sync; no foreign key on subtask_id so app-side deletions stay detectable
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS todo_items (
+            id TEXT PRIMARY KEY,
+            file_id TEXT NOT NULL,
+            subtask_id TEXT NOT NULL,
+            text TEXT NOT NULL,
+            checked INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            FOREIGN KEY(file_id) REFERENCES todo_files(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhook_endpoints (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            event_types TEXT NOT NULL DEFAULT '',
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // One row per event and endpoint, kept after delivery as a log
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS outbox (
+            id TEXT PRIMARY KEY,
+            endpoint_id TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TEXT NOT NULL,
+            delivered_at TEXT,
+            last_error TEXT,
+            FOREIGN KEY(endpoint_id) REFERENCES webhook_endpoints(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_outbox_pending ON outbox(delivered_at, next_attempt_at)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS plugins (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            source TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            hooks TEXT NOT NULL DEFAULT '',
+            last_error TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS plugin_notifications (
+            id TEXT PRIMARY KEY,
+            plugin_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            body TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            delivered INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY(plugin_id) REFERENCES plugins(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Team sync through a shared folder: our own publishing state and everyone's merged records
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS team_sync_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            folder TEXT NOT NULL,
+            profile_id TEXT NOT NULL,
+            clock INTEGER NOT NULL DEFAULT 0,
+            last_synced_at TEXT,
+            FOREIGN KEY(profile_id) REFERENCES user_profile(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS team_sync_exported (
+            entity_type TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            PRIMARY KEY(entity_type, entity_id)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS team_log_cursors (
+            file_name TEXT PRIMARY KEY,
+            offset INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS team_entities (
+            member_id TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            clock INTEGER NOT NULL,
+            change_id TEXT NOT NULL,
+            data TEXT,
+            PRIMARY KEY(member_id, entity_type, entity_id)
+        )",
+        [],
+    )?;
+
+    // Time-boxed challenges scored from profiles or the team view
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS challenges (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            metric TEXT NOT NULL,
+            category TEXT,
+            source TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            final_standings TEXT
+        )",
+        [],
+    )?;
+    // Standings are frozen as JSON once a challenge has ended
+    if !column_exists(conn, "challenges", "final_standings")? {
+        conn.execute("ALTER TABLE challenges ADD COLUMN final_standings TEXT", [])?;
+        println!("Migration: Added final_standings column to challenges table");
+    }
+
+    // Typed user preferences (settings.rs), one JSON value per top-level field
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Points are stored when work is completed, so rule changes only affect new completions
+    migrate_stored_points(conn)?;
 
     // Initialize user profile if it doesn't exist
     init_user_profile(conn)?;
 
+    // Scope tasks and XP to profiles
+    migrate_profiles(conn)?;
+    migrate_branch_tasks_per_profile(conn)?;
+
+    // Insert default categories if they don't exist
+    seed_default_categories(conn)?;
+
+    // Derive ledger entries for XP earned before the ledger existed
+    migrate_backfill_xp_ledger(conn)?;
+
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
     Ok(())
 }
 
@@ -172,6 +505,90 @@ fn migrate_add_category_to_subtasks(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+fn migrate_add_estimate_seconds(conn: &Connection) -> Result<()> {
+    for table in ["tasks", "subtasks"] {
+        let column_exists: bool = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name='estimate_seconds'", table),
+                [],
+                |row| row.get::<_, i32>(0),
+            )
+            .unwrap_or(0)
+            > 0;
+
+        if !column_exists {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN estimate_seconds INTEGER", table), [])?;
+            println!("Migration: Added estimate_seconds column to {} table", table);
+        }
+    }
+
+    Ok(())
+}
+
+fn migrate_backfill_xp_ledger(conn: &Connection) -> Result<()> {
+    let ledger_entries: i64 = conn.query_row("SELECT COUNT(*) FROM xp_ledger", [], |row| row.get(0))?;
+    let earned_xp: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(total_xp), 0) FROM category_experience",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if ledger_entries > 0 || earned_xp == 0 {
+        return Ok(());
+    }
+
+    // Completed subtasks earned their tracked time as XP on completion
+    let mut stmt = conn.prepare(
+        "SELECT s.category_id, s.id, COALESCE(SUM(ts.duration_seconds), 0), s.completed_at
+         FROM subtasks s
+         JOIN categories c ON s.category_id = c.id
+         LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+         WHERE s.status = 'done' AND s.completed_at IS NOT NULL
+         GROUP BY s.id",
+    )?;
+    let mut entries = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Some(row.get::<_, String>(1)?),
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<(String, Option<String>, i64, String)>>>()?;
+
+    // Streak bonuses and deleted subtasks cannot be reconstructed, so book the
+    // remaining difference as a single adjustment at the last category update
+    let mut stmt = conn.prepare("SELECT category_id, total_xp, updated_at FROM category_experience")?;
+    let totals = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+        })?
+        .collect::<Result<Vec<(String, i64, String)>>>()?;
+
+    for (category_id, total_xp, updated_at) in totals {
+        let derived: i64 = entries
+            .iter()
+            .filter(|(cat_id, _, _, _)| *cat_id == category_id)
+            .map(|(_, _, xp, _)| xp)
+            .sum();
+        if derived != total_xp {
+            entries.push((category_id, None, total_xp - derived, updated_at));
+        }
+    }
+
+    for (category_id, subtask_id, xp, earned_at) in entries.into_iter().filter(|e| e.2 != 0) {
+        conn.execute(
+            "INSERT INTO xp_ledger (id, category_id, subtask_id, xp, earned_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![uuid::Uuid::new_v4().to_string(), category_id, subtask_id, xp, earned_at],
+        )?;
+    }
+
+    println!("Migration: Backfilled xp_ledger from existing category experience");
+
+    Ok(())
+}
+
 fn seed_default_categories(conn: &Connection) -> Result<()> {
     let default_categories = vec![
         ("frontend", "#3b82f6"),
@@ -191,23 +608,212 @@ fn seed_default_categories(conn: &Connection) -> Result<()> {
             "INSERT OR IGNORE INTO categories (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
             rusqlite::params![id, name, color, &now],
         )?;
+    }
 
-        // Get the category id (either newly inserted or existing)
-        let category_id: String = conn.query_row(
-            "SELECT id FROM categories WHERE name = ?1",
-            [name],
-            |row| row.get(0),
+    ensure_category_experience(conn)?;
+
+    Ok(())
+}
+
+/// Give every profile an experience row for every category
+pub(crate) fn ensure_category_experience(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO category_experience (id, profile_id, category_id, total_xp, level, updated_at)
+         SELECT lower(hex(randomblob(16))), p.id, c.id, 0, 1, ?1
+         FROM user_profile p CROSS JOIN categories c",
+        [chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = ?1", table),
+        [column],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+/// Tasks, category experience and the XP ledger belong to a profile; exactly one profile is
+/// active. Existing data moves to the first profile.
+fn migrate_profiles(conn: &Connection) -> Result<()> {
+    if !column_exists(conn, "user_profile", "name")? {
+        conn.execute("ALTER TABLE user_profile ADD COLUMN name TEXT NOT NULL DEFAULT 'Default'", [])?;
+        conn.execute("ALTER TABLE user_profile ADD COLUMN is_active INTEGER NOT NULL DEFAULT 0", [])?;
+        println!("Migration: Added name and is_active columns to user_profile table");
+    }
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_user_profile_name ON user_profile(name)",
+        [],
+    )?;
+    conn.execute(
+        "UPDATE user_profile SET is_active = 1
+         WHERE id = (SELECT id FROM user_profile ORDER BY created_at, id LIMIT 1)
+           AND NOT EXISTS (SELECT 1 FROM user_profile WHERE is_active = 1)",
+        [],
+    )?;
+
+    for table in ["tasks", "xp_ledger"] {
+        if !column_exists(conn, table, "profile_id")? {
+            conn.execute(
+                &format!(
+                    "ALTER TABLE {} ADD COLUMN profile_id TEXT REFERENCES user_profile(id) ON DELETE CASCADE",
+                    table
+                ),
+                [],
+            )?;
+            println!("Migration: Added profile_id column to {} table", table);
+        }
+    }
+
+    // Rebuild category_experience, unique per category before profiles, as unique per profile
+    if !column_exists(conn, "category_experience", "profile_id")? {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(
+            "ALTER TABLE category_experience RENAME TO category_experience_single;
+             CREATE TABLE category_experience (
+                 id TEXT PRIMARY KEY,
+                 profile_id TEXT NOT NULL,
+                 category_id TEXT NOT NULL,
+                 total_xp INTEGER DEFAULT 0,
+                 level INTEGER DEFAULT 1,
+                 updated_at TEXT NOT NULL,
+                 UNIQUE(profile_id, category_id),
+                 FOREIGN KEY(profile_id) REFERENCES user_profile(id) ON DELETE CASCADE,
+                 FOREIGN KEY(category_id) REFERENCES categories(id) ON DELETE CASCADE
+             );
+             INSERT INTO category_experience (id, profile_id, category_id, total_xp, level, updated_at)
+                 SELECT id, (SELECT id FROM user_profile WHERE is_active = 1), category_id, total_xp, level, updated_at
+                 FROM category_experience_single;
+             DROP TABLE category_experience_single;
+             CREATE INDEX IF NOT EXISTS idx_category_experience_category_id ON category_experience(category_id);",
         )?;
+        tx.commit()?;
+        println!("Migration: Scoped category_experience to profiles");
+    }
+
+    conn.execute_batch(
+        "UPDATE tasks SET profile_id = (SELECT id FROM user_profile WHERE is_active = 1) WHERE profile_id IS NULL;
+         UPDATE xp_ledger SET profile_id = COALESCE(
+             (SELECT t.profile_id FROM subtasks s JOIN tasks t ON t.id = s.task_id WHERE s.id = xp_ledger.subtask_id),
+             (SELECT id FROM user_profile WHERE is_active = 1))
+         WHERE profile_id IS NULL;
+         CREATE INDEX IF NOT EXISTS idx_tasks_profile_id ON tasks(profile_id);
+         CREATE INDEX IF NOT EXISTS idx_xp_ledger_profile_id ON xp_ledger(profile_id);",
+    )?;
+
+    // Inserts that don't name a profile get the active one; ledger entries get the
+    // profile owning the subtask's task, which may differ while another profile is active
+    conn.execute_batch(
+        "CREATE TRIGGER IF NOT EXISTS tasks_default_profile AFTER INSERT ON tasks
+         WHEN NEW.profile_id IS NULL
+         BEGIN
+             UPDATE tasks SET profile_id = (SELECT id FROM user_profile WHERE is_active = 1) WHERE id = NEW.id;
+         END;
+         CREATE TRIGGER IF NOT EXISTS xp_ledger_default_profile AFTER INSERT ON xp_ledger
+         WHEN NEW.profile_id IS NULL
+         BEGIN
+             UPDATE xp_ledger SET profile_id = COALESCE(
+                 (SELECT t.profile_id FROM subtasks s JOIN tasks t ON t.id = s.task_id WHERE s.id = NEW.subtask_id),
+                 (SELECT id FROM user_profile WHERE is_active = 1))
+             WHERE id = NEW.id;
+         END;",
+    )?;
+
+    Ok(())
+}
 
-        // Create category_experience entry if it doesn't exist
-        let exp_id = uuid::Uuid::new_v4().to_string();
+/// Branch tasks were unique per watcher and branch; each profile now gets its own task
+fn migrate_branch_tasks_per_profile(conn: &Connection) -> Result<()> {
+    if column_exists(conn, "branch_tasks", "profile_id")? {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(
+        "ALTER TABLE branch_tasks RENAME TO branch_tasks_single;
+         CREATE TABLE branch_tasks (
+             id TEXT PRIMARY KEY,
+             watcher_id TEXT NOT NULL,
+             branch TEXT NOT NULL,
+             task_id TEXT NOT NULL,
+             profile_id TEXT NOT NULL,
+             created_at TEXT NOT NULL,
+             UNIQUE(watcher_id, branch, profile_id),
+             FOREIGN KEY(watcher_id) REFERENCES branch_watchers(id) ON DELETE CASCADE,
+             FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE
+         );
+         INSERT INTO branch_tasks (id, watcher_id, branch, task_id, profile_id, created_at)
+             SELECT bt.id, bt.watcher_id, bt.branch, bt.task_id, t.profile_id, bt.created_at
+             FROM branch_tasks_single bt JOIN tasks t ON t.id = bt.task_id;
+         DROP TABLE branch_tasks_single;",
+    )?;
+    tx.commit()?;
+    println!("Migration: Scoped branch_tasks to profiles");
+    Ok(())
+}
+
+fn migrate_stored_points(conn: &Connection) -> Result<()> {
+    if column_exists(conn, "subtasks", "points")? {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(
+        "ALTER TABLE subtasks ADD COLUMN points INTEGER;
+         ALTER TABLE subtasks ADD COLUMN efficient INTEGER;
+         ALTER TABLE tasks ADD COLUMN bonus_points INTEGER;",
+    )?;
+    // Existing work keeps what the rules in use until now gave it
+    record_missing_points(&tx, &settings::scoring_rules(&tx).unwrap_or_default())?;
+    tx.commit()?;
+    println!("Migration: Stored points of completed subtasks and tasks");
+    Ok(())
+}
+
+/// Score done subtasks that have no stored points yet, such as imported ones, and give
+/// their tasks the complexity bonus when it applies; work completed through the timer
+/// records its points right away
+pub(crate) fn record_missing_points(conn: &Connection, rules: &ScoringRules) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.task_id, COALESCE(SUM(ts.duration_seconds), 0), s.estimate_seconds
+         FROM subtasks s
+         LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+         WHERE s.status = 'done' AND s.points IS NULL
+         GROUP BY s.id",
+    )?;
+    let unscored = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<(String, String, i64, Option<i64>)>>>()?;
+
+    let mut task_ids = std::collections::HashSet::new();
+    for (subtask_id, task_id, duration, estimate_seconds) in unscored {
         conn.execute(
-            "INSERT OR IGNORE INTO category_experience (id, category_id, total_xp, level, updated_at)
-             VALUES (?1, ?2, 0, 1, ?3)",
-            rusqlite::params![exp_id, &category_id, &now],
+            "UPDATE subtasks SET points = ?1, efficient = ?2 WHERE id = ?3",
+            rusqlite::params![
+                calculate_subtask_points(rules, duration, estimate_seconds),
+                is_efficient(rules, duration, estimate_seconds),
+                subtask_id
+            ],
         )?;
+        task_ids.insert(task_id);
     }
 
+    for task_id in task_ids {
+        conn.execute(
+            "UPDATE tasks SET bonus_points = ?1
+             WHERE id = ?2 AND bonus_points IS NULL
+               AND (SELECT COUNT(*) FROM subtasks WHERE task_id = ?2) >= ?3
+               AND NOT EXISTS (SELECT 1 FROM subtasks WHERE task_id = ?2 AND status != 'done')",
+            rusqlite::params![rules.complexity_bonus_points, task_id, rules.complexity_min_subtasks],
+        )?;
+    }
     Ok(())
 }
 
@@ -0,0 +1,390 @@
+use crate::backup;
+use crate::db;
+use crate::encryption;
+use crate::models::{DatabaseLocation, DatabaseLocationSource};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Command-line flag of both binaries: `--db <path>` or `--db=<path>`
+pub const DB_FLAG: &str = "--db";
+pub const DB_PATH_ENV: &str = "DEVFOCUS_DB_PATH";
+/// Set to `1` (or `true`) to keep the data next to the executable
+pub const PORTABLE_ENV: &str = "DEVFOCUS_PORTABLE";
+/// Dropping this file next to the executable turns on portable mode
+pub const PORTABLE_MARKER: &str = "devfocus.portable";
+
+const PORTABLE_DATA_DIR: &str = "devfocus-data";
+const DB_FILE_NAME: &str = "devfocus.db";
+const LOCATION_FILE_NAME: &str = "location.json";
+
+static FLAG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocationFile {
+    database_path: String,
+}
+
+/// Everything the database location can come from, highest priority first
+#[derive(Debug, Default)]
+pub struct LocationInputs {
+    pub flag: Option<PathBuf>,
+    pub env: Option<PathBuf>,
+    // Folder next to the executable, when portable mode is on
+    pub portable_dir: Option<PathBuf>,
+    pub settings: Option<PathBuf>,
+    pub default_dir: PathBuf,
+}
+
+/// Remove `--db <path>` / `--db=<path>` from the arguments and return the path
+pub fn take_db_flag(args: &mut Vec<String>) -> Result<Option<PathBuf>, String> {
+    let Some(index) = args
+        .iter()
+        .position(|arg| arg == DB_FLAG || arg.starts_with(&format!("{}=", DB_FLAG)))
+    else {
+        return Ok(None);
+    };
+
+    let arg = args.remove(index);
+    let value = match arg.split_once('=') {
+        Some((_, value)) => value.to_string(),
+        None if index < args.len() => args.remove(index),
+        None => String::new(),
+    };
+    if value.is_empty() {
+        return Err(format!("{} needs a path", DB_FLAG));
+    }
+    Ok(Some(PathBuf::from(value)))
+}
+
+/// Use `path` for the rest of the process, as given by `--db`
+pub fn set_flag_path(path: PathBuf) {
+    let path = match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => cwd.join(path),
+        _ => path,
+    };
+    if FLAG_PATH.set(path).is_err() {
+        eprintln!("{} was given more than once; using the first", DB_FLAG);
+    }
+}
+
+/// `location.json` in the user's config folder remembers where `move_database` put the data
+pub fn location_file_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("devfocus");
+    path.push(LOCATION_FILE_NAME);
+    path
+}
+
+fn read_location_file(path: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<LocationFile>(&content) {
+        Ok(file) if !file.database_path.is_empty() => Some(PathBuf::from(file.database_path)),
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("Ignoring invalid {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn write_location_file(path: &Path, db_path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let file = LocationFile {
+        database_path: db_path.to_string_lossy().to_string(),
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn portable_dir() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    let enabled = std::env::var(PORTABLE_ENV).is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        || exe_dir.join(PORTABLE_MARKER).exists();
+    enabled.then(|| exe_dir.join(PORTABLE_DATA_DIR))
+}
+
+/// A folder (existing, or written with a trailing separator) gets the default file name
+fn database_file(path: PathBuf) -> PathBuf {
+    if path.is_dir() || path.as_os_str().to_string_lossy().ends_with(std::path::is_separator) {
+        path.join(DB_FILE_NAME)
+    } else {
+        path
+    }
+}
+
+/// Pick the database file: `--db`, then `DEVFOCUS_DB_PATH`, then portable mode,
+/// then the location file, then the per-user data folder
+pub fn resolve(inputs: LocationInputs) -> (PathBuf, DatabaseLocationSource) {
+    if let Some(path) = inputs.flag {
+        return (database_file(path), DatabaseLocationSource::CliFlag);
+    }
+    if let Some(path) = inputs.env {
+        return (database_file(path), DatabaseLocationSource::Env);
+    }
+    if let Some(dir) = inputs.portable_dir {
+        return (dir.join(DB_FILE_NAME), DatabaseLocationSource::Portable);
+    }
+    if let Some(path) = inputs.settings {
+        return (database_file(path), DatabaseLocationSource::SettingsFile);
+    }
+    (inputs.default_dir.join(DB_FILE_NAME), DatabaseLocationSource::Default)
+}
+
+/// Where this process keeps its database
+pub fn current() -> DatabaseLocation {
+    let location_file = location_file_path();
+    let mut default_dir = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    default_dir.push("devfocus");
+
+    let (path, source) = resolve(LocationInputs {
+        flag: FLAG_PATH.get().cloned(),
+        env: std::env::var_os(DB_PATH_ENV).filter(|value| !value.is_empty()).map(PathBuf::from),
+        portable_dir: portable_dir(),
+        settings: read_location_file(&location_file),
+        default_dir,
+    });
+
+    DatabaseLocation {
+        path: path.to_string_lossy().to_string(),
+        source,
+        location_file: location_file.to_string_lossy().to_string(),
+        warnings: Vec::new(),
+    }
+}
+
+pub fn remove_file_if_exists(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to delete {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Rename, falling back to copy and delete across file systems
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to).map_err(|e| format!("Failed to copy {}: {}", from.display(), e))?;
+    remove_file_if_exists(from)
+}
+
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut sidecar = path.to_path_buf().into_os_string();
+    sidecar.push(suffix);
+    PathBuf::from(sidecar)
+}
+
+/// The new database file and what could not be cleaned up at the old location
+#[derive(Debug)]
+pub struct MovedDatabase {
+    pub path: PathBuf,
+    pub warnings: Vec<String>,
+}
+
+/// Copy the live database and its key file to `target`, check the copy, point
+/// `location_file` at it and switch the connection over. Only then are the old files
+/// and backups removed or moved, so a failure before the switch leaves the original in
+/// use; failures after it are returned as warnings.
+pub fn move_database(
+    conn: &mut Connection,
+    target: &Path,
+    key: Option<&str>,
+    location_file: &Path,
+) -> Result<MovedDatabase, String> {
+    let source = conn
+        .path()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| "The database is not stored in a file".to_string())?;
+    if !target.is_absolute() {
+        return Err(format!("Target must be an absolute path: {}", target.display()));
+    }
+    let target = database_file(target.to_path_buf());
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()));
+    }
+    // Only a key file next to the database travels with it
+    let old_key_file = encryption::key_file_path(&source);
+    let key_file = (old_key_file == source.with_extension("key") && old_key_file.exists())
+        .then(|| (old_key_file, target.with_extension("key")));
+    if let Some((_, new_key_file)) = &key_file {
+        if new_key_file.exists() {
+            return Err(format!("{} already exists", new_key_file.display()));
+        }
+    }
+
+    let parent = target.parent().ok_or_else(|| format!("Invalid target: {}", target.display()))?;
+    std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+
+    // VACUUM INTO writes a consistent copy, encrypted with the same key if the source is
+    let scratch = sidecar(&target, ".moving");
+    remove_file_if_exists(&scratch)?;
+    let scratch_str = scratch.to_str().ok_or_else(|| format!("Invalid path: {}", scratch.display()))?;
+    conn.execute("VACUUM INTO ?1", [scratch_str])
+        .map_err(|e| format!("Failed to copy the database: {}", e))?;
+    std::fs::rename(&scratch, &target).map_err(|e| {
+        std::fs::remove_file(&scratch).ok();
+        format!("Failed to create {}: {}", target.display(), e)
+    })?;
+
+    let moved = db::open_db(&target, key)
+        .map_err(|e| e.to_string())
+        .and_then(|moved| {
+            let integrity: String = moved
+                .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            if integrity != "ok" {
+                return Err(format!("Copy failed integrity check: {}", integrity));
+            }
+            if let Some((old_key_file, new_key_file)) = &key_file {
+                std::fs::copy(old_key_file, new_key_file)
+                    .map_err(|e| format!("Failed to copy {}: {}", old_key_file.display(), e))?;
+            }
+            write_location_file(location_file, &target)?;
+            Ok(moved)
+        });
+    let moved = match moved {
+        Ok(moved) => moved,
+        Err(e) => {
+            std::fs::remove_file(&target).ok();
+            if let Some((_, new_key_file)) = &key_file {
+                std::fs::remove_file(new_key_file).ok();
+            }
+            return Err(e);
+        }
+    };
+
+    drop(std::mem::replace(conn, moved));
+
+    // The move succeeded; leftovers of the old location are only reported
+    let mut warnings = Vec::new();
+    let mut old_files = vec![source.clone(), sidecar(&source, "-wal"), sidecar(&source, "-shm")];
+    old_files.extend(key_file.map(|(old_key_file, _)| old_key_file));
+    for path in old_files {
+        if let Err(e) = remove_file_if_exists(&path) {
+            warnings.push(e);
+        }
+    }
+    let old_backups = backup::backups_dir(&source);
+    let new_backups = backup::backups_dir(&target);
+    if old_backups != new_backups {
+        if let Err(e) = move_backups(&old_backups, &new_backups) {
+            warnings.push(format!("Failed to move backups: {}", e));
+        }
+    }
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+
+    Ok(MovedDatabase { path: target, warnings })
+}
+
+fn move_backups(from: &Path, to: &Path) -> Result<(), String> {
+    let backups = backup::list_backups(from)?;
+    if backups.is_empty() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    for info in backups {
+        move_file(Path::new(&info.path), &to.join(&info.file_name))?;
+    }
+    std::fs::remove_dir(from).ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_location_precedence_and_db_flag() {
+        let inputs = || LocationInputs {
+            flag: Some(PathBuf::from("/flag/devfocus.db")),
+            env: Some(PathBuf::from("/env/work.db")),
+            portable_dir: Some(PathBuf::from("/usb/devfocus-data")),
+            settings: Some(PathBuf::from("/settings/devfocus.db")),
+            default_dir: PathBuf::from("/home/me/.local/share/devfocus"),
+        };
+
+        assert_eq!(resolve(inputs()), (PathBuf::from("/flag/devfocus.db"), DatabaseLocationSource::CliFlag));
+        let no_flag = LocationInputs { flag: None, ..inputs() };
+        assert_eq!(resolve(no_flag), (PathBuf::from("/env/work.db"), DatabaseLocationSource::Env));
+        let portable = LocationInputs { flag: None, env: None, ..inputs() };
+        assert_eq!(
+            resolve(portable),
+            (PathBuf::from("/usb/devfocus-data/devfocus.db"), DatabaseLocationSource::Portable)
+        );
+        let settings = LocationInputs { flag: None, env: None, portable_dir: None, ..inputs() };
+        assert_eq!(resolve(settings).1, DatabaseLocationSource::SettingsFile);
+        let default = LocationInputs {
+            default_dir: PathBuf::from("/data"),
+            ..Default::default()
+        };
+        assert_eq!(resolve(default), (PathBuf::from("/data/devfocus.db"), DatabaseLocationSource::Default));
+
+        let mut args: Vec<String> = ["--db", "/tmp/x.db", "status"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(take_db_flag(&mut args).unwrap(), Some(PathBuf::from("/tmp/x.db")));
+        assert_eq!(args, vec!["status"]);
+        let mut args: Vec<String> = vec!["status".into(), "--db=/tmp/y.db".into()];
+        assert_eq!(take_db_flag(&mut args).unwrap(), Some(PathBuf::from("/tmp/y.db")));
+        let mut args: Vec<String> = vec!["status".into(), "--db".into()];
+        assert!(take_db_flag(&mut args).is_err());
+    }
+
+    #[test]
+    fn test_move_database_relocates_data_backups_and_key_file() {
+        let dir = TempDir::new("location");
+        let source = dir.path().join("old").join("devfocus.db");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        let mut conn = db::open_db(&source, None).unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES ('t1', 'Task', 'todo', ?1, ?1)",
+            ["2025-05-01T08:00:00+00:00"],
+        )
+        .unwrap();
+        let backups = backup::backups_dir(&source);
+        let info = backup::create_backup(&conn, &backups, crate::models::BackupKind::Manual, chrono::Utc::now()).unwrap();
+        std::fs::write(source.with_extension("key"), "not used here").unwrap();
+
+        let location_file = dir.path().join("config").join("location.json");
+        assert!(move_database(&mut conn, Path::new("relative.db"), None, &location_file).is_err());
+
+        // A folder target gets the default file name
+        let target_dir = dir.path().join("new");
+        std::fs::create_dir_all(&target_dir).unwrap();
+        let moved = move_database(&mut conn, &target_dir, None, &location_file).unwrap();
+        let target = moved.path;
+        assert_eq!(target, target_dir.join("devfocus.db"));
+        assert!(moved.warnings.is_empty());
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(conn.path().map(PathBuf::from), Some(target.clone()));
+        assert!(!source.exists());
+        assert!(target.with_extension("key").exists());
+        assert!(!source.with_extension("key").exists());
+        assert!(backup::backups_dir(&target).join(&info.file_name).exists());
+        assert_eq!(read_location_file(&location_file), Some(target.clone()));
+
+        // Moving onto an existing file is refused and leaves the database in place
+        assert!(move_database(&mut conn, &target, None, &location_file).is_err());
+        assert_eq!(conn.path().map(PathBuf::from), Some(target.clone()));
+
+        // So is a target whose key file is already taken, before anything is copied
+        let taken = dir.path().join("taken");
+        std::fs::create_dir_all(&taken).unwrap();
+        std::fs::write(taken.join("devfocus.key"), "someone else's").unwrap();
+        assert!(move_database(&mut conn, &taken, None, &location_file).is_err());
+        assert!(!taken.join("devfocus.db").exists());
+        assert_eq!(conn.path().map(PathBuf::from), Some(target.clone()));
+        assert_eq!(read_location_file(&location_file), Some(target));
+    }
+}
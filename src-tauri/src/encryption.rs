@@ -0,0 +1,348 @@
+use crate::db;
+use crate::db_location;
+use crate::models::DatabaseEncryptionStatus;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+/// Passphrase for the database, read by the app at startup and by the `devfocus` CLI
+pub const KEY_ENV: &str = "DEVFOCUS_DB_KEY";
+/// Overrides where the key file is looked up
+pub const KEY_FILE_ENV: &str = "DEVFOCUS_DB_KEY_FILE";
+
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// First bytes of every plaintext SQLite file; SQLCipher encrypts the header too
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Whether this build links SQLCipher (the `encryption` cargo feature).
+/// Plain SQLite silently ignores `PRAGMA key`, so this must be checked first.
+pub fn cipher_available() -> bool {
+    Connection::open_in_memory()
+        .and_then(|conn| conn.query_row("PRAGMA cipher_version", [], |row| row.get::<_, String>(0)))
+        .is_ok()
+}
+
+/// A non-empty file without the SQLite header is an encrypted (or damaged) database
+pub fn is_encrypted(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut header = [0u8; 16];
+    match std::fs::File::open(path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => &header != SQLITE_HEADER,
+        Err(_) => false,
+    }
+}
+
+pub fn connection_is_encrypted(conn: &Connection) -> bool {
+    conn.path()
+        .filter(|path| !path.is_empty())
+        .is_some_and(|path| is_encrypted(Path::new(path)))
+}
+
+/// The key file lives next to the database unless `DEVFOCUS_DB_KEY_FILE` points elsewhere
+pub fn key_file_path(db_path: &Path) -> PathBuf {
+    match std::env::var_os(KEY_FILE_ENV) {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => db_path.with_extension("key"),
+    }
+}
+
+/// Passphrase from `DEVFOCUS_DB_KEY` or, failing that, from the key file
+pub fn stored_key(db_path: &Path) -> Result<Option<String>, String> {
+    if let Ok(key) = std::env::var(KEY_ENV) {
+        if !key.is_empty() {
+            return Ok(Some(key));
+        }
+    }
+
+    let path = key_file_path(db_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read key file {}: {}", path.display(), e))?;
+    let key = content.trim_end_matches(['\r', '\n']);
+    if key.is_empty() {
+        return Err(format!("Key file {} is empty", path.display()));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Key to open the database with at startup: the stored one, but only for an encrypted file
+pub fn startup_key(db_path: &Path) -> Option<String> {
+    if !is_encrypted(db_path) {
+        return None;
+    }
+    stored_key(db_path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        None
+    })
+}
+
+/// Write the passphrase to the key file, readable by the current user only
+pub fn save_key_file(db_path: &Path, key: &str) -> Result<PathBuf, String> {
+    use std::io::Write;
+
+    let path = key_file_path(db_path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(key.as_bytes()))
+        .map_err(|e| format!("Failed to write key file {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub fn remove_key_file(db_path: &Path) -> Result<(), String> {
+    db_location::remove_file_if_exists(&key_file_path(db_path))
+}
+
+pub fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.trim().chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("Passphrase must have at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    Ok(())
+}
+
+fn require_cipher() -> Result<(), String> {
+    if !cipher_available() {
+        return Err("This build has no database encryption support".to_string());
+    }
+    Ok(())
+}
+
+/// Set the key on a freshly opened connection, before anything reads the file
+pub fn apply_key(conn: &Connection, key: &str) -> rusqlite::Result<()> {
+    conn.pragma_update(None, "key", key)
+}
+
+pub fn get_status(conn: &Connection, db_path: &Path, locked: bool) -> DatabaseEncryptionStatus {
+    let key_file = key_file_path(db_path);
+    DatabaseEncryptionStatus {
+        available: cipher_available(),
+        encrypted: locked || connection_is_encrypted(conn),
+        locked,
+        key_file: key_file.exists().then(|| key_file.to_string_lossy().to_string()),
+    }
+}
+
+/// Open the encrypted database with a passphrase typed by the user
+pub fn unlock(db_path: &Path, passphrase: &str) -> Result<Connection, String> {
+    require_cipher()?;
+    db::open_db(db_path, Some(passphrase)).map_err(|e| match e.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::NotADatabase) => "Wrong passphrase".to_string(),
+        _ => format!("Failed to open database: {}", e),
+    })
+}
+
+/// Write a copy of the connection's main database to `dest`, encrypted with `key`
+/// or in plaintext when `key` is None
+fn export_copy(conn: &Connection, dest: &Path, key: Option<&str>) -> Result<(), String> {
+    db_location::remove_file_if_exists(dest)?;
+    let dest_str = dest.to_str().ok_or_else(|| format!("Invalid path: {}", dest.display()))?;
+    let version: i64 = conn
+        .query_row("PRAGMA main.user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    conn.execute("ATTACH DATABASE ?1 AS export KEY ?2", [dest_str, key.unwrap_or("")])
+        .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let exported = conn
+        .query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()))
+        .and_then(|_| conn.pragma_update(Some(rusqlite::DatabaseName::Attached("export")), "user_version", version));
+    let detached = conn.execute("DETACH DATABASE export", []);
+
+    exported.and(detached).map_err(|e| {
+        std::fs::remove_file(dest).ok();
+        format!("Failed to export database: {}", e)
+    })?;
+    Ok(())
+}
+
+fn live_path(conn: &Connection) -> Result<PathBuf, String> {
+    conn.path()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| "The database is not stored in a file".to_string())
+}
+
+/// Replace the live database file with `replacement` and reopen the connection with
+/// `new_key`. The connection is closed first so no stale WAL is left for the new file;
+/// if the swap fails the original file is reopened with `old_key`.
+fn swap_in(conn: &mut Connection, replacement: &Path, old_key: Option<&str>, new_key: Option<&str>) -> Result<(), String> {
+    let path = live_path(conn)?;
+    let placeholder = db::locked_placeholder().map_err(|e| e.to_string())?;
+    let old = std::mem::replace(conn, placeholder);
+    if let Err((old, e)) = old.close() {
+        *conn = old;
+        return Err(format!("Failed to close the database: {}", e));
+    }
+
+    let swapped = ["-wal", "-shm"]
+        .iter()
+        .try_for_each(|suffix| {
+            let mut sidecar = path.clone().into_os_string();
+            sidecar.push(suffix);
+            db_location::remove_file_if_exists(Path::new(&sidecar))
+        })
+        .and_then(|_| {
+            std::fs::rename(replacement, &path).map_err(|e| format!("Failed to replace the database file: {}", e))
+        });
+
+    let key = if swapped.is_ok() { new_key } else { old_key };
+    *conn = db::open_db(&path, key).map_err(|e| format!("Failed to reopen the database: {}", e))?;
+    swapped
+}
+
+fn scratch_path(path: &Path, purpose: &str) -> PathBuf {
+    let mut scratch = path.to_path_buf().into_os_string();
+    scratch.push(format!(".{}", purpose));
+    PathBuf::from(scratch)
+}
+
+/// Encrypt a plaintext database in place: an encrypted copy is written next to it
+/// and then atomically renamed over the original
+pub fn encrypt_in_place(conn: &mut Connection, passphrase: &str) -> Result<(), String> {
+    require_cipher()?;
+    validate_passphrase(passphrase)?;
+    if connection_is_encrypted(conn) {
+        return Err("The database is already encrypted".to_string());
+    }
+
+    let scratch = scratch_path(&live_path(conn)?, "encrypting");
+    export_copy(conn, &scratch, Some(passphrase))?;
+    swap_in(conn, &scratch, None, Some(passphrase))
+}
+
+/// Turn an encrypted database back into a plaintext one
+pub fn decrypt_in_place(conn: &mut Connection, current_key: &str) -> Result<(), String> {
+    require_cipher()?;
+    if !connection_is_encrypted(conn) {
+        return Err("The database is not encrypted".to_string());
+    }
+
+    let scratch = scratch_path(&live_path(conn)?, "decrypting");
+    export_copy(conn, &scratch, None)?;
+    swap_in(conn, &scratch, Some(current_key), None)
+}
+
+/// Change the passphrase of an encrypted database
+pub fn rekey(conn: &Connection, new_passphrase: &str) -> Result<(), String> {
+    require_cipher()?;
+    validate_passphrase(new_passphrase)?;
+    if !connection_is_encrypted(conn) {
+        return Err("The database is not encrypted".to_string());
+    }
+
+    conn.pragma_update(None, "rekey", new_passphrase)
+        .map_err(|e| format!("Failed to change the passphrase: {}", e))
+}
+
+/// Replace an encrypted live database with `source` (a backup, plaintext or encrypted
+/// with `key`), re-encrypting it with `key`. SQLite's backup API refuses encrypted databases.
+pub fn replace_from(conn: &mut Connection, source: &Path, key: &str) -> Result<(), String> {
+    require_cipher()?;
+    let source_conn = Connection::open(source).map_err(|e| format!("Cannot open backup: {}", e))?;
+    if is_encrypted(source) {
+        apply_key(&source_conn, key).map_err(|e| e.to_string())?;
+    }
+
+    let scratch = scratch_path(&live_path(conn)?, "restoring");
+    export_copy(&source_conn, &scratch, Some(key))?;
+    drop(source_conn);
+    swap_in(conn, &scratch, Some(key), Some(key))
+}
+
+/// Open a backup file read-only, with the live key if the backup is encrypted
+pub fn open_read_only(path: &Path, key: Option<&str>) -> rusqlite::Result<Connection> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    if let Some(key) = key.filter(|_| is_encrypted(path)) {
+        apply_key(&conn, key)?;
+    }
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_detects_plaintext_databases_and_reads_key_files() {
+        let dir = TempDir::new("encryption");
+        let db_path = dir.path().join("devfocus.db");
+        assert!(!is_encrypted(&db_path));
+
+        let conn = db::open_db(&db_path, None).unwrap();
+        assert!(!is_encrypted(&db_path));
+        assert!(!connection_is_encrypted(&conn));
+
+        let garbage = dir.path().join("garbage.db");
+        std::fs::write(&garbage, [7u8; 64]).unwrap();
+        assert!(is_encrypted(&garbage));
+
+        assert_eq!(stored_key(&db_path).unwrap(), None);
+        let key_file = save_key_file(&db_path, "correct horse").unwrap();
+        assert_eq!(key_file, dir.path().join("devfocus.key"));
+        std::fs::write(&key_file, "correct horse\n").unwrap();
+        assert_eq!(stored_key(&db_path).unwrap().as_deref(), Some("correct horse"));
+        // Plaintext databases are opened without the stored key
+        assert_eq!(startup_key(&db_path), None);
+
+        remove_key_file(&db_path).unwrap();
+        assert!(!key_file.exists());
+        assert!(validate_passphrase("short").is_err());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_encrypt_rekey_and_decrypt_in_place() {
+        use crate::backup;
+        use crate::models::BackupKind;
+        use chrono::Utc;
+
+        let dir = TempDir::new("encryption");
+        let db_path = dir.path().join("devfocus.db");
+        let mut conn = db::open_db(&db_path, None).unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES ('t1', 'Task', 'todo', ?1, ?1)",
+            ["2025-05-01T08:00:00+00:00"],
+        )
+        .unwrap();
+
+        encrypt_in_place(&mut conn, "first passphrase").unwrap();
+        assert!(is_encrypted(&db_path));
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+        assert!(encrypt_in_place(&mut conn, "first passphrase").is_err());
+
+        rekey(&conn, "second passphrase").unwrap();
+        drop(conn);
+        assert_eq!(unlock(&db_path, "first passphrase").unwrap_err(), "Wrong passphrase");
+        let mut conn = unlock(&db_path, "second passphrase").unwrap();
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, db::SCHEMA_VERSION);
+
+        // Backups stay encrypted and restore into the encrypted database
+        let backups = dir.path().join("backups");
+        let backup = backup::create_backup(&conn, &backups, BackupKind::Manual, Utc::now()).unwrap();
+        assert!(is_encrypted(Path::new(&backup.path)));
+        conn.execute("DELETE FROM tasks", []).unwrap();
+        backup::restore_backup(&mut conn, Path::new(&backup.path), &backups, Some("second passphrase"), Utc::now())
+            .unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        decrypt_in_place(&mut conn, "second passphrase").unwrap();
+        assert!(!is_encrypted(&db_path));
+        drop(conn);
+        let conn = db::open_db(&db_path, None).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+}
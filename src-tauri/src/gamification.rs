@@ -0,0 +1,194 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Points awarded for every completed subtask
+pub const BASE_POINTS: i64 = 10;
+
+/// Extra points for completing a subtask within its estimate, or in under
+/// 25 minutes when it has none
+pub const EFFICIENCY_BONUS_POINTS: i64 = 5;
+pub const EFFICIENCY_THRESHOLD_SECONDS: i64 = 1500;
+
+/// Extra points for tasks with at least 5 subtasks
+pub const COMPLEXITY_BONUS_POINTS: i64 = 20;
+pub const COMPLEXITY_MIN_SUBTASKS: i64 = 5;
+
+/// Streak lengths in days celebrated in the UI and announced to webhooks
+pub const STREAK_MILESTONES: [i64; 5] = [7, 14, 30, 60, 100];
+
+/// Point values used everywhere points are computed; the defaults are the constants
+/// above and the user can change them in the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoringRules {
+    pub base_points: i64,
+    pub efficiency_bonus_points: i64,
+    pub efficiency_threshold_seconds: i64,
+    pub complexity_bonus_points: i64,
+    pub complexity_min_subtasks: i64,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            base_points: BASE_POINTS,
+            efficiency_bonus_points: EFFICIENCY_BONUS_POINTS,
+            efficiency_threshold_seconds: EFFICIENCY_THRESHOLD_SECONDS,
+            complexity_bonus_points: COMPLEXITY_BONUS_POINTS,
+            complexity_min_subtasks: COMPLEXITY_MIN_SUBTASKS,
+        }
+    }
+}
+
+/// Whether a subtask was completed fast enough to earn the efficiency bonus
+pub fn is_efficient(rules: &ScoringRules, duration_seconds: i64, estimate_seconds: Option<i64>) -> bool {
+    match estimate_seconds {
+        Some(estimate) if estimate > 0 => duration_seconds <= estimate,
+        _ => duration_seconds < rules.efficiency_threshold_seconds,
+    }
+}
+
+/// Calculate points for a completed subtask
+/// Formula (defaults): 10 base + 5 if completed within its estimate (or in under 25 minutes)
+pub fn calculate_subtask_points(rules: &ScoringRules, duration_seconds: i64, estimate_seconds: Option<i64>) -> i64 {
+    if is_efficient(rules, duration_seconds, estimate_seconds) {
+        rules.base_points + rules.efficiency_bonus_points
+    } else {
+        rules.base_points
+    }
+}
+
+/// Calculate level from total XP
+/// Formula: level = floor(sqrt(xp / 100)) + 1
+pub fn calculate_level(xp: i64) -> i64 {
+    if xp <= 0 {
+        return 1;
+    }
+    let level = ((xp as f64 / 100.0).sqrt().floor() as i64) + 1;
+    level.max(1)
+}
+
+/// Calculate XP required for a specific level
+/// Formula: xp = (level - 1)^2 * 100
+pub fn calculate_xp_for_level(level: i64) -> i64 {
+    ((level - 1).pow(2)) * 100
+}
+
+/// Get XP needed for next level
+pub fn get_xp_for_next_level(current_level: i64) -> i64 {
+    calculate_xp_for_level(current_level + 1)
+}
+
+/// Calculate progress percentage to next level
+pub fn calculate_progress_percentage(total_xp: i64, current_level: i64) -> f64 {
+    let xp_current_level = calculate_xp_for_level(current_level);
+    let xp_next_level = get_xp_for_next_level(current_level);
+    let xp_in_current_level = total_xp - xp_current_level;
+    let xp_needed_for_next = xp_next_level - xp_current_level;
+
+    if xp_needed_for_next <= 0 {
+        return 100.0;
+    }
+
+    ((xp_in_current_level as f64 / xp_needed_for_next as f64) * 100.0).clamp(0.0, 100.0)
+}
+
+/// Calculate global level from total XP (uses different formula than categories)
+/// Formula: level = floor(sqrt(xp / 500)) + 1
+pub fn calculate_global_level(xp: i64) -> i64 {
+    if xp <= 0 {
+        return 1;
+    }
+    let level = ((xp as f64 / 500.0).sqrt().floor() as i64) + 1;
+    level.max(1)
+}
+
+/// Calculate XP required for a specific global level
+/// Formula: xp = (level - 1)^2 * 500
+pub fn calculate_xp_for_global_level(level: i64) -> i64 {
+    ((level - 1).pow(2)) * 500
+}
+
+/// Get XP needed for next global level
+pub fn get_xp_for_next_global_level(current_level: i64) -> i64 {
+    calculate_xp_for_global_level(current_level + 1)
+}
+
+/// Get title based on global level
+pub fn get_title_for_level(level: i64) -> String {
+    match level {
+        1..=4 => "novice".to_string(),
+        5..=9 => "junior".to_string(),
+        10..=14 => "mid".to_string(),
+        15..=19 => "senior".to_string(),
+        20..=24 => "expert".to_string(),
+        25..=29 => "master".to_string(),
+        _ => "legend".to_string(),
+    }
+}
+
+/// Calculate streak bonus percentage
+/// +5% per week, maximum 50%
+pub fn calculate_streak_bonus(streak_days: i64) -> f64 {
+    let weeks = (streak_days / 7) as f64;
+    (weeks * 0.05).min(0.50)
+}
+
+/// A stored streak only still counts if its last work day was `today` or yesterday, both
+/// local dates in the user's timezone
+pub fn effective_streak(current_streak: i64, last_work_date: Option<&str>, today: chrono::NaiveDate) -> i64 {
+    let yesterday = today - chrono::Duration::days(1);
+    let worked_recently = last_work_date
+        .and_then(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .is_some_and(|date| date >= yesterday);
+    if worked_recently {
+        current_streak
+    } else {
+        0
+    }
+}
+
+/// Append an XP gain to the ledger so historical timelines can be rebuilt
+pub fn record_xp_gain(
+    conn: &Connection,
+    category_id: &str,
+    subtask_id: Option<&str>,
+    xp: i64,
+    earned_at: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO xp_ledger (id, category_id, subtask_id, xp, earned_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![Uuid::new_v4().to_string(), category_id, subtask_id, xp, earned_at],
+    )
+    .map_err(|e| format!("Failed to record XP gain: {}", e))?;
+
+    Ok(())
+}
+
+/// Update a profile with its latest global level and XP
+pub fn update_user_profile_level(conn: &Connection, profile_id: &str) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    // Calculate total XP from all of the profile's categories
+    let total_xp: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(total_xp), 0) FROM category_experience WHERE profile_id = ?1",
+            [profile_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to calculate total XP: {}", e))?;
+
+    // Calculate global level
+    let level = calculate_global_level(total_xp);
+    let title = get_title_for_level(level);
+
+    // Update user profile
+    conn.execute(
+        "UPDATE user_profile SET level = ?1, total_xp = ?2, current_title = ?3, updated_at = ?4 WHERE id = ?5",
+        params![level, total_xp, &title, &now, profile_id],
+    )
+    .map_err(|e| format!("Failed to update user profile: {}", e))?;
+
+    Ok(())
+}
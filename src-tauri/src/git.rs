@@ -0,0 +1,454 @@
+use crate::analytics::load_running_intervals;
+use crate::models::*;
+use crate::profiles::active_profile_id;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result as SqlResult};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use uuid::Uuid;
+
+/// Commit messages reference a subtask as `#` followed by the first 8 characters of its id
+const SHORT_ID_LEN: usize = 8;
+
+const FIELD_SEPARATOR: char = '\u{1f}';
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+#[derive(Debug, Clone)]
+pub struct GitCommit {
+    pub hash: String,
+    pub author: String,
+    pub committed_at: DateTime<Utc>,
+    pub message: String,
+}
+
+impl GitCommit {
+    fn summary(&self) -> &str {
+        self.message.lines().next().unwrap_or("")
+    }
+}
+
+fn run_git(repo_path: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed in {}: {}",
+            args.first().unwrap_or(&""),
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| e.to_string())
+}
+
+/// Top-level directory of the work tree containing `path`
+pub fn repository_root(path: &Path) -> Result<String, String> {
+    Ok(run_git(path, &["rev-parse", "--show-toplevel"])?.trim().to_string())
+}
+
+/// Commits on any local branch committed at or after `since`
+pub fn read_commits(repo_path: &Path, since: DateTime<Utc>) -> Result<Vec<GitCommit>, String> {
+    let format = format!("--format=%H{0}%cI{0}%an{0}%B{1}", FIELD_SEPARATOR, RECORD_SEPARATOR);
+    let since = format!("--since={}", since.to_rfc3339());
+    let output = run_git(repo_path, &["log", "--branches", "--no-color", &since, &format])?;
+
+    let mut commits = Vec::new();
+    for record in output.split(RECORD_SEPARATOR) {
+        let fields: Vec<&str> = record.trim_start_matches('\n').splitn(4, FIELD_SEPARATOR).collect();
+        let [hash, committed_at, author, message] = fields.as_slice() else {
+            continue;
+        };
+        let committed_at = DateTime::parse_from_rfc3339(committed_at)
+            .map_err(|e| format!("Invalid commit date '{}': {}", committed_at, e))?
+            .with_timezone(&Utc);
+
+        commits.push(GitCommit {
+            hash: hash.to_string(),
+            author: author.to_string(),
+            committed_at,
+            message: message.trim_end().to_string(),
+        });
+    }
+    Ok(commits)
+}
+
+/// Short ids (`#1a2b3c4d`) mentioned in a commit message, lowercased
+fn referenced_short_ids(message: &str) -> Vec<String> {
+    message
+        .split('#')
+        .skip(1)
+        .filter_map(|rest| {
+            let candidate: String = rest.chars().take(SHORT_ID_LEN).collect();
+            let boundary = rest.chars().nth(SHORT_ID_LEN).map_or(true, |c| !c.is_ascii_alphanumeric());
+            (candidate.len() == SHORT_ID_LEN && candidate.chars().all(|c| c.is_ascii_hexdigit()) && boundary)
+                .then(|| candidate.to_ascii_lowercase())
+        })
+        .collect()
+}
+
+/// Link commits to the task's subtasks. A commit referencing subtasks by short id is linked
+/// to those only; otherwise it is linked to every subtask whose running interval contains it.
+/// Returns how many new links were stored.
+pub fn attribute_commits(
+    conn: &Connection,
+    task_id: &str,
+    repository_id: &str,
+    commits: &[GitCommit],
+) -> Result<i64, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM subtasks WHERE task_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let subtask_ids = stmt
+        .query_map([task_id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+
+    let intervals: Vec<_> = load_running_intervals(conn)?
+        .into_iter()
+        .filter(|interval| subtask_ids.contains(&interval.subtask_id))
+        .collect();
+
+    let mut links_created = 0;
+    for commit in commits {
+        let references = referenced_short_ids(&commit.message);
+        let referenced: Vec<(&String, CommitLinkReason)> = subtask_ids
+            .iter()
+            .filter(|id| references.iter().any(|short| id.to_ascii_lowercase().starts_with(short)))
+            .map(|id| (id, CommitLinkReason::Reference))
+            .collect();
+
+        let targets = if referenced.is_empty() {
+            intervals
+                .iter()
+                .filter(|interval| interval.start <= commit.committed_at && commit.committed_at <= interval.end)
+                .map(|interval| (&interval.subtask_id, CommitLinkReason::Session))
+                .collect()
+        } else {
+            referenced
+        };
+
+        // A subtask matched twice is ignored by the unique (subtask_id, commit_hash) constraint
+        for (subtask_id, reason) in targets {
+            links_created += conn
+                .execute(
+                    "INSERT OR IGNORE INTO commit_links
+                        (id, subtask_id, repository_id, commit_hash, summary, author, committed_at, reason)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        subtask_id,
+                        repository_id,
+                        &commit.hash,
+                        commit.summary(),
+                        &commit.author,
+                        commit.committed_at.to_rfc3339(),
+                        reason.as_str(),
+                    ],
+                )
+                .map_err(|e| e.to_string())? as i64;
+        }
+    }
+    Ok(links_created)
+}
+
+pub fn add_repository(conn: &Connection, task_id: &str, path: &Path) -> Result<GitRepository, String> {
+    let exists: bool = conn
+        .query_row("SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ?1)", [task_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Task not found: {}", task_id));
+    }
+
+    let root = repository_root(path)?;
+    let repository = GitRepository {
+        id: Uuid::new_v4().to_string(),
+        task_id: task_id.to_string(),
+        path: root,
+        created_at: Utc::now().to_rfc3339(),
+        last_scanned_at: None,
+    };
+
+    conn.execute(
+        "INSERT INTO git_repositories (id, task_id, path, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![&repository.id, &repository.task_id, &repository.path, &repository.created_at],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("{} is already linked to this task", repository.path)
+        }
+        e => e.to_string(),
+    })?;
+
+    Ok(repository)
+}
+
+/// Repositories configured for a task, or for every task of the active profile when `task_id` is None
+pub fn list_repositories(conn: &Connection, task_id: Option<&str>) -> Result<Vec<GitRepository>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.id, r.task_id, r.path, r.created_at, r.last_scanned_at FROM git_repositories r
+             JOIN tasks t ON r.task_id = t.id
+             WHERE (?1 IS NULL OR r.task_id = ?1) AND t.profile_id = ?2
+             ORDER BY r.created_at",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let repositories = stmt
+        .query_map(params![task_id, active_profile_id(conn)?], |row| {
+            Ok(GitRepository {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                path: row.get(2)?,
+                created_at: row.get(3)?,
+                last_scanned_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(repositories)
+}
+
+/// A configured repository and the creation time of its task, which bounds the commits read
+pub struct ScanTarget {
+    pub repository: GitRepository,
+    pub since: DateTime<Utc>,
+}
+
+/// Repositories to scan for the task, or for every task of the active profile
+pub fn scan_targets(conn: &Connection, task_id: Option<&str>) -> Result<Vec<ScanTarget>, String> {
+    list_repositories(conn, task_id)?
+        .into_iter()
+        .map(|repository| {
+            let task_created_at: String = conn
+                .query_row("SELECT created_at FROM tasks WHERE id = ?1", [&repository.task_id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            let since = DateTime::parse_from_rfc3339(&task_created_at)
+                .map_err(|e| format!("Invalid task creation date '{}': {}", task_created_at, e))?
+                .with_timezone(&Utc);
+            Ok(ScanTarget { repository, since })
+        })
+        .collect()
+}
+
+/// Run git for every target. Doesn't touch the database, so callers can release it meanwhile.
+pub fn read_targets(targets: Vec<ScanTarget>) -> Vec<(GitRepository, Result<Vec<GitCommit>, String>)> {
+    targets
+        .into_iter()
+        .map(|target| {
+            let commits = read_commits(Path::new(&target.repository.path), target.since);
+            (target.repository, commits)
+        })
+        .collect()
+}
+
+/// Store the commits read by `read_targets`. A repository that couldn't be read is reported
+/// in `errors` without stopping the scan; one removed while git ran is skipped.
+pub fn store_scan_results(
+    conn: &Connection,
+    results: Vec<(GitRepository, Result<Vec<GitCommit>, String>)>,
+    now: DateTime<Utc>,
+) -> Result<GitScanSummary, String> {
+    let mut summary = GitScanSummary::default();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    for (repository, commits) in results {
+        let commits = match commits {
+            Ok(commits) => commits,
+            Err(e) => {
+                summary.errors.push(e);
+                continue;
+            }
+        };
+
+        let still_linked = tx
+            .execute(
+                "UPDATE git_repositories SET last_scanned_at = ?1 WHERE id = ?2",
+                params![now.to_rfc3339(), &repository.id],
+            )
+            .map_err(|e| e.to_string())?
+            > 0;
+        if !still_linked {
+            continue;
+        }
+
+        summary.repositories_scanned += 1;
+        summary.commits_scanned += commits.len() as i64;
+        summary.links_created += attribute_commits(&tx, &repository.task_id, &repository.id, &commits)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+/// Linked commits of a task, keyed by subtask id, oldest first
+pub fn commit_links_for_task(conn: &Connection, task_id: &str) -> Result<HashMap<String, Vec<CommitLink>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT cl.subtask_id, cl.repository_id, r.path, cl.commit_hash, cl.summary, cl.author,
+                    cl.committed_at, cl.reason
+             FROM commit_links cl
+             JOIN subtasks s ON cl.subtask_id = s.id
+             JOIN git_repositories r ON cl.repository_id = r.id
+             WHERE s.task_id = ?1
+             ORDER BY cl.committed_at, cl.commit_hash",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut links: HashMap<String, Vec<CommitLink>> = HashMap::new();
+    for row in stmt
+        .query_map([task_id], |row| {
+            Ok(CommitLink {
+                subtask_id: row.get(0)?,
+                repository_id: row.get(1)?,
+                repository_path: row.get(2)?,
+                commit_hash: row.get(3)?,
+                summary: row.get(4)?,
+                author: row.get(5)?,
+                committed_at: row.get(6)?,
+                reason: CommitLinkReason::from_str(&row.get::<_, String>(7)?),
+            })
+        })
+        .map_err(|e| e.to_string())?
+    {
+        let link = row.map_err(|e| e.to_string())?;
+        links.entry(link.subtask_id.clone()).or_default().push(link);
+    }
+    Ok(links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use crate::test_support::TempDir;
+
+    const SUBTASK_A: &str = "a1b2c3d4-0000-4000-8000-000000000001";
+    const SUBTASK_B: &str = "b5e6f7a8-0000-4000-8000-000000000002";
+
+    fn seeded_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at)
+             VALUES ('t1', 'Login', 'in_progress', '2025-07-01T08:00:00+00:00', '2025-07-01T08:00:00+00:00');
+             INSERT INTO git_repositories (id, task_id, path, created_at)
+             VALUES ('r1', 't1', '/tmp/login', '2025-07-01T08:00:00+00:00');",
+        )
+        .unwrap();
+        for id in [SUBTASK_A, SUBTASK_B] {
+            conn.execute(
+                "INSERT INTO subtasks (id, task_id, title, status, created_at, updated_at)
+                 VALUES (?1, 't1', ?1, 'done', '2025-07-01T08:00:00+00:00', '2025-07-01T08:00:00+00:00')",
+                [id],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+             VALUES ('ts1', ?1, '2025-07-01T09:00:00+00:00', '2025-07-01T11:00:00+00:00', 3600)",
+            [SUBTASK_A],
+        )
+        .unwrap();
+        conn.execute_batch(
+            "INSERT INTO session_intervals (id, session_id, started_at, ended_at)
+             VALUES ('i1', 'ts1', '2025-07-01T09:00:00+00:00', '2025-07-01T09:30:00+00:00'),
+                    ('i2', 'ts1', '2025-07-01T10:30:00+00:00', '2025-07-01T11:00:00+00:00');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn commit(hash: &str, at: &str, message: &str) -> GitCommit {
+        GitCommit {
+            hash: hash.to_string(),
+            author: "Ada".to_string(),
+            committed_at: DateTime::parse_from_rfc3339(at).unwrap().with_timezone(&Utc),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_referenced_short_ids() {
+        assert_eq!(referenced_short_ids("Fix login (#A1B2C3D4)"), vec!["a1b2c3d4"]);
+        assert!(referenced_short_ids("Closes #12 and #a1b2c3d4e5").is_empty());
+        assert!(referenced_short_ids("No references").is_empty());
+    }
+
+    #[test]
+    fn test_attributes_by_interval_and_reference() {
+        let conn = seeded_connection();
+        let commits = vec![
+            commit("c1", "2025-07-01T09:10:00+00:00", "Add form"),
+            // Between the two intervals, while the session was paused
+            commit("c2", "2025-07-01T10:00:00+00:00", "Unrelated"),
+            // Inside A's interval but explicitly about B
+            commit("c3", "2025-07-01T10:45:00+00:00", "Validate email\n\nRefs #b5e6f7a8"),
+        ];
+
+        assert_eq!(attribute_commits(&conn, "t1", "r1", &commits).unwrap(), 2);
+        // Rescanning doesn't duplicate links
+        assert_eq!(attribute_commits(&conn, "t1", "r1", &commits).unwrap(), 0);
+
+        let links = commit_links_for_task(&conn, "t1").unwrap();
+        let a = &links[SUBTASK_A];
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].commit_hash, "c1");
+        assert_eq!(a[0].reason, CommitLinkReason::Session);
+        let b = &links[SUBTASK_B];
+        assert_eq!(b[0].commit_hash, "c3");
+        assert_eq!(b[0].summary, "Validate email");
+        assert_eq!(b[0].reason, CommitLinkReason::Reference);
+    }
+
+    #[test]
+    fn test_reads_commits_from_a_real_repository() {
+        assert!(
+            Command::new("git").arg("--version").output().is_ok(),
+            "this test needs the git executable on PATH"
+        );
+        let dir = TempDir::new("git");
+        let git = |args: &[&str], date: &str| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com", "-c", "commit.gpgsign=false"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"], "2025-07-01T08:00:00+00:00");
+        git(&["commit", "-q", "--allow-empty", "-m", "Before the task"], "2025-06-01T08:00:00+00:00");
+        git(&["commit", "-q", "--allow-empty", "-m", "Add form\n\nBody line"], "2025-07-01T09:10:00+00:00");
+
+        let since = DateTime::parse_from_rfc3339("2025-07-01T08:00:00+00:00").unwrap().with_timezone(&Utc);
+        let commits = read_commits(dir.path(), since).unwrap();
+
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary(), "Add form");
+        assert_eq!(commits[0].message, "Add form\n\nBody line");
+        assert_eq!(commits[0].author, "Ada");
+        assert_eq!(commits[0].hash.len(), 40);
+        assert_eq!(commits[0].committed_at.to_rfc3339(), "2025-07-01T09:10:00+00:00");
+
+        // The full scan links the commit to the subtask that was running
+        let conn = seeded_connection();
+        conn.execute("UPDATE git_repositories SET path = ?1", [dir.path().to_string_lossy()])
+            .unwrap();
+        let targets = scan_targets(&conn, Some("t1")).unwrap();
+        let summary = store_scan_results(&conn, read_targets(targets), Utc::now()).unwrap();
+        assert_eq!((summary.repositories_scanned, summary.commits_scanned, summary.links_created), (1, 1, 1));
+        assert!(summary.errors.is_empty());
+    }
+}
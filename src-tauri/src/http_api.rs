@@ -0,0 +1,520 @@
+use crate::models::*;
+use crate::profiles::active_profile_id;
+use crate::timer;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tiny_http::{Header, Response, Server};
+use uuid::Uuid;
+
+pub const DEFAULT_PORT: u16 = 47615;
+
+/// Request bodies larger than this are rejected
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+pub fn generate_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Stored configuration, creating a disabled one with a fresh token on first use
+pub fn load_config(conn: &Connection) -> Result<HttpApiConfig, String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO http_api_config (id, enabled, port, token) VALUES (1, 0, ?1, ?2)",
+        params![DEFAULT_PORT, generate_token()],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.query_row("SELECT enabled, port, token FROM http_api_config WHERE id = 1", [], |row| {
+        Ok(HttpApiConfig {
+            enabled: row.get(0)?,
+            port: row.get(1)?,
+            token: row.get(2)?,
+        })
+    })
+    .map_err(|e| e.to_string())
+}
+
+pub fn save_config(conn: &Connection, config: &HttpApiConfig) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO http_api_config (id, enabled, port, token) VALUES (1, ?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET enabled = excluded.enabled, port = excluded.port, token = excluded.token",
+        params![config.enabled, config.port, &config.token],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub struct ApiRequest<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub authorization: Option<&'a str>,
+    pub body: &'a str,
+}
+
+#[derive(Debug)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+    // Whether the request changed timer or task state
+    pub mutated: bool,
+}
+
+impl ApiResponse {
+    fn ok<T: serde::Serialize>(value: T, mutated: bool) -> Self {
+        match serde_json::to_value(value) {
+            Ok(body) => ApiResponse { status: 200, body, mutated },
+            Err(e) => ApiResponse::error(500, e.to_string()),
+        }
+    }
+
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        ApiResponse {
+            status,
+            body: json!({ "error": message.into() }),
+            mutated: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateTaskBody {
+    title: String,
+    description: Option<String>,
+    estimate_seconds: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSubtaskBody {
+    title: String,
+    category_id: Option<String>,
+    estimate_seconds: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DurationBody {
+    // Defaults to the time tracked by the session's intervals
+    duration_seconds: Option<i64>,
+}
+
+/// Compare without short-circuiting so response timing doesn't leak the token
+fn tokens_match(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn parse_body<T: serde::de::DeserializeOwned + Default>(body: &str) -> Result<T, ApiResponse> {
+    if body.trim().is_empty() {
+        return Ok(T::default());
+    }
+    parse_required_body(body)
+}
+
+fn parse_required_body<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, ApiResponse> {
+    serde_json::from_str(body).map_err(|e| ApiResponse::error(400, format!("Invalid JSON body: {}", e)))
+}
+
+/// Status of one of the active profile's subtasks
+fn subtask_status(conn: &Connection, subtask_id: &str) -> Result<Option<SubtaskStatus>, String> {
+    conn.query_row(
+        "SELECT s.status FROM subtasks s JOIN tasks t ON t.id = s.task_id WHERE s.id = ?1 AND t.profile_id = ?2",
+        params![subtask_id, active_profile_id(conn)?],
+        |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map(|status| status.map(|s| SubtaskStatus::from_str(&s)))
+    .map_err(|e| e.to_string())
+}
+
+fn is_running(conn: &Connection, session_id: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM session_intervals WHERE session_id = ?1 AND ended_at IS NULL)",
+        [session_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn duration_or_tracked(conn: &Connection, session: Option<&TimeSession>, requested: Option<i64>) -> Result<i64, String> {
+    match (requested, session) {
+        (Some(seconds), _) => Ok(seconds),
+        (None, Some(session)) => timer::tracked_seconds(conn, &session.id, Utc::now()),
+        (None, None) => Ok(0),
+    }
+}
+
+fn subtask_action(conn: &Connection, subtask_id: &str, action: &str, body: &str) -> Result<ApiResponse, String> {
+    let Some(status) = subtask_status(conn, subtask_id)? else {
+        return Ok(ApiResponse::error(404, format!("Subtask not found: {}", subtask_id)));
+    };
+    let session = timer::active_session(conn, subtask_id)?;
+    let running = match &session {
+        Some(session) => is_running(conn, &session.id)?,
+        None => false,
+    };
+
+    let response = match action {
+        "start" => {
+            if session.is_some() {
+                return Ok(ApiResponse::error(409, "Subtask already has an active session"));
+            }
+            ApiResponse::ok(timer::start_subtask(conn, subtask_id)?, true)
+        }
+        "pause" => {
+            if !running {
+                return Ok(ApiResponse::error(409, "Subtask is not running"));
+            }
+            let body: DurationBody = match parse_body(body) {
+                Ok(body) => body,
+                Err(response) => return Ok(response),
+            };
+            if let Err(e) = timer::validate_duration(body.duration_seconds) {
+                return Ok(ApiResponse::error(400, e));
+            }
+            let duration = duration_or_tracked(conn, session.as_ref(), body.duration_seconds)?;
+            ApiResponse::ok(timer::pause_subtask(conn, subtask_id, duration)?, true)
+        }
+        "resume" => {
+            if session.is_none() || running {
+                return Ok(ApiResponse::error(409, "Subtask is not paused"));
+            }
+            ApiResponse::ok(timer::resume_subtask(conn, subtask_id)?, true)
+        }
+        "complete" => {
+            if matches!(status, SubtaskStatus::Done) {
+                return Ok(ApiResponse::error(409, "Subtask is already completed"));
+            }
+            let body: DurationBody = match parse_body(body) {
+                Ok(body) => body,
+                Err(response) => return Ok(response),
+            };
+            if let Err(e) = timer::validate_duration(body.duration_seconds) {
+                return Ok(ApiResponse::error(400, e));
+            }
+            let duration = duration_or_tracked(conn, session.as_ref(), body.duration_seconds)?;
+            ApiResponse::ok(timer::complete_subtask(conn, subtask_id, duration)?, true)
+        }
+        _ => ApiResponse::error(404, "Not found"),
+    };
+    Ok(response)
+}
+
+fn route(conn: &Connection, request: &ApiRequest) -> Result<ApiResponse, String> {
+    let path = request.path.split('?').next().unwrap_or("");
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let response = match (request.method, segments.as_slice()) {
+        ("GET", ["status"]) => ApiResponse::ok(
+            json!({ "activeSessions": timer::list_active_sessions(conn, Utc::now())? }),
+            false,
+        ),
+        ("POST", ["tasks"]) => match parse_required_body::<CreateTaskBody>(request.body) {
+            Ok(body) => match timer::validate_estimate(body.estimate_seconds) {
+                Ok(()) => ApiResponse::ok(
+                    timer::create_task(conn, body.title, body.description, body.estimate_seconds)?,
+                    true,
+                ),
+                Err(e) => ApiResponse::error(400, e),
+            },
+            Err(response) => response,
+        },
+        ("POST", ["tasks", task_id, "subtasks"]) => {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ?1 AND profile_id = ?2)",
+                    params![task_id, active_profile_id(conn)?],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            if !exists {
+                return Ok(ApiResponse::error(404, format!("Task not found: {}", task_id)));
+            }
+            match parse_required_body::<CreateSubtaskBody>(request.body) {
+                Ok(body) => match timer::validate_estimate(body.estimate_seconds) {
+                    Ok(()) => ApiResponse::ok(
+                        timer::create_subtask(conn, task_id.to_string(), body.title, body.category_id, body.estimate_seconds)?,
+                        true,
+                    ),
+                    Err(e) => ApiResponse::error(400, e),
+                },
+                Err(response) => response,
+            }
+        }
+        ("POST", ["subtasks", subtask_id, action]) => subtask_action(conn, subtask_id, action, request.body)?,
+        (_, ["status"]) | (_, ["tasks"]) | (_, ["tasks", _, "subtasks"]) | (_, ["subtasks", _, _]) => {
+            ApiResponse::error(405, "Method not allowed")
+        }
+        _ => ApiResponse::error(404, "Not found"),
+    };
+    Ok(response)
+}
+
+/// Authenticate and dispatch one request against the database
+pub fn handle_request(conn: &Connection, token: &str, request: &ApiRequest) -> ApiResponse {
+    let provided = request
+        .authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    if !provided.is_some_and(|provided| tokens_match(token, provided)) {
+        return ApiResponse::error(401, "Missing or invalid bearer token");
+    }
+
+    route(conn, request).unwrap_or_else(|e| ApiResponse::error(500, e))
+}
+
+/// A running server; dropping it without `stop` leaves the thread serving
+pub struct HttpApiServer {
+    pub port: u16,
+    server: Arc<Server>,
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl HttpApiServer {
+    pub fn stop(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.server.unblock();
+        let _ = self.thread.join();
+    }
+}
+
+/// Serve on 127.0.0.1 only; `handler` runs on the server thread for every request
+pub fn start_server<F>(port: u16, handler: F) -> Result<HttpApiServer, String>
+where
+    F: Fn(&ApiRequest) -> ApiResponse + Send + 'static,
+{
+    let server = Arc::new(
+        Server::http(("127.0.0.1", port)).map_err(|e| format!("Failed to start HTTP API on port {}: {}", port, e))?,
+    );
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map(|addr| addr.port())
+        .unwrap_or(port);
+    let stopping = Arc::new(AtomicBool::new(false));
+
+    let thread = {
+        let server = Arc::clone(&server);
+        let stopping = Arc::clone(&stopping);
+        std::thread::spawn(move || loop {
+            let mut request = match server.recv() {
+                Ok(request) => request,
+                Err(_) if stopping.load(Ordering::SeqCst) => break,
+                Err(e) => {
+                    eprintln!("HTTP API error: {}", e);
+                    continue;
+                }
+            };
+
+            let mut body = String::new();
+            let response = if request
+                .as_reader()
+                .take(MAX_BODY_BYTES + 1)
+                .read_to_string(&mut body)
+                .is_err()
+            {
+                ApiResponse::error(400, "Request body must be UTF-8")
+            } else if body.len() as u64 > MAX_BODY_BYTES {
+                ApiResponse::error(413, "Request body too large")
+            } else {
+                let method = request.method().to_string();
+                let authorization = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Authorization"))
+                    .map(|header| header.value.as_str().to_string());
+                handler(&ApiRequest {
+                    method: &method,
+                    path: request.url(),
+                    authorization: authorization.as_deref(),
+                    body: &body,
+                })
+            };
+
+            let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+            let _ = request.respond(
+                Response::from_string(response.body.to_string())
+                    .with_status_code(response.status)
+                    .with_header(content_type),
+            );
+        })
+    };
+
+    Ok(HttpApiServer {
+        port,
+        server,
+        stopping,
+        thread,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    const TOKEN: &str = "test-token";
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn call(conn: &Connection, method: &str, path: &str, body: &str) -> ApiResponse {
+        handle_request(
+            conn,
+            TOKEN,
+            &ApiRequest {
+                method,
+                path,
+                authorization: Some("Bearer test-token"),
+                body,
+            },
+        )
+    }
+
+    #[test]
+    fn test_rejects_missing_or_wrong_token() {
+        let conn = setup();
+        for authorization in [None, Some("Bearer nope"), Some("test-token")] {
+            let response = handle_request(
+                &conn,
+                TOKEN,
+                &ApiRequest {
+                    method: "GET",
+                    path: "/status",
+                    authorization,
+                    body: "",
+                },
+            );
+            assert_eq!(response.status, 401);
+        }
+    }
+
+    #[test]
+    fn test_drives_timer_through_routes() {
+        let conn = setup();
+
+        let task = call(&conn, "POST", "/tasks", r#"{"title": "Editor plugin"}"#);
+        assert_eq!(task.status, 200);
+        let task_id = task.body["id"].as_str().unwrap().to_string();
+
+        let subtask = call(&conn, "POST", &format!("/tasks/{}/subtasks", task_id), r#"{"title": "Status bar"}"#);
+        assert_eq!(subtask.status, 200);
+        let subtask_id = subtask.body["id"].as_str().unwrap().to_string();
+
+        let start = call(&conn, "POST", &format!("/subtasks/{}/start", subtask_id), "");
+        assert_eq!(start.status, 200);
+        assert!(start.mutated);
+        assert_eq!(call(&conn, "POST", &format!("/subtasks/{}/start", subtask_id), "").status, 409);
+        assert_eq!(call(&conn, "POST", &format!("/subtasks/{}/resume", subtask_id), "").status, 409);
+
+        let status = call(&conn, "GET", "/status", "");
+        assert_eq!(status.status, 200);
+        assert!(!status.mutated);
+        assert_eq!(status.body["activeSessions"][0]["subtaskId"], subtask_id.as_str());
+        assert_eq!(status.body["activeSessions"][0]["running"], true);
+
+        let pause = call(&conn, "POST", &format!("/subtasks/{}/pause", subtask_id), r#"{"durationSeconds": 90}"#);
+        assert_eq!(pause.status, 200);
+        assert_eq!(pause.body["durationSeconds"], 90);
+        assert_eq!(call(&conn, "POST", &format!("/subtasks/{}/pause", subtask_id), "").status, 409);
+
+        assert_eq!(call(&conn, "POST", &format!("/subtasks/{}/resume", subtask_id), "").status, 200);
+
+        let complete = call(&conn, "POST", &format!("/subtasks/{}/complete", subtask_id), r#"{"durationSeconds": 120}"#);
+        assert_eq!(complete.status, 200);
+        assert_eq!(complete.body["timeSpentSeconds"], 120);
+        assert_eq!(complete.body["subtask"]["status"], "done");
+        assert_eq!(call(&conn, "POST", &format!("/subtasks/{}/complete", subtask_id), "").status, 409);
+
+        let status = call(&conn, "GET", "/status", "");
+        assert_eq!(status.body["activeSessions"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_reports_client_errors() {
+        let conn = setup();
+        assert_eq!(call(&conn, "POST", "/subtasks/missing/start", "").status, 404);
+        assert_eq!(call(&conn, "POST", "/tasks/missing/subtasks", r#"{"title": "x"}"#).status, 404);
+        assert_eq!(call(&conn, "POST", "/tasks", "{").status, 400);
+        assert_eq!(call(&conn, "POST", "/tasks", "").status, 400);
+        assert_eq!(call(&conn, "POST", "/tasks", r#"{"title": "x", "estimateSeconds": 0}"#).status, 400);
+        assert_eq!(call(&conn, "DELETE", "/tasks", "").status, 405);
+        assert_eq!(call(&conn, "GET", "/nowhere", "").status, 404);
+
+        // Negative durations would write negative sessions and XP
+        let task = timer::create_task(&conn, "API".to_string(), None, None).unwrap();
+        let subtask = timer::create_subtask(&conn, task.id.clone(), "Routes".to_string(), None, None).unwrap();
+        assert_eq!(call(&conn, "POST", &format!("/subtasks/{}/start", subtask.id), "").status, 200);
+        for action in ["pause", "complete"] {
+            let path = format!("/subtasks/{}/{}", subtask.id, action);
+            assert_eq!(call(&conn, "POST", &path, r#"{"durationSeconds": -5000}"#).status, 400);
+        }
+        assert!(timer::active_session(&conn, &subtask.id).unwrap().is_some());
+
+        // Tasks of a profile that isn't active are not found
+        let task = timer::create_task(&conn, "Private".to_string(), None, None).unwrap();
+        let subtask = timer::create_subtask(&conn, task.id.clone(), "Notes".to_string(), None, None).unwrap();
+        let other = crate::profiles::create_profile(&conn, "Side project").unwrap();
+        crate::profiles::switch_profile(&conn, &other.id).unwrap();
+        assert_eq!(call(&conn, "POST", &format!("/subtasks/{}/start", subtask.id), "").status, 404);
+        assert_eq!(call(&conn, "POST", &format!("/tasks/{}/subtasks", task.id), r#"{"title": "x"}"#).status, 404);
+    }
+
+    #[test]
+    fn test_config_is_created_once_and_saved() {
+        let conn = setup();
+        let config = load_config(&conn).unwrap();
+        assert!(!config.enabled);
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(config.token.len(), 64);
+        assert_eq!(load_config(&conn).unwrap(), config);
+
+        let updated = HttpApiConfig {
+            enabled: true,
+            port: 9000,
+            ..config
+        };
+        save_config(&conn, &updated).unwrap();
+        assert_eq!(load_config(&conn).unwrap(), updated);
+    }
+
+    #[test]
+    fn test_server_answers_on_loopback() {
+        let server = start_server(0, |request| ApiResponse::ok(json!({ "path": request.path }), false)).unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+        stream
+            .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("application/json"));
+        assert!(response.ends_with(r#"{"path":"/status"}"#));
+        server.stop();
+    }
+}
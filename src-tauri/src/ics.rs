@@ -0,0 +1,252 @@
+use crate::models::*;
+use crate::timesheet::{build_timesheet, TimesheetFilter};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+/// RFC 5545 limits content lines to 75 octets, excluding the CRLF
+const MAX_LINE_OCTETS: usize = 75;
+
+const PRODID: &str = "-//DevFocus//Focus Sessions//EN";
+
+/// Escape a TEXT value (RFC 5545 section 3.3.11)
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold a content line into CRLF-terminated chunks of at most 75 octets,
+/// never splitting a UTF-8 character (RFC 5545 section 3.1)
+fn fold_line(line: &str, output: &mut String) {
+    let mut limit = MAX_LINE_OCTETS;
+    let mut chunk_start = 0;
+
+    for (index, c) in line.char_indices() {
+        if index + c.len_utf8() - chunk_start > limit {
+            output.push_str(&line[chunk_start..index]);
+            output.push_str("\r\n ");
+            chunk_start = index;
+            // Continuation lines start with a space, which counts towards the limit
+            limit = MAX_LINE_OCTETS - 1;
+        }
+    }
+
+    output.push_str(&line[chunk_start..]);
+    output.push_str("\r\n");
+}
+
+fn format_timestamp(value: &DateTime<Utc>) -> String {
+    value.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn parse_entry_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|parsed| parsed.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid session time '{}': {}", value, e))
+}
+
+/// Render timesheet entries as a VCALENDAR with one VEVENT per running interval
+pub fn render_ics(timesheet: &Timesheet, generated_at: DateTime<Utc>) -> Result<String, String> {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+    ];
+
+    for entry in &timesheet.entries {
+        let start = parse_entry_time(&entry.start)?;
+        let end = parse_entry_time(&entry.end)?;
+
+        let mut description = format!("Task: {}\nSubtask: {}", entry.task, entry.subtask);
+        if let Some(category) = &entry.category {
+            description.push_str(&format!("\nCategory: {}", category));
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        // Stable across exports so calendars update events instead of duplicating them
+        lines.push(format!("UID:{}-{}@devfocus", entry.subtask_id, format_timestamp(&start)));
+        lines.push(format!("DTSTAMP:{}", format_timestamp(&generated_at)));
+        lines.push(format!("DTSTART:{}", format_timestamp(&start)));
+        lines.push(format!("DTEND:{}", format_timestamp(&end)));
+        lines.push(format!("SUMMARY:{}", escape_text(&format!("{} — {}", entry.task, entry.subtask))));
+        lines.push(format!("DESCRIPTION:{}", escape_text(&description)));
+        if let Some(category) = &entry.category {
+            lines.push(format!("CATEGORIES:{}", escape_text(category)));
+        }
+        lines.push("TRANSP:OPAQUE".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut output = String::new();
+    for line in &lines {
+        fold_line(line, &mut output);
+    }
+    Ok(output)
+}
+
+/// Focus sessions that started within an inclusive `YYYY-MM-DD` range, as iCalendar
+pub fn export_ics(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    generated_at: DateTime<Utc>,
+) -> Result<String, String> {
+    let filter = TimesheetFilter {
+        start_date: Some(start_date.to_string()),
+        end_date: Some(end_date.to_string()),
+        ..Default::default()
+    };
+    let timesheet = build_timesheet(conn, &filter, None, false)?;
+    render_ics(&timesheet, generated_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use chrono::TimeZone;
+
+    fn seeded_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let backend: String = conn
+            .query_row("SELECT id FROM categories WHERE name = 'backend'", [], |row| row.get(0))
+            .unwrap();
+
+        conn.execute(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at)
+             VALUES ('t1', 'Pagos; integración, fase 2', 'in_progress', '2025-04-01T08:00:00+00:00', '2025-04-01T08:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO subtasks (id, task_id, title, status, category_id, created_at, updated_at)
+             VALUES ('s1', 't1', ?1, 'done', ?2, '2025-04-01T08:00:00+00:00', '2025-04-01T08:00:00+00:00')",
+            [
+                "Conectar la pasarela de pagos con el servicio de facturación electrónica y validar los webhooks",
+                backend.as_str(),
+            ],
+        )
+        .unwrap();
+        conn.execute_batch(
+            "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+             VALUES ('ts1', 's1', '2025-04-02T09:00:00+00:00', '2025-04-02T11:00:00+00:00', 6000),
+                    ('ts2', 's1', '2025-04-20T09:00:00+00:00', '2025-04-20T10:00:00+00:00', 3600);
+             INSERT INTO session_intervals (id, session_id, started_at, ended_at)
+             VALUES ('i1', 'ts1', '2025-04-02T09:00:00+00:00', '2025-04-02T09:30:00+00:00'),
+                    ('i2', 'ts1', '2025-04-02T09:50:00+00:00', '2025-04-02T11:00:00+00:00'),
+                    ('i3', 'ts2', '2025-04-20T09:00:00+00:00', '2025-04-20T10:00:00+00:00');",
+        )
+        .unwrap();
+
+        conn
+    }
+
+    /// Undo line folding (RFC 5545 section 3.1)
+    fn unfold(ics: &str) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for line in ics.split("\r\n").filter(|line| !line.is_empty()) {
+            match line.strip_prefix(' ') {
+                Some(continuation) => lines.last_mut().unwrap().push_str(continuation),
+                None => lines.push(line.to_string()),
+            }
+        }
+        lines
+    }
+
+    fn is_utc_date_time(value: &str) -> bool {
+        let bytes = value.as_bytes();
+        bytes.len() == 16
+            && bytes[8] == b'T'
+            && bytes[15] == b'Z'
+            && bytes[..8].iter().chain(&bytes[9..15]).all(u8::is_ascii_digit)
+    }
+
+    #[test]
+    fn test_ics_follows_rfc5545_structure() {
+        let conn = seeded_connection();
+        let generated_at = Utc.with_ymd_and_hms(2025, 4, 30, 18, 0, 0).unwrap();
+        let ics = export_ics(&conn, "2025-04-01", "2025-04-10", generated_at).unwrap();
+
+        // Every line is CRLF-terminated and at most 75 octets
+        assert!(ics.ends_with("\r\n"));
+        assert!(!ics.replace("\r\n", "").contains('\n'));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS, "line too long: {:?}", line);
+        }
+
+        let lines = unfold(&ics);
+        assert_eq!(lines.first().map(String::as_str), Some("BEGIN:VCALENDAR"));
+        assert_eq!(lines.last().map(String::as_str), Some("END:VCALENDAR"));
+        assert!(lines.contains(&"VERSION:2.0".to_string()));
+        assert!(lines.iter().any(|line| line.starts_with("PRODID:")));
+
+        // Components are balanced and each event has the required properties exactly once
+        let mut events: Vec<Vec<&String>> = Vec::new();
+        let mut current: Option<Vec<&String>> = None;
+        for line in &lines {
+            match line.as_str() {
+                "BEGIN:VEVENT" => {
+                    assert!(current.is_none(), "nested VEVENT");
+                    current = Some(Vec::new());
+                }
+                "END:VEVENT" => events.push(current.take().expect("END without BEGIN")),
+                _ => {
+                    if let Some(properties) = current.as_mut() {
+                        properties.push(line);
+                    }
+                }
+            }
+        }
+        assert!(current.is_none());
+        // Two intervals inside the range; the session on the 20th is outside it
+        assert_eq!(events.len(), 2);
+
+        for event in &events {
+            for name in ["UID", "DTSTAMP", "DTSTART", "DTEND", "SUMMARY"] {
+                let values: Vec<&str> = event
+                    .iter()
+                    .filter_map(|line| line.strip_prefix(&format!("{}:", name)))
+                    .collect();
+                assert_eq!(values.len(), 1, "{} must appear once", name);
+                if name.starts_with("DT") {
+                    assert!(is_utc_date_time(values[0]), "{} is not a UTC DATE-TIME: {}", name, values[0]);
+                }
+            }
+        }
+
+        let first = &events[0];
+        assert!(first.contains(&&"DTSTART:20250402T090000Z".to_string()));
+        assert!(first.contains(&&"DTEND:20250402T093000Z".to_string()));
+        assert!(first.contains(&&"CATEGORIES:backend".to_string()));
+        assert!(first.iter().any(|line| line.starts_with("SUMMARY:Pagos\\; integración\\, fase 2 — Conectar")));
+        assert!(first
+            .iter()
+            .any(|line| line.starts_with("DESCRIPTION:Task: Pagos\\; integración\\, fase 2\\nSubtask: ")));
+    }
+
+    #[test]
+    fn test_fold_line_keeps_utf8_characters_whole() {
+        let line = format!("SUMMARY:{}", "á".repeat(80));
+        let mut output = String::new();
+        fold_line(&line, &mut output);
+
+        for physical in output.split("\r\n").filter(|l| !l.is_empty()) {
+            assert!(physical.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(unfold(&output), vec![line]);
+    }
+}
@@ -0,0 +1,637 @@
+use crate::db::{ensure_category_experience, record_missing_points};
+use crate::gamification::{calculate_level, record_xp_gain, update_user_profile_level};
+use crate::models::*;
+use crate::profiles::active_profile_id;
+use crate::settings;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Color given to categories created from imported tags or contexts
+const IMPORTED_CATEGORY_COLOR: &str = "#6b7280";
+
+const DEFAULT_TASK_TITLE: &str = "No project";
+const DEFAULT_SUBTASK_TITLE: &str = "(no description)";
+const TODO_TXT_TASK_TITLE: &str = "todo.txt";
+
+const TOGGL_DATE_FORMATS: &[&str] = &["%Y-%m-%d"];
+const TOGGL_TIME_FORMATS: &[&str] = &["%H:%M:%S", "%H:%M"];
+// Clockify follows the workspace locale; US formats are the default
+const CLOCKIFY_DATE_FORMATS: &[&str] = &["%m/%d/%Y", "%Y-%m-%d", "%d.%m.%Y"];
+const CLOCKIFY_TIME_FORMATS: &[&str] = &["%I:%M:%S %p", "%I:%M %p", "%H:%M:%S", "%H:%M"];
+
+/// A unit of work read from another tool, before it is matched against existing data
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedEntry {
+    pub task: String,
+    pub subtask: String,
+    pub category: Option<String>,
+    /// Tracked time; None for entries without time (todo.txt)
+    pub span: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    pub done: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedImport {
+    pub entries: Vec<ImportedEntry>,
+    pub warnings: Vec<String>,
+}
+
+// ============================================================================
+// PARSERS
+// ============================================================================
+
+/// Parse an export file. Times without a zone are read in `offset`.
+pub fn parse(source: ImportSource, content: &str, offset: FixedOffset) -> Result<ParsedImport, String> {
+    match source {
+        ImportSource::Toggl => parse_time_tracker_csv(content, offset, TOGGL_DATE_FORMATS, TOGGL_TIME_FORMATS),
+        ImportSource::Clockify => {
+            parse_time_tracker_csv(content, offset, CLOCKIFY_DATE_FORMATS, CLOCKIFY_TIME_FORMATS)
+        }
+        ImportSource::TodoTxt => Ok(parse_todo_txt(content, offset)),
+    }
+}
+
+fn to_utc(date: NaiveDate, time: NaiveTime, offset: FixedOffset) -> Option<DateTime<Utc>> {
+    offset
+        .from_local_datetime(&NaiveDateTime::new(date, time))
+        .single()
+        .map(|local| local.with_timezone(&Utc))
+}
+
+fn parse_with_formats<T>(value: &str, formats: &[&str], parse: fn(&str, &str) -> chrono::ParseResult<T>) -> Option<T> {
+    formats.iter().find_map(|format| parse(value.trim(), format).ok())
+}
+
+/// Toggl and Clockify detailed reports share column names (up to case) and differ in date formats
+fn parse_time_tracker_csv(
+    content: &str,
+    offset: FixedOffset,
+    date_formats: &[&str],
+    time_formats: &[&str],
+) -> Result<ParsedImport, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV header: {}", e))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+
+    let (Some(start_date), Some(start_time), Some(end_date), Some(end_time)) = (
+        column("start date"),
+        column("start time"),
+        column("end date"),
+        column("end time"),
+    ) else {
+        return Err("CSV is missing start/end date and time columns".to_string());
+    };
+    let project = column("project");
+    let description = column("description");
+    let task = column("task");
+    let tags = column("tags");
+
+    let mut parsed = ParsedImport::default();
+
+    for (index, record) in reader.records().enumerate() {
+        // Header is line 1
+        let line = index + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                parsed.warnings.push(format!("Line {}: {}", line, e));
+                continue;
+            }
+        };
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+
+        let timestamp = |date_column: usize, time_column: usize| {
+            let date = parse_with_formats(field(Some(date_column))?, date_formats, NaiveDate::parse_from_str)?;
+            let time = parse_with_formats(field(Some(time_column))?, time_formats, NaiveTime::parse_from_str)?;
+            to_utc(date, time, offset)
+        };
+
+        let (Some(start), Some(end)) = (timestamp(start_date, start_time), timestamp(end_date, end_time)) else {
+            parsed.warnings.push(format!("Line {}: unreadable start or end time", line));
+            continue;
+        };
+        if end <= start {
+            parsed.warnings.push(format!("Line {}: entry ends before it starts", line));
+            continue;
+        }
+
+        parsed.entries.push(ImportedEntry {
+            task: field(project).unwrap_or(DEFAULT_TASK_TITLE).to_string(),
+            subtask: field(description)
+                .or_else(|| field(task))
+                .unwrap_or(DEFAULT_SUBTASK_TITLE)
+                .to_string(),
+            // The first tag becomes the category
+            category: field(tags)
+                .and_then(|tags| tags.split(',').map(str::trim).find(|tag| !tag.is_empty()))
+                .map(str::to_lowercase),
+            span: Some((start, end)),
+            done: true,
+            created_at: Some(start),
+            completed_at: Some(end),
+        });
+    }
+
+    Ok(parsed)
+}
+
+fn parse_todo_date(token: &str, offset: FixedOffset) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(token, "%Y-%m-%d").ok()?;
+    to_utc(date, NaiveTime::MIN, offset)
+}
+
+/// todo.txt: `x` marks done, then completion/creation dates, `(A)` priority,
+/// `+project` becomes the task and `@context` the category
+fn parse_todo_txt(content: &str, offset: FixedOffset) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+
+    for (index, line) in content.lines().enumerate() {
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens.peek().is_none() {
+            continue;
+        }
+
+        let done = tokens.next_if_eq(&"x").is_some();
+        let mut completed_at = None;
+        if done {
+            completed_at = tokens.peek().and_then(|token| parse_todo_date(token, offset));
+            if completed_at.is_some() {
+                tokens.next();
+            }
+        } else {
+            tokens.next_if(|token| token.len() == 3 && token.starts_with('(') && token.ends_with(')'));
+        }
+        let created_at = tokens.peek().and_then(|token| parse_todo_date(token, offset));
+        if created_at.is_some() {
+            tokens.next();
+        }
+
+        let mut project = None;
+        let mut context = None;
+        let mut words = Vec::new();
+        for token in tokens {
+            if let Some(name) = token.strip_prefix('+').filter(|name| !name.is_empty()) {
+                project.get_or_insert(name);
+            } else if let Some(name) = token.strip_prefix('@').filter(|name| !name.is_empty()) {
+                context.get_or_insert(name);
+            } else if is_todo_tag(token) {
+                // key:value metadata such as due:2025-01-31
+            } else {
+                words.push(token);
+            }
+        }
+
+        if words.is_empty() {
+            parsed.warnings.push(format!("Line {}: task has no description", index + 1));
+            continue;
+        }
+
+        parsed.entries.push(ImportedEntry {
+            task: project.unwrap_or(TODO_TXT_TASK_TITLE).to_string(),
+            subtask: words.join(" "),
+            category: context.map(str::to_lowercase),
+            span: None,
+            done,
+            created_at,
+            completed_at,
+        });
+    }
+
+    parsed
+}
+
+fn is_todo_tag(token: &str) -> bool {
+    match token.split_once(':') {
+        Some((key, value)) => {
+            !key.is_empty() && !value.is_empty() && !value.starts_with("//") && !value.contains(':')
+        }
+        None => false,
+    }
+}
+
+// ============================================================================
+// IMPORT
+// ============================================================================
+
+fn find_or_create_category(
+    conn: &Connection,
+    name: &str,
+    now: &str,
+    report: &mut TrackerImportReport,
+) -> Result<String, String> {
+    let existing: Option<String> = conn
+        .query_row("SELECT id FROM categories WHERE name = ?1", [name], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO categories (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, name, IMPORTED_CATEGORY_COLOR, now],
+    )
+    .map_err(|e| format!("Failed to create category {}: {}", name, e))?;
+    ensure_category_experience(conn).map_err(|e| e.to_string())?;
+
+    report.categories_created += 1;
+    Ok(id)
+}
+
+/// Aggregates of a task created by this import, applied once all its subtasks are in
+#[derive(Default)]
+struct NewTask {
+    all_done: bool,
+    created_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+/// Write parsed entries as tasks, subtasks and closed sessions, reusing tasks,
+/// subtasks and categories with the same names. Sessions already imported
+/// (same subtask and start time) are skipped, so importing a file twice is harmless.
+/// With `dry_run` everything runs inside a transaction that is rolled back.
+pub fn import_entries(
+    conn: &mut Connection,
+    source: ImportSource,
+    parsed: ParsedImport,
+    dry_run: bool,
+    now: DateTime<Utc>,
+) -> Result<TrackerImportReport, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let now_str = now.to_rfc3339();
+    let profile_id = active_profile_id(&tx)?;
+    let mut report = TrackerImportReport {
+        source,
+        dry_run,
+        tasks_created: 0,
+        subtasks_created: 0,
+        sessions_created: 0,
+        categories_created: 0,
+        duplicates_skipped: 0,
+        tracked_seconds: 0,
+        xp_awarded: 0,
+        warnings: parsed.warnings,
+    };
+
+    // Group entries into subtasks, keeping file order
+    let mut groups: Vec<((String, String), Vec<ImportedEntry>)> = Vec::new();
+    for entry in parsed.entries {
+        let key = (entry.task.clone(), entry.subtask.clone());
+        match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+            Some((_, entries)) => entries.push(entry),
+            None => groups.push((key, vec![entry])),
+        }
+    }
+
+    let mut task_ids: HashMap<String, String> = HashMap::new();
+    let mut new_tasks: HashMap<String, NewTask> = HashMap::new();
+    let mut category_ids: HashMap<String, String> = HashMap::new();
+    let mut xp_by_category: HashMap<String, i64> = HashMap::new();
+
+    for ((task_title, subtask_title), entries) in groups {
+        let created_at = entries.iter().filter_map(|e| e.created_at).min();
+        let completed_at = entries.iter().filter_map(|e| e.completed_at).max();
+        let done = entries.iter().all(|e| e.done);
+
+        let task_id = match task_ids.get(&task_title) {
+            Some(id) => id.clone(),
+            None => {
+                let existing: Option<String> = tx
+                    .query_row(
+                        "SELECT id FROM tasks WHERE profile_id = ?1 AND title = ?2 ORDER BY created_at LIMIT 1",
+                        params![&profile_id, &task_title],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .map_err(|e| e.to_string())?;
+                let id = match existing {
+                    Some(id) => id,
+                    None => {
+                        let id = Uuid::new_v4().to_string();
+                        tx.execute(
+                            "INSERT INTO tasks (id, title, status, created_at, updated_at, profile_id)
+                             VALUES (?1, ?2, 'todo', ?3, ?3, ?4)",
+                            params![id, task_title, now_str, &profile_id],
+                        )
+                        .map_err(|e| format!("Failed to create task {}: {}", task_title, e))?;
+                        report.tasks_created += 1;
+                        new_tasks.insert(
+                            id.clone(),
+                            NewTask {
+                                all_done: true,
+                                ..Default::default()
+                            },
+                        );
+                        id
+                    }
+                };
+                task_ids.insert(task_title.clone(), id.clone());
+                id
+            }
+        };
+
+        if let Some(task) = new_tasks.get_mut(&task_id) {
+            task.all_done &= done;
+            task.created_at = match (task.created_at, created_at) {
+                (Some(current), Some(created)) => Some(current.min(created)),
+                (current, created) => current.or(created),
+            };
+            task.completed_at = task.completed_at.max(completed_at);
+        }
+
+        let category_id = match entries.iter().find_map(|e| e.category.clone()) {
+            Some(name) => match category_ids.get(&name) {
+                Some(id) => Some(id.clone()),
+                None => {
+                    let id = find_or_create_category(&tx, &name, &now_str, &mut report)?;
+                    category_ids.insert(name, id.clone());
+                    Some(id)
+                }
+            },
+            None => None,
+        };
+
+        let existing_subtask: Option<String> = tx
+            .query_row(
+                "SELECT id FROM subtasks WHERE task_id = ?1 AND title = ?2 LIMIT 1",
+                params![task_id, subtask_title],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let subtask_id = match existing_subtask {
+            Some(id) => {
+                if entries.iter().all(|e| e.span.is_none()) {
+                    report.duplicates_skipped += 1;
+                }
+                id
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                let created = created_at.unwrap_or(now).to_rfc3339();
+                let completed = done.then(|| completed_at.unwrap_or(now).to_rfc3339());
+                tx.execute(
+                    "INSERT INTO subtasks (id, task_id, title, status, category_id, created_at, updated_at, completed_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        id,
+                        task_id,
+                        subtask_title,
+                        if done { "done" } else { "todo" },
+                        category_id,
+                        created,
+                        completed.as_deref().unwrap_or(&created),
+                        completed
+                    ],
+                )
+                .map_err(|e| format!("Failed to create subtask {}: {}", subtask_title, e))?;
+                report.subtasks_created += 1;
+                id
+            }
+        };
+
+        for (start, end) in entries.iter().filter_map(|e| e.span) {
+            let started_at = start.to_rfc3339();
+            let exists: bool = tx
+                .query_row(
+                    "SELECT COUNT(*) FROM time_sessions WHERE subtask_id = ?1 AND started_at = ?2",
+                    params![subtask_id, started_at],
+                    |row| row.get::<_, i64>(0),
+                )
+                .map_err(|e| e.to_string())?
+                > 0;
+            if exists {
+                report.duplicates_skipped += 1;
+                continue;
+            }
+
+            let session_id = Uuid::new_v4().to_string();
+            let ended_at = end.to_rfc3339();
+            let duration = (end - start).num_seconds();
+            tx.execute(
+                "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![session_id, subtask_id, started_at, ended_at, duration],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.execute(
+                "INSERT INTO session_intervals (id, session_id, started_at, ended_at) VALUES (?1, ?2, ?3, ?4)",
+                params![Uuid::new_v4().to_string(), session_id, started_at, ended_at],
+            )
+            .map_err(|e| e.to_string())?;
+
+            report.sessions_created += 1;
+            report.tracked_seconds += duration;
+
+            // 1 XP per second worked, as for tracked sessions
+            if let Some(category_id) = &category_id {
+                record_xp_gain(&tx, category_id, Some(&subtask_id), duration, &ended_at)?;
+                *xp_by_category.entry(category_id.clone()).or_default() += duration;
+                report.xp_awarded += duration;
+            }
+        }
+    }
+
+    for (task_id, task) in new_tasks {
+        let created = task.created_at.unwrap_or(now).to_rfc3339();
+        let completed = task
+            .all_done
+            .then(|| task.completed_at.unwrap_or(now).to_rfc3339());
+        tx.execute(
+            "UPDATE tasks SET status = ?1, created_at = ?2, updated_at = ?3, completed_at = ?4 WHERE id = ?5",
+            params![
+                if task.all_done { "done" } else { "todo" },
+                created,
+                completed.as_deref().unwrap_or(&created),
+                completed,
+                task_id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // Recompute XP and levels from the imported time
+    for (category_id, xp) in xp_by_category {
+        let total_xp: i64 = tx
+            .query_row(
+                "SELECT total_xp FROM category_experience WHERE profile_id = ?1 AND category_id = ?2",
+                params![&profile_id, &category_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE category_experience SET total_xp = ?1, level = ?2, updated_at = ?3
+             WHERE profile_id = ?4 AND category_id = ?5",
+            params![total_xp + xp, calculate_level(total_xp + xp), now_str, &profile_id, category_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    update_user_profile_level(&tx, &profile_id)?;
+    record_missing_points(&tx, &settings::scoring_rules(&tx)?).map_err(|e| e.to_string())?;
+
+    if dry_run {
+        tx.rollback().map_err(|e| e.to_string())?;
+    } else {
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+
+    const TOGGL_CSV: &str = "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()
+Ana,ana@example.com,,Website,,Fix login,No,2025-02-03,09:00:00,2025-02-03,09:45:00,00:45:00,\"Backend, urgent\",
+Ana,ana@example.com,,Website,,Fix login,No,2025-02-04,10:00:00,2025-02-04,10:30:00,00:30:00,Backend,
+Ana,ana@example.com,,,,,No,2025-02-04,11:00:00,2025-02-04,11:10:00,00:10:00,,
+Ana,ana@example.com,,Website,,Broken row,No,not-a-date,10:00:00,2025-02-04,10:30:00,00:30:00,,
+";
+
+    const CLOCKIFY_CSV: &str = "Project,Client,Description,Task,User,Group,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h),Duration (decimal)
+Mobile app,,,Release build,Ana,,ana@example.com,DevOps,Yes,02/05/2025,01:15:00 PM,02/05/2025,02:45:00 PM,01:30:00,1.50
+";
+
+    fn utc() -> FixedOffset {
+        FixedOffset::east_opt(0).unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_toggl_csv() {
+        let parsed = parse(ImportSource::Toggl, TOGGL_CSV, utc()).unwrap();
+
+        assert_eq!(parsed.entries.len(), 3);
+        assert_eq!(parsed.warnings, vec!["Line 5: unreadable start or end time".to_string()]);
+
+        let first = &parsed.entries[0];
+        assert_eq!((first.task.as_str(), first.subtask.as_str()), ("Website", "Fix login"));
+        assert_eq!(first.category.as_deref(), Some("backend"));
+        assert_eq!(first.span.map(|(start, end)| (end - start).num_minutes()), Some(45));
+        assert_eq!(parsed.entries[2].task, DEFAULT_TASK_TITLE);
+        assert_eq!(parsed.entries[2].subtask, DEFAULT_SUBTASK_TITLE);
+    }
+
+    #[test]
+    fn test_parse_clockify_csv_with_offset() {
+        let offset = FixedOffset::west_opt(3 * 3600).unwrap();
+        let parsed = parse(ImportSource::Clockify, CLOCKIFY_CSV, offset).unwrap();
+
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.subtask, "Release build");
+        assert_eq!(entry.category.as_deref(), Some("devops"));
+        assert_eq!(
+            entry.span.unwrap().0,
+            Utc.with_ymd_and_hms(2025, 2, 5, 16, 15, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_todo_txt() {
+        let content = "x 2025-01-10 2025-01-02 Write release notes +Website @docs due:2025-01-09\n\
+                       (A) 2025-01-05 Call the bank\n\
+                       \n\
+                       +Website @frontend\n";
+        let parsed = parse(ImportSource::TodoTxt, content, utc()).unwrap();
+
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.warnings.len(), 1);
+
+        let done = &parsed.entries[0];
+        assert!(done.done);
+        assert_eq!(done.subtask, "Write release notes");
+        assert_eq!(done.task, "Website");
+        assert_eq!(done.category.as_deref(), Some("docs"));
+        assert_eq!(done.completed_at, Some(Utc.with_ymd_and_hms(2025, 1, 10, 0, 0, 0).unwrap()));
+
+        let open = &parsed.entries[1];
+        assert!(!open.done);
+        assert_eq!(open.task, TODO_TXT_TASK_TITLE);
+        assert_eq!(open.created_at, Some(Utc.with_ymd_and_hms(2025, 1, 5, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_dry_run_reports_without_writing() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let tasks_before = count(&conn, "tasks");
+
+        let parsed = parse(ImportSource::Toggl, TOGGL_CSV, utc()).unwrap();
+        let report = import_entries(&mut conn, ImportSource::Toggl, parsed, true, now()).unwrap();
+
+        assert!(report.dry_run);
+        assert_eq!(report.tasks_created, 2);
+        assert_eq!(report.subtasks_created, 2);
+        assert_eq!(report.sessions_created, 3);
+        assert_eq!(report.categories_created, 0);
+        assert_eq!(report.tracked_seconds, (45 + 30 + 10) * 60);
+        assert_eq!(report.xp_awarded, 75 * 60);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(count(&conn, "tasks"), tasks_before);
+        assert_eq!(count(&conn, "time_sessions"), 0);
+    }
+
+    #[test]
+    fn test_import_awards_xp_and_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        let parsed = parse(ImportSource::Clockify, CLOCKIFY_CSV, utc()).unwrap();
+        let report = import_entries(&mut conn, ImportSource::Clockify, parsed, false, now()).unwrap();
+        assert_eq!(report.categories_created, 1);
+        assert_eq!(report.sessions_created, 1);
+
+        let (total_xp, level): (i64, i64) = conn
+            .query_row(
+                "SELECT ce.total_xp, ce.level FROM category_experience ce
+                 JOIN categories c ON c.id = ce.category_id WHERE c.name = 'devops'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(total_xp, 5400);
+        assert_eq!(level, calculate_level(5400));
+
+        let (status, completed_at): (String, Option<String>) = conn
+            .query_row("SELECT status, completed_at FROM tasks WHERE title = 'Mobile app'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(status, "done");
+        assert_eq!(completed_at.as_deref(), Some("2025-02-05T14:45:00+00:00"));
+
+        let parsed = parse(ImportSource::Clockify, CLOCKIFY_CSV, utc()).unwrap();
+        let again = import_entries(&mut conn, ImportSource::Clockify, parsed, false, now()).unwrap();
+        assert_eq!(again.tasks_created + again.subtasks_created + again.sessions_created, 0);
+        assert_eq!(again.duplicates_skipped, 1);
+        assert_eq!(count(&conn, "xp_ledger"), 1);
+    }
+}
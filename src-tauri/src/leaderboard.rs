@@ -0,0 +1,698 @@
+use crate::analytics::{parse_date_range, period_start, timestamp_date};
+use crate::gamification::{calculate_subtask_points, effective_streak};
+use crate::models::*;
+use crate::profiles;
+use crate::settings::{self, Settings};
+use crate::team_sync::{self, MemberRecord, SessionRecord, SubtaskRecord, TaskRecord, TeamEntityKind, XpRecord};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+struct CompletionFact {
+    date: NaiveDate,
+    category: Option<String>,
+    points: i64,
+}
+
+struct SessionFact {
+    date: NaiveDate,
+    category: Option<String>,
+    seconds: i64,
+}
+
+struct XpFact {
+    date: NaiveDate,
+    category: String,
+    xp: i64,
+}
+
+/// Everything a leaderboard needs about one profile or team member
+pub struct Participant {
+    pub id: String,
+    pub name: String,
+    pub is_self: bool,
+    pub current_streak: i64,
+    completions: Vec<CompletionFact>,
+    // Completion dates and points of tasks that earned the complexity bonus
+    bonuses: Vec<(NaiveDate, i64)>,
+    sessions: Vec<SessionFact>,
+    xp: Vec<XpFact>,
+}
+
+impl Participant {
+    fn new(id: String, name: String, is_self: bool, current_streak: i64) -> Self {
+        Participant {
+            id,
+            name,
+            is_self,
+            current_streak,
+            completions: Vec::new(),
+            bonuses: Vec::new(),
+            sessions: Vec::new(),
+            xp: Vec::new(),
+        }
+    }
+
+    /// Points earned between two dates, inclusive. With a category only that category's
+    /// subtasks count, and the task complexity bonus (which has no category) is left out.
+    pub fn points_between(&self, start: NaiveDate, end: NaiveDate, category: Option<&str>) -> i64 {
+        let in_range = |date: &NaiveDate| *date >= start && *date <= end;
+        let subtask_points: i64 = self
+            .completions
+            .iter()
+            .filter(|c| in_range(&c.date) && matches_category(c.category.as_deref(), category))
+            .map(|c| c.points)
+            .sum();
+        let bonus_points = match category {
+            Some(_) => 0,
+            None => self.bonuses.iter().filter(|(date, _)| in_range(date)).map(|(_, points)| points).sum(),
+        };
+        subtask_points + bonus_points
+    }
+
+    fn subtasks_between(&self, start: NaiveDate, end: NaiveDate, category: Option<&str>) -> i64 {
+        self.completions
+            .iter()
+            .filter(|c| c.date >= start && c.date <= end && matches_category(c.category.as_deref(), category))
+            .count() as i64
+    }
+
+    fn focus_seconds_between(&self, start: NaiveDate, end: NaiveDate, category: Option<&str>) -> i64 {
+        self.sessions
+            .iter()
+            .filter(|s| s.date >= start && s.date <= end && matches_category(s.category.as_deref(), category))
+            .map(|s| s.seconds)
+            .sum()
+    }
+
+    fn xp_between(&self, start: NaiveDate, end: NaiveDate, category: Option<&str>) -> i64 {
+        self.xp
+            .iter()
+            .filter(|x| x.date >= start && x.date <= end && matches_category(Some(&x.category), category))
+            .map(|x| x.xp)
+            .sum()
+    }
+}
+
+// Category names are stored lowercase
+fn matches_category(value: Option<&str>, filter: Option<&str>) -> bool {
+    match filter {
+        Some(filter) => value.is_some_and(|value| value.eq_ignore_ascii_case(filter)),
+        None => true,
+    }
+}
+
+fn normalize_category(category: Option<&str>) -> Option<String> {
+    category
+        .map(|category| category.trim().to_lowercase())
+        .filter(|category| !category.is_empty())
+}
+
+/// Every profile in this database with its completed work, sessions and XP
+fn load_profile_participants(conn: &Connection, settings: &Settings, now: DateTime<Utc>) -> Result<Vec<Participant>, String> {
+    let today = settings.local_time(now).date();
+    let mut participants: HashMap<String, Participant> = profiles::list_profiles(conn)?
+        .into_iter()
+        .map(|profile| {
+            let streak = effective_streak(profile.current_streak, profile.last_work_date.as_deref(), today);
+            (profile.id.clone(), Participant::new(profile.id, profile.name, profile.active, streak))
+        })
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.profile_id, c.name, s.completed_at, COALESCE(s.points, 0)
+             FROM subtasks s
+             JOIN tasks t ON s.task_id = t.id
+             LEFT JOIN categories c ON s.category_id = c.id
+             WHERE s.status = 'done' AND s.completed_at IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    for (profile_id, category, completed_at, points) in rows {
+        if let (Some(participant), Some(date)) = (participants.get_mut(&profile_id), timestamp_date(&completed_at, settings)) {
+            participant.completions.push(CompletionFact { date, category, points });
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT profile_id, completed_at, bonus_points
+             FROM tasks
+             WHERE status = 'done' AND completed_at IS NOT NULL AND bonus_points IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    for (profile_id, completed_at, bonus_points) in rows {
+        if let (Some(participant), Some(date)) = (participants.get_mut(&profile_id), timestamp_date(&completed_at, settings)) {
+            participant.bonuses.push((date, bonus_points));
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.profile_id, c.name, ts.started_at, ts.duration_seconds
+             FROM time_sessions ts
+             JOIN subtasks s ON ts.subtask_id = s.id
+             JOIN tasks t ON s.task_id = t.id
+             LEFT JOIN categories c ON s.category_id = c.id
+             WHERE ts.ended_at IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    for (profile_id, category, started_at, seconds) in rows {
+        if let (Some(participant), Some(date)) = (participants.get_mut(&profile_id), timestamp_date(&started_at, settings)) {
+            participant.sessions.push(SessionFact { date, category, seconds });
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT x.profile_id, c.name, x.xp, x.earned_at
+             FROM xp_ledger x
+             JOIN categories c ON x.category_id = c.id
+             WHERE x.earned_at IS NOT NULL AND x.profile_id IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    for (profile_id, category, xp, earned_at) in rows {
+        if let (Some(participant), Some(date)) = (participants.get_mut(&profile_id), timestamp_date(&earned_at, settings)) {
+            participant.xp.push(XpFact { date, category, xp });
+        }
+    }
+
+    Ok(participants.into_values().collect())
+}
+
+/// Every member of the merged team view
+fn load_team_participants(conn: &Connection, settings: &Settings, now: DateTime<Utc>) -> Result<Vec<Participant>, String> {
+    let today = settings.local_time(now).date();
+    let rules = &settings.scoring;
+    let self_id = team_sync::get_status(conn)?.member_id;
+    let mut participants: HashMap<String, Participant> = team_sync::load_records::<MemberRecord>(conn, TeamEntityKind::Member)?
+        .into_iter()
+        .map(|(member_id, _, member)| {
+            let streak = effective_streak(member.current_streak, member.last_work_date.as_deref(), today);
+            let is_self = self_id.as_ref() == Some(&member_id);
+            (member_id.clone(), Participant::new(member_id, member.name, is_self, streak))
+        })
+        .collect();
+
+    let sessions = team_sync::load_records::<SessionRecord>(conn, TeamEntityKind::Session)?;
+    let mut tracked: HashMap<(&str, &str), i64> = HashMap::new();
+    for (member_id, _, session) in &sessions {
+        *tracked.entry((member_id, &session.subtask_id)).or_default() += session.duration_seconds;
+    }
+
+    let subtasks = team_sync::load_records::<SubtaskRecord>(conn, TeamEntityKind::Subtask)?;
+    // Subtasks per task, how many are done, and whether the member publishes stored points
+    let mut task_subtasks: HashMap<(&str, &str), (i64, i64, bool)> = HashMap::new();
+    for (member_id, id, subtask) in &subtasks {
+        let counts = task_subtasks.entry((member_id, &subtask.task_id)).or_default();
+        counts.0 += 1;
+        if subtask.status != "done" {
+            continue;
+        }
+        counts.1 += 1;
+        counts.2 |= subtask.points.is_some();
+
+        let date = subtask.completed_at.as_deref().and_then(|completed_at| timestamp_date(completed_at, settings));
+        if let (Some(participant), Some(date)) = (participants.get_mut(member_id), date) {
+            // Members on older versions publish no points; score those with the local rules
+            let points = subtask.points.unwrap_or_else(|| {
+                let duration = tracked.get(&(member_id.as_str(), id.as_str())).copied().unwrap_or(0);
+                calculate_subtask_points(rules, duration, subtask.estimate_seconds)
+            });
+            participant.completions.push(CompletionFact {
+                date,
+                category: subtask.category.clone(),
+                points,
+            });
+        }
+    }
+
+    for (member_id, id, task) in team_sync::load_records::<TaskRecord>(conn, TeamEntityKind::Task)? {
+        let (total, done, stored) = task_subtasks.get(&(member_id.as_str(), id.as_str())).copied().unwrap_or_default();
+        let date = task.completed_at.as_deref().and_then(|completed_at| timestamp_date(completed_at, settings));
+        if let (Some(participant), Some(date)) = (participants.get_mut(&member_id), date) {
+            let bonus_points = match task.bonus_points {
+                None if !stored && total >= rules.complexity_min_subtasks && done == total => {
+                    Some(rules.complexity_bonus_points)
+                }
+                bonus_points => bonus_points,
+            };
+            if let (true, Some(bonus_points)) = (task.status == "done", bonus_points) {
+                participant.bonuses.push((date, bonus_points));
+            }
+        }
+    }
+
+    for (member_id, _, session) in sessions {
+        let date = timestamp_date(&session.started_at, settings);
+        if let (Some(participant), Some(date)) = (participants.get_mut(&member_id), date) {
+            participant.sessions.push(SessionFact {
+                date,
+                category: session.category,
+                seconds: session.duration_seconds,
+            });
+        }
+    }
+
+    for (member_id, _, entry) in team_sync::load_records::<XpRecord>(conn, TeamEntityKind::Xp)? {
+        let date = timestamp_date(&entry.earned_at, settings);
+        if let (Some(participant), Some(date)) = (participants.get_mut(&member_id), date) {
+            participant.xp.push(XpFact {
+                date,
+                category: entry.category,
+                xp: entry.xp,
+            });
+        }
+    }
+
+    Ok(participants.into_values().collect())
+}
+
+pub fn load_participants(
+    conn: &Connection,
+    source: LeaderboardSource,
+    now: DateTime<Utc>,
+) -> Result<Vec<Participant>, String> {
+    // Work is bucketed by the day it happened in the user's timezone
+    let settings = settings::load_settings(conn)?;
+    match source {
+        LeaderboardSource::Profiles => load_profile_participants(conn, &settings, now),
+        LeaderboardSource::Team => load_team_participants(conn, &settings, now),
+    }
+}
+
+/// Order items by `key`, highest first, and rank with ties sharing a rank (1, 1, 3). Tied
+/// items are listed by name, then id, so the order never depends on load order.
+pub fn rank_by<T, K: Ord>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> K,
+    name_and_id: impl Fn(&T) -> (&str, &str),
+) -> Vec<(i64, T)> {
+    items.sort_by(|a, b| key(b).cmp(&key(a)).then_with(|| name_and_id(a).cmp(&name_and_id(b))));
+
+    let mut ranked: Vec<(i64, T)> = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let rank = match ranked.last() {
+            Some((previous_rank, previous)) if key(previous) == key(&item) => *previous_rank,
+            _ => index as i64 + 1,
+        };
+        ranked.push((rank, item));
+    }
+    ranked
+}
+
+/// Rank participants by value with `rank_by`
+pub fn rank(scores: Vec<(Participant, i64)>) -> Vec<LeaderboardEntry> {
+    rank_by(scores, |(_, value)| *value, |(participant, _)| (&participant.name, &participant.id))
+        .into_iter()
+        .map(|(rank, (participant, value))| LeaderboardEntry {
+            rank,
+            participant_id: participant.id,
+            name: participant.name,
+            value,
+            is_self: participant.is_self,
+        })
+        .collect()
+}
+
+/// Rank profiles or team members by a metric as of `now`. `CategoryXp` needs a category.
+pub fn leaderboard(
+    conn: &Connection,
+    source: LeaderboardSource,
+    metric: LeaderboardMetric,
+    category: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Vec<LeaderboardEntry>, String> {
+    let category = normalize_category(category);
+    if metric == LeaderboardMetric::CategoryXp && category.is_none() {
+        return Err("A category is required for the category XP leaderboard".to_string());
+    }
+
+    let week_start = period_start(settings::local_time_at(conn, now).date(), Granularity::Week);
+    let week_end = week_start + Duration::days(6);
+    let scores = load_participants(conn, source, now)?
+        .into_iter()
+        .map(|participant| {
+            let value = match metric {
+                LeaderboardMetric::WeeklyPoints => participant.points_between(week_start, week_end, category.as_deref()),
+                LeaderboardMetric::CategoryXp => participant.xp_between(NaiveDate::MIN, NaiveDate::MAX, category.as_deref()),
+                LeaderboardMetric::Streak => participant.current_streak,
+            };
+            (participant, value)
+        })
+        .collect();
+
+    Ok(rank(scores))
+}
+
+fn challenge_from_row(row: &rusqlite::Row) -> SqlResult<Challenge> {
+    let metric: String = row.get(2)?;
+    let source: String = row.get(4)?;
+    Ok(Challenge {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        metric: ChallengeMetric::from_str(&metric).unwrap_or(ChallengeMetric::Points),
+        category: row.get(3)?,
+        source: LeaderboardSource::from_str(&source).unwrap_or(LeaderboardSource::Profiles),
+        start_date: row.get(5)?,
+        end_date: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+const CHALLENGE_COLUMNS: &str = "id, name, metric, category, source, start_date, end_date, created_at";
+
+/// The profile or team member the app runs as, for `is_self`
+fn self_id(conn: &Connection, source: LeaderboardSource) -> Result<Option<String>, String> {
+    match source {
+        LeaderboardSource::Profiles => profiles::active_profile_id(conn).map(Some),
+        LeaderboardSource::Team => Ok(team_sync::get_status(conn)?.member_id),
+    }
+}
+
+fn frozen_standings(conn: &Connection, challenge_id: &str) -> Result<Option<Vec<LeaderboardEntry>>, String> {
+    let frozen: Option<String> = conn
+        .query_row("SELECT final_standings FROM challenges WHERE id = ?1", [challenge_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    frozen
+        .map(|json| serde_json::from_str(&json).map_err(|e| format!("Invalid frozen standings: {}", e)))
+        .transpose()
+}
+
+/// Create a time-boxed challenge over an inclusive `YYYY-MM-DD` range
+pub fn create_challenge(
+    conn: &Connection,
+    name: &str,
+    metric: ChallengeMetric,
+    category: Option<&str>,
+    source: LeaderboardSource,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Challenge, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Challenge name cannot be empty".to_string());
+    }
+    parse_date_range(start_date, end_date)?;
+
+    let challenge = Challenge {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        metric,
+        category: normalize_category(category),
+        source,
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO challenges (id, name, metric, category, source, start_date, end_date, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            &challenge.id,
+            &challenge.name,
+            challenge.metric.as_str(),
+            &challenge.category,
+            challenge.source.as_str(),
+            &challenge.start_date,
+            &challenge.end_date,
+            &challenge.created_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(challenge)
+}
+
+/// Challenges, most recent start first
+pub fn list_challenges(conn: &Connection) -> Result<Vec<Challenge>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM challenges ORDER BY start_date DESC, created_at DESC", CHALLENGE_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let challenges = stmt
+        .query_map([], challenge_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(challenges)
+}
+
+pub fn get_challenge(conn: &Connection, challenge_id: &str) -> Result<Challenge, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM challenges WHERE id = ?1", CHALLENGE_COLUMNS),
+        [challenge_id],
+        challenge_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Challenge not found: {}", challenge_id))
+}
+
+/// Scores for a challenge counting only work inside its dates. A finished challenge's
+/// standings are frozen the first time they're read, so later edits don't change the result.
+pub fn challenge_standings(conn: &Connection, challenge_id: &str, now: DateTime<Utc>) -> Result<ChallengeStandings, String> {
+    let challenge = get_challenge(conn, challenge_id)?;
+    let (start, end) = parse_date_range(&challenge.start_date, &challenge.end_date)?;
+    let today = settings::local_time_at(conn, now).date();
+    let status = if today < start {
+        ChallengeStatus::Upcoming
+    } else if today > end {
+        ChallengeStatus::Finished
+    } else {
+        ChallengeStatus::Active
+    };
+
+    let frozen = match status {
+        ChallengeStatus::Finished => frozen_standings(conn, &challenge.id)?,
+        _ => None,
+    };
+    let entries = match frozen {
+        Some(mut entries) => {
+            let self_id = self_id(conn, challenge.source)?;
+            for entry in &mut entries {
+                entry.is_self = self_id.as_ref() == Some(&entry.participant_id);
+            }
+            entries
+        }
+        None => {
+            let category = challenge.category.as_deref();
+            let scores = load_participants(conn, challenge.source, now)?
+                .into_iter()
+                .map(|participant| {
+                    let value = match challenge.metric {
+                        ChallengeMetric::FocusSeconds => participant.focus_seconds_between(start, end, category),
+                        ChallengeMetric::Points => participant.points_between(start, end, category),
+                        ChallengeMetric::SubtasksCompleted => participant.subtasks_between(start, end, category),
+                        ChallengeMetric::Xp => participant.xp_between(start, end, category),
+                    };
+                    (participant, value)
+                })
+                .collect();
+            let entries = rank(scores);
+            if status == ChallengeStatus::Finished {
+                conn.execute(
+                    "UPDATE challenges SET final_standings = ?1 WHERE id = ?2",
+                    params![serde_json::to_string(&entries).map_err(|e| e.to_string())?, &challenge.id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            entries
+        }
+    };
+
+    let winners = match status {
+        ChallengeStatus::Finished => entries
+            .iter()
+            .filter(|entry| entry.rank == 1 && entry.value > 0)
+            .map(|entry| entry.participant_id.clone())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(ChallengeStandings {
+        challenge,
+        status,
+        entries,
+        winners,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_tables, record_missing_points};
+    use crate::gamification::ScoringRules;
+    use crate::timer;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        // A Wednesday
+        Utc.with_ymd_and_hms(2025, 6, 11, 12, 0, 0).unwrap()
+    }
+
+    fn category_id(conn: &Connection, name: &str) -> String {
+        conn.query_row("SELECT id FROM categories WHERE name = ?1", [name], |row| row.get(0))
+            .unwrap()
+    }
+
+    /// A finished subtask with one session, completed at `completed_at`
+    fn complete(conn: &Connection, category: &str, seconds: i64, completed_at: &str) {
+        let task = timer::create_task(conn, "Work".to_string(), None, None).unwrap();
+        let category = category_id(conn, category);
+        let subtask = timer::create_subtask(conn, task.id, "Step".to_string(), Some(category.clone()), None).unwrap();
+        conn.execute(
+            "UPDATE subtasks SET status = 'done', completed_at = ?1 WHERE id = ?2",
+            params![completed_at, &subtask.id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+             VALUES (?1, ?2, ?3, ?3, ?4)",
+            params![Uuid::new_v4().to_string(), &subtask.id, completed_at, seconds],
+        )
+        .unwrap();
+        record_missing_points(conn, &ScoringRules::default()).unwrap();
+        conn.execute(
+            "INSERT INTO xp_ledger (id, category_id, subtask_id, xp, earned_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![Uuid::new_v4().to_string(), &category, &subtask.id, seconds, completed_at],
+        )
+        .unwrap();
+    }
+
+    /// Three profiles: Ana and Ben tie this week, Cleo worked last week
+    fn seeded() -> (Connection, Vec<String>) {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        settings::update_settings(&conn, serde_json::json!({ "timezone": "UTC" }), "2025-06-01T00:00:00+00:00").unwrap();
+        conn.execute("UPDATE user_profile SET name = 'Ana'", []).unwrap();
+        let ana = profiles::active_profile_id(&conn).unwrap();
+        let ben = profiles::create_profile(&conn, "Ben").unwrap().id;
+        let cleo = profiles::create_profile(&conn, "Cleo").unwrap().id;
+
+        complete(&conn, "backend", 3600, "2025-06-09T10:00:00+00:00");
+        profiles::switch_profile(&conn, &ben).unwrap();
+        complete(&conn, "frontend", 1800, "2025-06-10T10:00:00+00:00");
+        profiles::switch_profile(&conn, &cleo).unwrap();
+        complete(&conn, "backend", 7200, "2025-06-04T10:00:00+00:00");
+        complete(&conn, "backend", 600, "2025-06-04T11:00:00+00:00");
+        profiles::switch_profile(&conn, &ana).unwrap();
+
+        conn.execute("UPDATE user_profile SET current_streak = 4, last_work_date = '2025-06-10' WHERE id = ?1", [&ana])
+            .unwrap();
+        conn.execute("UPDATE user_profile SET current_streak = 9, last_work_date = '2025-06-01' WHERE id = ?1", [&cleo])
+            .unwrap();
+        (conn, vec![ana, ben, cleo])
+    }
+
+    fn ranked(entries: &[LeaderboardEntry]) -> Vec<(i64, &str, i64)> {
+        entries.iter().map(|e| (e.rank, e.name.as_str(), e.value)).collect()
+    }
+
+    #[test]
+    fn test_leaderboards_rank_with_shared_ties() {
+        let (conn, _) = seeded();
+        let weekly = leaderboard(&conn, LeaderboardSource::Profiles, LeaderboardMetric::WeeklyPoints, None, now()).unwrap();
+        assert_eq!(ranked(&weekly), vec![(1, "Ana", 10), (1, "Ben", 10), (3, "Cleo", 0)]);
+        assert!(weekly[0].is_self);
+
+        let backend =
+            leaderboard(&conn, LeaderboardSource::Profiles, LeaderboardMetric::CategoryXp, Some("Backend"), now()).unwrap();
+        assert_eq!(ranked(&backend), vec![(1, "Cleo", 7800), (2, "Ana", 3600), (3, "Ben", 0)]);
+        assert!(leaderboard(&conn, LeaderboardSource::Profiles, LeaderboardMetric::CategoryXp, None, now()).is_err());
+
+        // Cleo's streak lapsed without work yesterday or today
+        let streaks = leaderboard(&conn, LeaderboardSource::Profiles, LeaderboardMetric::Streak, None, now()).unwrap();
+        assert_eq!(ranked(&streaks), vec![(1, "Ana", 4), (2, "Ben", 0), (2, "Cleo", 0)]);
+
+        // Fourteen hours east it's already Thursday, so Ana's Tuesday no longer counts
+        settings::update_settings(&conn, serde_json::json!({ "timezone": "+14:00" }), "2025-06-11T12:00:00+00:00").unwrap();
+        let streaks = leaderboard(&conn, LeaderboardSource::Profiles, LeaderboardMetric::Streak, None, now()).unwrap();
+        assert_eq!(streaks[0].value, 0);
+    }
+
+    #[test]
+    fn test_challenge_counts_only_its_dates_and_category() {
+        let (conn, ids) = seeded();
+        let sprint = create_challenge(
+            &conn,
+            "Most backend hours this sprint",
+            ChallengeMetric::FocusSeconds,
+            Some("backend"),
+            LeaderboardSource::Profiles,
+            "2025-06-02",
+            "2025-06-08",
+        )
+        .unwrap();
+        assert!(create_challenge(&conn, "Backwards", ChallengeMetric::Points, None, LeaderboardSource::Team, "2025-06-08", "2025-06-02")
+            .is_err());
+
+        let standings = challenge_standings(&conn, &sprint.id, now()).unwrap();
+        assert_eq!(standings.status, ChallengeStatus::Finished);
+        assert_eq!(ranked(&standings.entries), vec![(1, "Cleo", 7800), (2, "Ana", 0), (2, "Ben", 0)]);
+        assert_eq!(standings.winners, vec![ids[2].clone()]);
+
+        // Finished standings are frozen: work recorded later in the challenge's dates doesn't count
+        complete(&conn, "backend", 9000, "2025-06-05T10:00:00+00:00");
+        let frozen = challenge_standings(&conn, &sprint.id, now()).unwrap();
+        assert_eq!(ranked(&frozen.entries), vec![(1, "Cleo", 7800), (2, "Ana", 0), (2, "Ben", 0)]);
+        assert!(frozen.entries[1].is_self);
+
+        let current = create_challenge(
+            &conn,
+            "Subtasks this week",
+            ChallengeMetric::SubtasksCompleted,
+            None,
+            LeaderboardSource::Profiles,
+            "2025-06-09",
+            "2025-06-15",
+        )
+        .unwrap();
+        let standings = challenge_standings(&conn, &current.id, now()).unwrap();
+        assert_eq!(standings.status, ChallengeStatus::Active);
+        assert!(standings.winners.is_empty());
+        assert_eq!(ranked(&standings.entries), vec![(1, "Ana", 1), (1, "Ben", 1), (3, "Cleo", 0)]);
+        assert_eq!(list_challenges(&conn).unwrap().len(), 2);
+    }
+}
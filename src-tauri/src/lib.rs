@@ -1,8 +1,34 @@
+mod analytics;
+mod archive;
+mod backup;
+mod branch_watch;
+pub mod cli;
 mod commands;
 mod db;
+mod db_location;
+mod encryption;
+mod gamification;
+mod git;
+mod http_api;
+mod ics;
+mod importers;
+mod leaderboard;
 mod models;
+mod outbox;
+mod plugins;
+mod prediction;
+mod profiles;
+mod report;
+mod settings;
+mod team_sync;
+#[cfg(test)]
+mod test_support;
+mod timer;
+mod timesheet;
+mod todo_sync;
 
 use commands::AppState;
+use std::sync::atomic::AtomicBool;
 use std::sync::Mutex;
 use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
@@ -10,6 +36,14 @@ use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+  // `--db <path>` opens a database other than the configured one
+  let mut args: Vec<String> = std::env::args().skip(1).collect();
+  match db_location::take_db_flag(&mut args) {
+    Ok(Some(path)) => db_location::set_flag_path(path),
+    Ok(None) => {}
+    Err(e) => eprintln!("{}", e),
+  }
+
   tauri::Builder::default()
     .setup(|app| {
       if cfg!(debug_assertions) {
@@ -20,9 +54,35 @@ pub fn run() {
         )?;
       }
 
-      // Initialize database
-      let conn = db::init_db().expect("Failed to initialize database");
-      app.manage(AppState { db: Mutex::new(conn) });
+      // Initialize database. An encrypted database without a stored key stays locked
+      // until the user enters the passphrase (unlock_database).
+      let db_path = db::get_db_path();
+      let key = encryption::startup_key(&db_path);
+      let (conn, locked) = match db::open_db(&db_path, key.as_deref()) {
+        Ok(conn) => (conn, false),
+        Err(e) if encryption::is_encrypted(&db_path) => {
+          eprintln!("Database is locked: {}", e);
+          (db::locked_placeholder().expect("Failed to initialize database"), true)
+        }
+        Err(e) => panic!("Failed to initialize database: {}", e),
+      };
+      app.manage(AppState {
+        db: Mutex::new(conn),
+        db_key: Mutex::new(key.filter(|_| !locked)),
+        db_locked: AtomicBool::new(locked),
+        http_api: Mutex::new(None),
+      });
+
+      // Background workers wait for the passphrase when the database is locked
+      if !locked {
+        commands::start_background_workers(app.handle());
+      }
+
+      if commands::tray_settings(app.handle()).start_minimized {
+        if let Some(window) = app.get_webview_window("main") {
+          let _ = window.hide();
+        }
+      }
 
       // Create tray menu
       let show_hide = MenuItem::with_id(app, "show_hide", "Show/Hide", true, None::<&str>)?;
@@ -108,14 +168,26 @@ pub fn run() {
 
       Ok(())
     })
+    .on_window_event(|window, event| {
+      // With close-to-tray on, closing the main window only hides it
+      if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+        if window.label() == "main" && commands::tray_settings(window.app_handle()).close_to_tray {
+          api.prevent_close();
+          let _ = window.hide();
+        }
+      }
+    })
     .invoke_handler(tauri::generate_handler![
       commands::create_task,
       commands::list_tasks_with_active_subtasks,
       commands::get_task_with_subtasks,
       commands::get_task_with_subtasks_and_sessions,
       commands::update_task_status,
+      commands::update_task_estimate,
       commands::delete_task,
       commands::create_subtask,
+      commands::update_subtask_estimate,
+      commands::predict_subtask_duration,
       commands::delete_subtask,
       commands::start_subtask,
       commands::pause_subtask,
@@ -128,7 +200,71 @@ pub fn run() {
       commands::list_categories,
       commands::get_category_experience,
       commands::get_all_category_stats,
+      commands::get_xp_timeline,
+      commands::get_metrics_for_range,
+      commands::compare_periods,
+      commands::get_focus_heatmap,
+      commands::export_report,
+      commands::export_timesheet,
+      commands::export_ics,
+      commands::create_backup,
+      commands::list_backups,
+      commands::restore_backup,
+      commands::get_database_encryption_status,
+      commands::unlock_database,
+      commands::encrypt_database,
+      commands::rekey_database,
+      commands::decrypt_database,
+      commands::get_database_location,
+      commands::move_database,
+      commands::export_data,
+      commands::import_data,
+      commands::import_from_tracker,
+      commands::add_git_repository,
+      commands::list_git_repositories,
+      commands::remove_git_repository,
+      commands::scan_git_repositories,
+      commands::add_branch_watcher,
+      commands::list_branch_watchers,
+      commands::set_branch_watcher_switch_timer,
+      commands::remove_branch_watcher,
+      commands::link_todo_file,
+      commands::list_todo_files,
+      commands::unlink_todo_file,
+      commands::sync_todo_file,
+      commands::add_webhook_endpoint,
+      commands::list_webhook_endpoints,
+      commands::set_webhook_endpoint_enabled,
+      commands::remove_webhook_endpoint,
+      commands::list_outbox,
+      commands::retry_outbox_entry,
+      commands::add_plugin,
+      commands::list_plugins,
+      commands::update_plugin_source,
+      commands::set_plugin_enabled,
+      commands::remove_plugin,
+      commands::get_team_sync_status,
+      commands::set_team_sync_folder,
+      commands::sync_team_now,
+      commands::list_team_members,
+      commands::get_team_leaderboard,
+      commands::get_leaderboard,
+      commands::create_challenge,
+      commands::list_challenges,
+      commands::get_challenge_standings,
+      commands::delete_challenge,
+      commands::get_http_api_status,
+      commands::set_http_api_enabled,
+      commands::regenerate_http_api_token,
+      commands::get_estimation_accuracy,
       commands::get_user_profile,
+      commands::list_profiles,
+      commands::create_profile,
+      commands::rename_profile,
+      commands::switch_profile,
+      commands::delete_profile,
+      commands::get_settings,
+      commands::update_settings,
       commands::minimize_to_tray,
       commands::restore_from_tray,
     ])
@@ -55,6 +55,27 @@ impl SubtaskStatus {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Granularity {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "day" => Some(Granularity::Day),
+            "week" => Some(Granularity::Week),
+            "month" => Some(Granularity::Month),
+            "year" => Some(Granularity::Year),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Task {
@@ -65,6 +86,7 @@ pub struct Task {
     pub created_at: String,
     pub updated_at: String,
     pub completed_at: Option<String>,
+    pub estimate_seconds: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -109,6 +131,7 @@ pub struct Subtask {
     pub total_time_seconds: Option<i64>,
     pub category_id: Option<String>,
     pub category: Option<Category>,
+    pub estimate_seconds: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,6 +156,7 @@ pub struct TaskWithSubtasks {
     pub created_at: String,
     pub updated_at: String,
     pub completed_at: Option<String>,
+    pub estimate_seconds: Option<i64>,
     pub subtasks: Vec<Subtask>,
 }
 
@@ -171,6 +195,7 @@ pub struct TaskMetrics {
     pub task_id: String,
     pub task_title: String,
     pub total_time_seconds: i64,
+    pub estimate_seconds: Option<i64>,
     pub total_points: i64,
     pub subtasks_completed: i64,
     pub subtasks_total: i64,
@@ -198,6 +223,8 @@ pub struct SubtaskCompletion {
 pub struct SubtaskWithSession {
     pub subtask: Subtask,
     pub session: Option<TimeSession>,
+    #[serde(default)]
+    pub commits: Vec<CommitLink>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -210,6 +237,7 @@ pub struct TaskWithSubtasksAndSessions {
     pub created_at: String,
     pub updated_at: String,
     pub completed_at: Option<String>,
+    pub estimate_seconds: Option<i64>,
     pub subtasks_with_sessions: Vec<SubtaskWithSession>,
 }
 
@@ -238,6 +266,9 @@ pub struct GeneralMetrics {
 #[serde(rename_all = "camelCase")]
 pub struct UserProfile {
     pub id: String,
+    pub name: String,
+    // The profile the app is scoped to
+    pub active: bool,
     pub level: i64,
     pub total_xp: i64,
     pub current_title: String,
@@ -249,4 +280,778 @@ pub struct UserProfile {
     // Computed fields
     pub xp_for_next_level: i64,
     pub progress_percentage: f64,
+    pub task_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XpTimelinePoint {
+    pub period_start: String,
+    pub period_end: String,
+    pub xp_gained: i64,
+    // Cumulative values at the end of the period
+    pub total_xp: i64,
+    pub level: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryXpSeries {
+    pub category: Category,
+    pub points: Vec<XpTimelinePoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XpTimeline {
+    pub start_date: String,
+    pub end_date: String,
+    pub granularity: Granularity,
+    pub global: Vec<XpTimelinePoint>,
+    pub categories: Vec<CategoryXpSeries>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryBreakdown {
+    pub category: Option<Category>,
+    pub focused_seconds: i64,
+    pub points: i64,
+    pub subtasks_completed: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodMetrics {
+    pub period_start: String,
+    pub period_end: String,
+    pub points: i64,
+    pub subtasks_completed: i64,
+    pub tasks_completed: i64,
+    pub focused_seconds: i64,
+    pub average_completion_time_seconds: f64,
+    pub categories: Vec<CategoryBreakdown>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeMetrics {
+    pub start_date: String,
+    pub end_date: String,
+    pub granularity: Granularity,
+    pub periods: Vec<PeriodMetrics>,
+    pub totals: PeriodMetrics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricDelta {
+    pub current: f64,
+    pub previous: f64,
+    pub delta: f64,
+    // None when the previous value is zero
+    pub percent_change: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryTimeDelta {
+    pub category: Option<Category>,
+    pub focused_seconds: MetricDelta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodComparison {
+    pub current: PeriodMetrics,
+    pub previous: PeriodMetrics,
+    pub focused_seconds: MetricDelta,
+    pub points: MetricDelta,
+    pub subtasks_completed: MetricDelta,
+    pub tasks_completed: MetricDelta,
+    pub average_completion_time_seconds: MetricDelta,
+    pub categories: Vec<CategoryTimeDelta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusHeatmap {
+    // cells[weekday][hour] in seconds, weekday 0 = Monday, hours in UTC
+    pub cells: Vec<Vec<i64>>,
+    pub total_seconds: i64,
+    pub category_id: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimationStats {
+    pub samples: i64,
+    pub estimated_seconds: i64,
+    pub actual_seconds: i64,
+    // > 1.0 means work took longer than estimated
+    pub actual_to_estimate_ratio: f64,
+    // Share of subtasks that took longer / less time than estimated
+    pub underestimated_ratio: f64,
+    pub overestimated_ratio: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryEstimation {
+    pub category: Option<Category>,
+    pub stats: EstimationStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimationPeriod {
+    pub period_start: String,
+    pub period_end: String,
+    pub overall: EstimationStats,
+    pub categories: Vec<CategoryEstimation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimationAccuracy {
+    pub start_date: String,
+    pub end_date: String,
+    pub granularity: Granularity,
+    pub overall: EstimationStats,
+    pub categories: Vec<CategoryEstimation>,
+    pub periods: Vec<EstimationPeriod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarSubtask {
+    pub id: String,
+    pub title: String,
+    pub duration_seconds: i64,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DurationPrediction {
+    pub predicted_seconds: i64,
+    // Interquartile range of the durations the prediction is based on
+    pub low_seconds: i64,
+    pub high_seconds: i64,
+    pub sample_size: i64,
+    // False when the category had too few completed subtasks and the global median was used
+    pub based_on_category: bool,
+    pub similar_subtasks: Vec<SimilarSubtask>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimesheetFormat {
+    Csv,
+    Json,
+}
+
+impl TimesheetFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "csv" => Some(TimesheetFormat::Csv),
+            "json" => Some(TimesheetFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimesheetEntry {
+    pub date: String,
+    pub task_id: String,
+    pub task: String,
+    pub subtask_id: String,
+    pub subtask: String,
+    pub category: Option<String>,
+    pub start: String,
+    pub end: String,
+    pub duration_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimesheetDayTotal {
+    pub date: String,
+    pub duration_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timesheet {
+    pub entries: Vec<TimesheetEntry>,
+    pub daily_totals: Vec<TimesheetDayTotal>,
+    pub total_seconds: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    Manual,
+    Automatic,
+    PreRestore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub kind: BackupKind,
+    pub created_at: String,
+    pub size_bytes: u64,
+    // None when the file can't be opened as a database
+    pub schema_version: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseEncryptionStatus {
+    // Whether this build was compiled with SQLCipher
+    pub available: bool,
+    pub encrypted: bool,
+    // Encrypted and still waiting for the passphrase
+    pub locked: bool,
+    // Path of the key file used to unlock at startup, if one exists
+    pub key_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseLocationSource {
+    CliFlag,
+    Env,
+    Portable,
+    SettingsFile,
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseLocation {
+    pub path: String,
+    pub source: DatabaseLocationSource,
+    // Where `move_database` records the new location
+    pub location_file: String,
+    // Leftovers `move_database` could not clean up after switching over
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    Replace,
+    Merge,
+}
+
+impl ImportMode {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "replace" => Some(ImportMode::Replace),
+            "merge" => Some(ImportMode::Merge),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub path: String,
+    pub tasks: i64,
+    pub subtasks: i64,
+    pub categories: i64,
+    pub sessions: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub tasks_imported: i64,
+    pub subtasks_imported: i64,
+    pub sessions_imported: i64,
+    pub categories_created: i64,
+    // Archive categories mapped onto an existing category with the same name
+    pub categories_remapped: i64,
+    // Records skipped because a record with the same id already exists
+    pub duplicates_skipped: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    Toggl,
+    Clockify,
+    TodoTxt,
+}
+
+impl ImportSource {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "toggl" => Some(ImportSource::Toggl),
+            "clockify" => Some(ImportSource::Clockify),
+            "todo_txt" => Some(ImportSource::TodoTxt),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerImportReport {
+    pub source: ImportSource,
+    // When true nothing was written; the counts describe what the import would create
+    pub dry_run: bool,
+    pub tasks_created: i64,
+    pub subtasks_created: i64,
+    pub sessions_created: i64,
+    pub categories_created: i64,
+    // Entries already imported by a previous run
+    pub duplicates_skipped: i64,
+    pub tracked_seconds: i64,
+    pub xp_awarded: i64,
+    // Rows that could not be parsed, with their line number
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveSession {
+    pub session_id: String,
+    pub subtask_id: String,
+    pub subtask_title: String,
+    pub task_id: String,
+    pub task_title: String,
+    pub started_at: String,
+    // False while the session is paused
+    pub running: bool,
+    pub tracked_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    // Sent as `Authorization: Bearer <token>`
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRepository {
+    pub id: String,
+    pub task_id: String,
+    // Top-level directory of the work tree
+    pub path: String,
+    pub created_at: String,
+    pub last_scanned_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitLinkReason {
+    // Committed while one of the subtask's intervals was running
+    Session,
+    // The commit message mentions the subtask's short id (`#1a2b3c4d`)
+    Reference,
+}
+
+impl CommitLinkReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CommitLinkReason::Session => "session",
+            CommitLinkReason::Reference => "reference",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "reference" => CommitLinkReason::Reference,
+            _ => CommitLinkReason::Session,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitLink {
+    pub subtask_id: String,
+    pub repository_id: String,
+    pub repository_path: String,
+    pub commit_hash: String,
+    pub summary: String,
+    pub author: String,
+    pub committed_at: String,
+    pub reason: CommitLinkReason,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitScanSummary {
+    pub repositories_scanned: i64,
+    pub commits_scanned: i64,
+    pub links_created: i64,
+    // Repositories that could not be read, e.g. moved or deleted
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSyncSide {
+    File,
+    App,
+}
+
+impl TodoSyncSide {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "file" => Some(TodoSyncSide::File),
+            "app" => Some(TodoSyncSide::App),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoConflictKind {
+    // Item removed from the file while its subtask was completed or tracked in the app
+    RemovedInFile,
+    // Subtask deleted in the app while its item was checked or unchecked in the file
+    DeletedInApp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoConflict {
+    pub subtask_id: String,
+    pub text: String,
+    pub kind: TodoConflictKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoFile {
+    pub id: String,
+    pub task_id: String,
+    pub path: String,
+    // Last sync that changed either side
+    pub last_synced_at: Option<String>,
+    // Unresolved conflicts from the last sync
+    pub conflicts: Vec<TodoConflict>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoSyncReport {
+    pub subtasks_created: i64,
+    pub subtasks_completed: i64,
+    pub subtasks_reopened: i64,
+    pub subtasks_deleted: i64,
+    pub file_updated: bool,
+    pub conflicts: Vec<TodoConflict>,
+}
+
+impl TodoSyncReport {
+    pub fn changed_app(&self) -> bool {
+        self.subtasks_created + self.subtasks_completed + self.subtasks_reopened + self.subtasks_deleted > 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchWatcher {
+    pub id: String,
+    // Work tree root
+    pub path: String,
+    // Move the running timer to the branch's task on checkout
+    pub switch_timer: bool,
+    pub last_branch: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchSwitch {
+    pub watcher_id: String,
+    pub branch: String,
+    pub task_id: String,
+    pub task_created: bool,
+    // Set when the watcher switched the timer
+    pub running_subtask_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxEventType {
+    SubtaskCompleted,
+    TaskCompleted,
+    LevelUp,
+    StreakMilestone,
+}
+
+impl OutboxEventType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            OutboxEventType::SubtaskCompleted => "subtask_completed",
+            OutboxEventType::TaskCompleted => "task_completed",
+            OutboxEventType::LevelUp => "level_up",
+            OutboxEventType::StreakMilestone => "streak_milestone",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "subtask_completed" => Some(OutboxEventType::SubtaskCompleted),
+            "task_completed" => Some(OutboxEventType::TaskCompleted),
+            "level_up" => Some(OutboxEventType::LevelUp),
+            "streak_milestone" => Some(OutboxEventType::StreakMilestone),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEndpoint {
+    pub id: String,
+    pub url: String,
+    // Empty means every event type
+    pub event_types: Vec<OutboxEventType>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEntry {
+    pub id: String,
+    pub endpoint_id: String,
+    pub url: String,
+    pub event_type: OutboxEventType,
+    // The JSON body POSTed to the endpoint
+    pub payload: serde_json::Value,
+    pub created_at: String,
+    pub attempts: i64,
+    pub next_attempt_at: String,
+    pub delivered_at: Option<String>,
+    pub last_error: Option<String>,
+    // Gave up after the maximum number of attempts
+    pub abandoned: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxRunSummary {
+    pub delivered: i64,
+    pub failed: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Plugin {
+    pub id: String,
+    pub name: String,
+    // Rhai script
+    pub source: String,
+    pub enabled: bool,
+    // Hook functions the script defines
+    pub hooks: Vec<String>,
+    // Most recent script failure, cleared when the source is updated
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginNotification {
+    pub id: String,
+    pub plugin_id: String,
+    pub plugin_name: String,
+    pub title: String,
+    pub body: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamSyncStatus {
+    // None when team sync is off
+    pub folder: Option<String>,
+    // The shared profile's id, which is also our member id in the team
+    pub member_id: Option<String>,
+    pub last_synced_at: Option<String>,
+    pub members: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamSyncSummary {
+    pub changes_written: i64,
+    // Changes from other members applied to the merged view
+    pub changes_merged: i64,
+    pub members: i64,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamMember {
+    pub member_id: String,
+    pub name: String,
+    pub level: i64,
+    pub total_xp: i64,
+    // Zero once a day passes without work
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    pub last_work_date: Option<String>,
+    pub tasks_completed: i64,
+    pub subtasks_completed: i64,
+    pub tracked_seconds: i64,
+    pub is_self: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamLeaderboardEntry {
+    pub rank: i64,
+    pub member_id: String,
+    pub name: String,
+    pub weekly_points: i64,
+    pub total_xp: i64,
+    pub current_streak: i64,
+    pub is_self: bool,
+}
+
+/// Whose data a leaderboard or challenge ranks: this database's profiles or the merged team view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardSource {
+    Profiles,
+    Team,
+}
+
+impl LeaderboardSource {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "profiles" => Some(LeaderboardSource::Profiles),
+            "team" => Some(LeaderboardSource::Team),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaderboardSource::Profiles => "profiles",
+            LeaderboardSource::Team => "team",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    // Points earned this week (Monday to Sunday)
+    WeeklyPoints,
+    // All-time XP in one category
+    CategoryXp,
+    // Current streak in days
+    Streak,
+}
+
+impl LeaderboardMetric {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "weekly_points" => Some(LeaderboardMetric::WeeklyPoints),
+            "category_xp" => Some(LeaderboardMetric::CategoryXp),
+            "streak" => Some(LeaderboardMetric::Streak),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeMetric {
+    FocusSeconds,
+    Points,
+    SubtasksCompleted,
+    Xp,
+}
+
+impl ChallengeMetric {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "focus_seconds" => Some(ChallengeMetric::FocusSeconds),
+            "points" => Some(ChallengeMetric::Points),
+            "subtasks_completed" => Some(ChallengeMetric::SubtasksCompleted),
+            "xp" => Some(ChallengeMetric::Xp),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeMetric::FocusSeconds => "focus_seconds",
+            ChallengeMetric::Points => "points",
+            ChallengeMetric::SubtasksCompleted => "subtasks_completed",
+            ChallengeMetric::Xp => "xp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    // Participants with equal values share a rank (1, 1, 3…) and are listed by name
+    pub rank: i64,
+    // Profile id or team member id
+    pub participant_id: String,
+    pub name: String,
+    pub value: i64,
+    pub is_self: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Challenge {
+    pub id: String,
+    pub name: String,
+    pub metric: ChallengeMetric,
+    // Only work in this category counts; None counts everything
+    pub category: Option<String>,
+    pub source: LeaderboardSource,
+    // Inclusive YYYY-MM-DD range
+    pub start_date: String,
+    pub end_date: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeStatus {
+    Upcoming,
+    Active,
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeStandings {
+    pub challenge: Challenge,
+    pub status: ChallengeStatus,
+    pub entries: Vec<LeaderboardEntry>,
+    // Participant ids ranked first with a non-zero score, once the challenge is finished
+    pub winners: Vec<String>,
 }
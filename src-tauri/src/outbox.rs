@@ -0,0 +1,385 @@
+use crate::models::*;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Deliveries are abandoned after this many failed attempts (about 2 hours of retries)
+pub const MAX_ATTEMPTS: i64 = 8;
+
+const FIRST_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 3600;
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+// Delivered entries are kept this long for the delivery log
+const RETENTION_DAYS: i64 = 30;
+
+/// Delay before retrying after the given number of failed attempts: 30 s, 1 min, 2 min… capped at 1 h
+pub fn retry_delay(attempts: i64) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    Duration::seconds((FIRST_RETRY_SECONDS * 2i64.pow(exponent)).min(MAX_RETRY_SECONDS))
+}
+
+fn parse_event_types(value: &str) -> Vec<OutboxEventType> {
+    value.split(',').filter_map(OutboxEventType::from_str).collect()
+}
+
+fn endpoint_from_row(row: &rusqlite::Row) -> SqlResult<WebhookEndpoint> {
+    Ok(WebhookEndpoint {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        event_types: parse_event_types(&row.get::<_, String>(2)?),
+        enabled: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// Register a URL to receive events; an empty `event_types` subscribes to all of them
+pub fn add_endpoint(conn: &Connection, url: &str, event_types: &[OutboxEventType]) -> Result<WebhookEndpoint, String> {
+    let url = url.trim();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!("Webhook URL must start with http:// or https://: {}", url));
+    }
+
+    let endpoint = WebhookEndpoint {
+        id: Uuid::new_v4().to_string(),
+        url: url.to_string(),
+        event_types: event_types.to_vec(),
+        enabled: true,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    let event_types: Vec<&str> = endpoint.event_types.iter().map(|t| t.as_str()).collect();
+
+    conn.execute(
+        "INSERT INTO webhook_endpoints (id, url, event_types, enabled, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![&endpoint.id, &endpoint.url, event_types.join(","), endpoint.enabled, &endpoint.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(endpoint)
+}
+
+pub fn list_endpoints(conn: &Connection) -> Result<Vec<WebhookEndpoint>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, url, event_types, enabled, created_at FROM webhook_endpoints ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+    let endpoints = stmt
+        .query_map([], endpoint_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(endpoints)
+}
+
+pub fn set_endpoint_enabled(conn: &Connection, endpoint_id: &str, enabled: bool) -> Result<WebhookEndpoint, String> {
+    conn.execute(
+        "UPDATE webhook_endpoints SET enabled = ?1 WHERE id = ?2",
+        params![enabled, endpoint_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT id, url, event_types, enabled, created_at FROM webhook_endpoints WHERE id = ?1",
+        [endpoint_id],
+        endpoint_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Webhook endpoint not found: {}", endpoint_id))
+}
+
+/// Queue an event for every enabled endpoint subscribed to its type. All deliveries of one
+/// event share its id so receivers can deduplicate retries.
+pub fn record_event(conn: &Connection, event_type: OutboxEventType, data: Value, occurred_at: &str) -> Result<(), String> {
+    let endpoints: Vec<WebhookEndpoint> = list_endpoints(conn)?
+        .into_iter()
+        .filter(|endpoint| {
+            endpoint.enabled && (endpoint.event_types.is_empty() || endpoint.event_types.contains(&event_type))
+        })
+        .collect();
+    if endpoints.is_empty() {
+        return Ok(());
+    }
+
+    let payload = json!({
+        "id": Uuid::new_v4().to_string(),
+        "type": event_type.as_str(),
+        "occurredAt": occurred_at,
+        "data": data,
+    })
+    .to_string();
+
+    for endpoint in endpoints {
+        conn.execute(
+            "INSERT INTO outbox (id, endpoint_id, event_type, payload, created_at, attempts, next_attempt_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 0, ?5)",
+            params![Uuid::new_v4().to_string(), &endpoint.id, event_type.as_str(), &payload, occurred_at],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn list_outbox(conn: &Connection, limit: i64) -> Result<Vec<OutboxEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT o.id, o.endpoint_id, e.url, o.event_type, o.payload, o.created_at, o.attempts,
+                    o.next_attempt_at, o.delivered_at, o.last_error
+             FROM outbox o
+             JOIN webhook_endpoints e ON e.id = o.endpoint_id
+             ORDER BY o.created_at DESC, o.id
+             LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let entries = stmt
+        .query_map([limit], |row| {
+            let attempts: i64 = row.get(6)?;
+            let delivered_at: Option<String> = row.get(8)?;
+            Ok(OutboxEntry {
+                id: row.get(0)?,
+                endpoint_id: row.get(1)?,
+                url: row.get(2)?,
+                event_type: OutboxEventType::from_str(&row.get::<_, String>(3)?).unwrap_or(OutboxEventType::SubtaskCompleted),
+                payload: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or(Value::Null),
+                created_at: row.get(5)?,
+                attempts,
+                next_attempt_at: row.get(7)?,
+                abandoned: delivered_at.is_none() && attempts >= MAX_ATTEMPTS,
+                delivered_at,
+                last_error: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(entries)
+}
+
+/// Schedule an abandoned or pending delivery to be attempted again right away
+pub fn retry_entry(conn: &Connection, entry_id: &str, now: DateTime<Utc>) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE outbox SET attempts = 0, next_attempt_at = ?1 WHERE id = ?2 AND delivered_at IS NULL",
+            params![now.to_rfc3339(), entry_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("No undelivered outbox entry {}", entry_id));
+    }
+    Ok(())
+}
+
+struct PendingDelivery {
+    id: String,
+    url: String,
+    event_type: String,
+    payload: String,
+    attempts: i64,
+}
+
+fn due_deliveries(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<PendingDelivery>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT o.id, e.url, o.event_type, o.payload, o.attempts
+             FROM outbox o
+             JOIN webhook_endpoints e ON e.id = o.endpoint_id
+             WHERE o.delivered_at IS NULL AND e.enabled = 1
+               AND o.attempts < ?1 AND o.next_attempt_at <= ?2
+             ORDER BY o.created_at, o.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let deliveries = stmt
+        .query_map(params![MAX_ATTEMPTS, now.to_rfc3339()], |row| {
+            Ok(PendingDelivery {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                event_type: row.get(2)?,
+                payload: row.get(3)?,
+                attempts: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(deliveries)
+}
+
+/// POST one event; any non-2xx status is a failure
+fn post(delivery: &PendingDelivery) -> Result<(), String> {
+    ureq::post(&delivery.url)
+        .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+        .set("Content-Type", "application/json")
+        .set("X-DevFocus-Event", &delivery.event_type)
+        .send_string(&delivery.payload)
+        .map(|_| ())
+        .map_err(|e| match e {
+            ureq::Error::Status(code, _) => format!("HTTP {}", code),
+            e => e.to_string(),
+        })
+}
+
+/// Attempt every due delivery. The database lock is only held around reads and writes,
+/// never across a request, so a slow endpoint can't stall the app.
+pub fn process_due(db: &Mutex<Connection>, now: DateTime<Utc>) -> Result<OutboxRunSummary, String> {
+    let due = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "DELETE FROM outbox WHERE delivered_at IS NOT NULL AND delivered_at < ?1",
+            [(now - Duration::days(RETENTION_DAYS)).to_rfc3339()],
+        )
+        .map_err(|e| e.to_string())?;
+        due_deliveries(&conn, now)?
+    };
+
+    let mut summary = OutboxRunSummary::default();
+    for delivery in due {
+        let result = post(&delivery);
+
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        match result {
+            Ok(()) => {
+                conn.execute(
+                    "UPDATE outbox SET attempts = ?1, delivered_at = ?2, last_error = NULL WHERE id = ?3",
+                    params![delivery.attempts + 1, now.to_rfc3339(), &delivery.id],
+                )
+                .map_err(|e| e.to_string())?;
+                summary.delivered += 1;
+            }
+            Err(error) => {
+                let attempts = delivery.attempts + 1;
+                conn.execute(
+                    "UPDATE outbox SET attempts = ?1, next_attempt_at = ?2, last_error = ?3 WHERE id = ?4",
+                    params![attempts, (now + retry_delay(attempts)).to_rfc3339(), &error, &delivery.id],
+                )
+                .map_err(|e| e.to_string())?;
+                summary.failed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use std::sync::mpsc;
+
+    /// Local HTTP server answering each request with the next queued status
+    fn stub_server(statuses: Vec<u16>) -> (String, mpsc::Receiver<(Option<String>, String)>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", server.server_addr().to_ip().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for status in statuses {
+                let mut request = server.recv().unwrap();
+                let event = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("X-DevFocus-Event"))
+                    .map(|h| h.value.to_string());
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                sender.send((event, body)).unwrap();
+                request.respond(tiny_http::Response::empty(status)).unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+        assert_eq!(retry_delay(20), Duration::seconds(3600));
+    }
+
+    #[test]
+    fn test_events_fan_out_to_subscribed_endpoints() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        add_endpoint(&conn, "http://127.0.0.1:9/all", &[]).unwrap();
+        add_endpoint(&conn, "http://127.0.0.1:9/levels", &[OutboxEventType::LevelUp]).unwrap();
+        assert!(add_endpoint(&conn, "ftp://example.com", &[]).is_err());
+
+        record_event(&conn, OutboxEventType::TaskCompleted, json!({"taskId": "t1"}), "2025-08-01T10:00:00+00:00").unwrap();
+        record_event(&conn, OutboxEventType::LevelUp, json!({"level": 3}), "2025-08-01T10:00:00+00:00").unwrap();
+
+        let entries = list_outbox(&conn, 10).unwrap();
+        assert_eq!(entries.len(), 3);
+        let level_ups: Vec<&OutboxEntry> = entries.iter().filter(|e| e.event_type == OutboxEventType::LevelUp).collect();
+        assert_eq!(level_ups.len(), 2);
+        assert_eq!(level_ups[0].payload["id"], level_ups[1].payload["id"]);
+        assert_eq!(level_ups[0].payload["data"]["level"], 3);
+    }
+
+    #[test]
+    fn test_completing_a_subtask_queues_an_event() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        add_endpoint(&conn, "http://127.0.0.1:9/events", &[OutboxEventType::SubtaskCompleted]).unwrap();
+        let task = crate::timer::create_task(&conn, "Release".to_string(), None, None).unwrap();
+        let subtask = crate::timer::create_subtask(&conn, task.id.clone(), "Tag".to_string(), None, None).unwrap();
+
+        crate::timer::start_subtask(&conn, &subtask.id).unwrap();
+        crate::timer::complete_subtask(&conn, &subtask.id, 120).unwrap();
+
+        let entries = list_outbox(&conn, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].payload["data"]["taskId"], task.id);
+        assert_eq!(entries[0].payload["data"]["timeSpentSeconds"], 120);
+    }
+
+    #[test]
+    fn test_failed_delivery_is_retried_after_backoff() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let (url, received) = stub_server(vec![500, 204]);
+        add_endpoint(&conn, &url, &[]).unwrap();
+        let now = DateTime::parse_from_rfc3339("2025-08-01T10:00:00+00:00").unwrap().with_timezone(&Utc);
+        record_event(&conn, OutboxEventType::SubtaskCompleted, json!({"subtaskId": "s1"}), &now.to_rfc3339()).unwrap();
+        let db = Mutex::new(conn);
+
+        let summary = process_due(&db, now).unwrap();
+        assert_eq!((summary.delivered, summary.failed), (0, 1));
+        let (event, body) = received.recv().unwrap();
+        assert_eq!(event.as_deref(), Some("subtask_completed"));
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["data"]["subtaskId"], "s1");
+
+        // Not due again until the backoff has passed
+        let summary = process_due(&db, now + Duration::seconds(10)).unwrap();
+        assert_eq!((summary.delivered, summary.failed), (0, 0));
+
+        let summary = process_due(&db, now + Duration::seconds(30)).unwrap();
+        assert_eq!((summary.delivered, summary.failed), (1, 0));
+        received.recv().unwrap();
+
+        let entry = list_outbox(&db.lock().unwrap(), 10).unwrap().remove(0);
+        assert_eq!(entry.attempts, 2);
+        assert!(entry.delivered_at.is_some());
+        assert_eq!(entry.last_error, None);
+    }
+
+    #[test]
+    fn test_delivery_is_abandoned_after_max_attempts() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        // Nothing listens on the discard port
+        add_endpoint(&conn, "http://127.0.0.1:9/events", &[]).unwrap();
+        let now = Utc::now();
+        record_event(&conn, OutboxEventType::StreakMilestone, json!({"streak": 7}), &now.to_rfc3339()).unwrap();
+        conn.execute("UPDATE outbox SET attempts = ?1", [MAX_ATTEMPTS - 1]).unwrap();
+        let db = Mutex::new(conn);
+
+        assert_eq!(process_due(&db, now).unwrap().failed, 1);
+        let entry = list_outbox(&db.lock().unwrap(), 10).unwrap().remove(0);
+        assert!(entry.abandoned);
+        assert!(entry.last_error.is_some());
+        assert_eq!(process_due(&db, now + Duration::days(1)).unwrap().failed, 0);
+
+        retry_entry(&db.lock().unwrap(), &entry.id, now).unwrap();
+        assert!(!list_outbox(&db.lock().unwrap(), 10).unwrap()[0].abandoned);
+    }
+}
@@ -0,0 +1,464 @@
+use crate::models::*;
+use chrono::{Datelike, NaiveDateTime, Timelike, Utc};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use uuid::Uuid;
+
+/// Functions a plugin script may define; each receives an `event` map
+pub const HOOKS: [&str; 4] = ["on_session_start", "on_session_stop", "on_subtask_completed", "calculate_xp"];
+
+// Sandbox limits: a runaway script is stopped instead of freezing the timer
+const MAX_OPERATIONS: u64 = 200_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 10_000;
+const MAX_COLLECTION_SIZE: usize = 1_000;
+const MAX_NOTIFICATIONS_PER_RUN: usize = 5;
+
+type Notifications = Rc<RefCell<Vec<(String, String)>>>;
+
+thread_local! {
+    // Compiled scripts by plugin id, with the source they were compiled from
+    static COMPILED: RefCell<HashMap<String, (String, Rc<AST>)>> = RefCell::new(HashMap::new());
+}
+
+/// A Rhai engine without access to files, the network or the host, plus a
+/// `notify(title, body)` function collecting notifications for the app
+fn sandboxed_engine(notifications: Notifications) -> Engine {
+    let mut engine = Engine::new();
+    // `import` would otherwise load script files from disk
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+    engine.disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+
+    engine.register_fn("notify", move |title: &str, body: &str| {
+        let mut notifications = notifications.borrow_mut();
+        if notifications.len() < MAX_NOTIFICATIONS_PER_RUN {
+            notifications.push((title.to_string(), body.to_string()));
+        }
+    });
+    engine
+}
+
+/// Compile a script and list the hooks it defines
+pub fn compile(source: &str) -> Result<Vec<String>, String> {
+    let engine = sandboxed_engine(Notifications::default());
+    let ast = engine.compile(source).map_err(|e| format!("Script error: {}", e))?;
+    let hooks = defined_hooks(&ast);
+    if hooks.is_empty() {
+        return Err(format!("The script defines none of the hooks: {}", HOOKS.join(", ")));
+    }
+    Ok(hooks)
+}
+
+/// The plugin's compiled script, reusing the last compilation while its source is unchanged
+fn compiled(engine: &Engine, plugin: &Plugin) -> Result<Rc<AST>, String> {
+    COMPILED.with(|cache| {
+        if let Some((source, ast)) = cache.borrow().get(&plugin.id) {
+            if *source == plugin.source {
+                return Ok(ast.clone());
+            }
+        }
+        let ast = Rc::new(engine.compile(&plugin.source).map_err(|e| e.to_string())?);
+        cache
+            .borrow_mut()
+            .insert(plugin.id.clone(), (plugin.source.clone(), ast.clone()));
+        Ok(ast)
+    })
+}
+
+fn defined_hooks(ast: &AST) -> Vec<String> {
+    HOOKS
+        .iter()
+        .filter(|hook| ast.iter_functions().any(|f| f.name == **hook && f.params.len() == 1))
+        .map(|hook| hook.to_string())
+        .collect()
+}
+
+fn plugin_from_row(row: &rusqlite::Row) -> SqlResult<Plugin> {
+    let hooks: String = row.get(4)?;
+    Ok(Plugin {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source: row.get(2)?,
+        enabled: row.get(3)?,
+        hooks: hooks.split(',').filter(|h| !h.is_empty()).map(str::to_string).collect(),
+        last_error: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+const PLUGIN_COLUMNS: &str = "id, name, source, enabled, hooks, last_error, created_at, updated_at";
+
+pub fn get_plugin(conn: &Connection, plugin_id: &str) -> Result<Plugin, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM plugins WHERE id = ?1", PLUGIN_COLUMNS),
+        [plugin_id],
+        plugin_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Plugin not found: {}", plugin_id))
+}
+
+/// Plugins in the order their hooks run
+pub fn list_plugins(conn: &Connection) -> Result<Vec<Plugin>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM plugins ORDER BY created_at, id", PLUGIN_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let plugins = stmt
+        .query_map([], plugin_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(plugins)
+}
+
+pub fn add_plugin(conn: &Connection, name: &str, source: &str) -> Result<Plugin, String> {
+    let hooks = compile(source)?;
+    let now = Utc::now().to_rfc3339();
+    let id = Uuid::new_v4().to_string();
+
+    conn.execute(
+        "INSERT INTO plugins (id, name, source, enabled, hooks, last_error, created_at, updated_at)
+         VALUES (?1, ?2, ?3, 1, ?4, NULL, ?5, ?5)",
+        params![&id, name.trim(), source, hooks.join(","), &now],
+    )
+    .map_err(|e| e.to_string())?;
+
+    get_plugin(conn, &id)
+}
+
+/// Replace a plugin's script; clears its last error
+pub fn update_plugin_source(conn: &Connection, plugin_id: &str, source: &str) -> Result<Plugin, String> {
+    let hooks = compile(source)?;
+    conn.execute(
+        "UPDATE plugins SET source = ?1, hooks = ?2, last_error = NULL, updated_at = ?3 WHERE id = ?4",
+        params![source, hooks.join(","), Utc::now().to_rfc3339(), plugin_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    get_plugin(conn, plugin_id)
+}
+
+pub fn set_plugin_enabled(conn: &Connection, plugin_id: &str, enabled: bool) -> Result<Plugin, String> {
+    conn.execute(
+        "UPDATE plugins SET enabled = ?1, updated_at = ?2 WHERE id = ?3",
+        params![enabled, Utc::now().to_rfc3339(), plugin_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    get_plugin(conn, plugin_id)
+}
+
+/// Notifications not yet shown, oldest first; they are marked as delivered
+pub fn take_notifications(conn: &Connection) -> Result<Vec<PluginNotification>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.plugin_id, p.name, n.title, n.body, n.created_at
+             FROM plugin_notifications n
+             JOIN plugins p ON p.id = n.plugin_id
+             WHERE n.delivered = 0
+             ORDER BY n.created_at, n.rowid",
+        )
+        .map_err(|e| e.to_string())?;
+    let notifications = stmt
+        .query_map([], |row| {
+            Ok(PluginNotification {
+                id: row.get(0)?,
+                plugin_id: row.get(1)?,
+                plugin_name: row.get(2)?,
+                title: row.get(3)?,
+                body: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    for notification in &notifications {
+        conn.execute("UPDATE plugin_notifications SET delivered = 1 WHERE id = ?1", [&notification.id])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(notifications)
+}
+
+/// The fields every hook event carries: the subtask, its task and category, and the local time
+fn base_event(conn: &Connection, subtask_id: &str, local_time: NaiveDateTime) -> Result<Map, String> {
+    let (subtask_title, task_id, task_title, category_id, category, estimate_seconds): (
+        String,
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+        Option<i64>,
+    ) = conn
+        .query_row(
+            "SELECT s.title, t.id, t.title, s.category_id, c.name, s.estimate_seconds
+             FROM subtasks s
+             JOIN tasks t ON t.id = s.task_id
+             LEFT JOIN categories c ON c.id = s.category_id
+             WHERE s.id = ?1",
+            [subtask_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let optional = |value: Option<Dynamic>| value.unwrap_or(Dynamic::UNIT);
+    let mut event = Map::new();
+    event.insert("subtask_id".into(), subtask_id.to_string().into());
+    event.insert("subtask_title".into(), subtask_title.into());
+    event.insert("task_id".into(), task_id.into());
+    event.insert("task_title".into(), task_title.into());
+    event.insert("category_id".into(), optional(category_id.map(Dynamic::from)));
+    event.insert("category".into(), optional(category.map(Dynamic::from)));
+    event.insert("estimate_seconds".into(), optional(estimate_seconds.map(Dynamic::from)));
+    // 1 = Monday … 7 = Sunday
+    event.insert("weekday".into(), (local_time.weekday().number_from_monday() as i64).into());
+    event.insert("hour".into(), (local_time.hour() as i64).into());
+    event.insert("date".into(), local_time.date().format("%Y-%m-%d").to_string().into());
+    Ok(event)
+}
+
+/// Run `hook` of every enabled plugin defining it, in order. `on_result` sees each plugin's
+/// return value and may update the event passed to the next plugin. Script failures are
+/// recorded on the plugin and skipped; only database errors are returned.
+fn run_hook(
+    conn: &Connection,
+    hook: &str,
+    mut event: Map,
+    mut on_result: impl FnMut(Dynamic, &mut Map) -> Result<(), String>,
+) -> Result<Map, String> {
+    let plugins: Vec<Plugin> = list_plugins(conn)?
+        .into_iter()
+        .filter(|plugin| plugin.enabled && plugin.hooks.iter().any(|h| h == hook))
+        .collect();
+
+    for plugin in plugins {
+        let notifications = Notifications::default();
+        let engine = sandboxed_engine(notifications.clone());
+
+        let result = compiled(&engine, &plugin)
+            .and_then(|ast| {
+                engine
+                    .call_fn::<Dynamic>(&mut Scope::new(), &ast, hook, (Dynamic::from_map(event.clone()),))
+                    .map_err(|e| e.to_string())
+            })
+            .and_then(|value| on_result(value, &mut event));
+
+        let now = Utc::now().to_rfc3339();
+        if let Err(e) = result {
+            conn.execute(
+                "UPDATE plugins SET last_error = ?1 WHERE id = ?2",
+                params![format!("{}: {}", hook, e), &plugin.id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        for (title, body) in notifications.borrow().iter() {
+            conn.execute(
+                "INSERT INTO plugin_notifications (id, plugin_id, title, body, created_at, delivered)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 0)",
+                params![Uuid::new_v4().to_string(), &plugin.id, title, body, &now],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(event)
+}
+
+fn ignore_result(_: Dynamic, _: &mut Map) -> Result<(), String> {
+    Ok(())
+}
+
+/// A session started (`resumed` false) or resumed
+pub fn on_session_start(conn: &Connection, subtask_id: &str, resumed: bool, local_time: NaiveDateTime) -> Result<(), String> {
+    let mut event = base_event(conn, subtask_id, local_time)?;
+    event.insert("resumed".into(), resumed.into());
+    run_hook(conn, "on_session_start", event, ignore_result)?;
+    Ok(())
+}
+
+/// A session was paused or completed (`reason` is "pause" or "complete")
+pub fn on_session_stop(
+    conn: &Connection,
+    subtask_id: &str,
+    reason: &str,
+    duration_seconds: i64,
+    local_time: NaiveDateTime,
+) -> Result<(), String> {
+    let mut event = base_event(conn, subtask_id, local_time)?;
+    event.insert("reason".into(), reason.into());
+    event.insert("duration_seconds".into(), duration_seconds.into());
+    run_hook(conn, "on_session_stop", event, ignore_result)?;
+    Ok(())
+}
+
+/// Let plugins adjust the XP of a completion. Each `calculate_xp` receives the XP returned by
+/// the previous plugin in `event.xp` and returns the new value (unit keeps it); XP never goes negative.
+pub fn calculate_xp(
+    conn: &Connection,
+    subtask_id: &str,
+    duration_seconds: i64,
+    streak: i64,
+    xp: i64,
+    local_time: NaiveDateTime,
+) -> Result<i64, String> {
+    let mut event = base_event(conn, subtask_id, local_time)?;
+    event.insert("duration_seconds".into(), duration_seconds.into());
+    event.insert("streak".into(), streak.into());
+    event.insert("base_xp".into(), xp.into());
+    event.insert("xp".into(), xp.into());
+
+    let event = run_hook(conn, "calculate_xp", event, |value, event| {
+        if value.is_unit() {
+            return Ok(());
+        }
+        let xp = value
+            .as_int()
+            .or_else(|_| value.as_float().map(|f| f.round() as i64))
+            .map_err(|t| format!("calculate_xp must return a number, got {}", t))?;
+        event.insert("xp".into(), xp.max(0).into());
+        Ok(())
+    })?;
+
+    Ok(event.get("xp").and_then(|xp| xp.as_int().ok()).unwrap_or(xp))
+}
+
+pub fn on_subtask_completed(
+    conn: &Connection,
+    subtask_id: &str,
+    duration_seconds: i64,
+    xp_gained: i64,
+    points: i64,
+    local_time: NaiveDateTime,
+) -> Result<(), String> {
+    let mut event = base_event(conn, subtask_id, local_time)?;
+    event.insert("duration_seconds".into(), duration_seconds.into());
+    event.insert("xp".into(), xp_gained.into());
+    event.insert("points".into(), points.into());
+    run_hook(conn, "on_subtask_completed", event, ignore_result)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use crate::timer;
+    use chrono::NaiveDate;
+
+    fn setup(category: &str) -> (Connection, String) {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO categories (id, name, color, created_at) VALUES ('c1', ?1, '#000', '2025-08-01')",
+            [category],
+        )
+        .unwrap();
+        let task = timer::create_task(&conn, "Release".to_string(), None, None).unwrap();
+        let subtask = timer::create_subtask(&conn, task.id, "Review PR".to_string(), Some("c1".to_string()), None).unwrap();
+        (conn, subtask.id)
+    }
+
+    fn at(date: &str, hour: u32) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    const FRIDAY_REVIEWS: &str = r#"
+        fn calculate_xp(event) {
+            if event.category == "Code Review" && event.weekday == 5 {
+                notify("Review Friday", `Double XP for ${event.subtask_title}`);
+                return event.xp * 2;
+            }
+        }
+    "#;
+
+    #[test]
+    fn test_compile_lists_hooks() {
+        assert_eq!(compile(FRIDAY_REVIEWS).unwrap(), vec!["calculate_xp"]);
+        assert!(compile("fn helper(x) { x }").is_err());
+        assert!(compile("fn calculate_xp(event) {").is_err());
+    }
+
+    #[test]
+    fn test_scripts_cannot_import_files() {
+        let (conn, subtask_id) = setup("Backend");
+        let dir = crate::test_support::TempDir::new("plugin");
+        std::fs::write(dir.path().join("bonus.rhai"), "fn xp() { 1000 }").unwrap();
+        let module = dir.path().join("bonus").to_string_lossy().replace('\\', "/");
+        let plugin = add_plugin(
+            &conn,
+            "Import",
+            &format!("fn calculate_xp(event) {{ import \"{}\" as bonus; bonus::xp() }}", module),
+        )
+        .unwrap();
+
+        assert_eq!(calculate_xp(&conn, &subtask_id, 60, 0, 60, at("2025-06-06", 9)).unwrap(), 60);
+        assert!(get_plugin(&conn, &plugin.id).unwrap().last_error.unwrap().contains("bonus"));
+    }
+
+    #[test]
+    fn test_calculate_xp_chains_plugins_and_queues_notifications() {
+        let (conn, subtask_id) = setup("Code Review");
+        add_plugin(&conn, "Friday reviews", FRIDAY_REVIEWS).unwrap();
+        add_plugin(&conn, "Bonus", "fn calculate_xp(event) { event.xp + 10 }").unwrap();
+
+        // 2025-08-01 is a Friday
+        assert_eq!(calculate_xp(&conn, &subtask_id, 600, 0, 600, at("2025-08-01", 16)).unwrap(), 1210);
+        assert_eq!(calculate_xp(&conn, &subtask_id, 600, 0, 600, at("2025-08-04", 16)).unwrap(), 610);
+
+        let notifications = take_notifications(&conn).unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].plugin_name, "Friday reviews");
+        assert_eq!(notifications[0].body, "Double XP for Review PR");
+        assert!(take_notifications(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_failing_plugin_is_recorded_and_skipped() {
+        let (conn, subtask_id) = setup("Code Review");
+        let looping = add_plugin(&conn, "Loop", "fn calculate_xp(event) { loop { } }").unwrap();
+        let wrong_type = add_plugin(&conn, "Text", "fn calculate_xp(event) { \"lots\" }").unwrap();
+        let disabled = add_plugin(&conn, "Off", "fn calculate_xp(event) { 0 }").unwrap();
+        set_plugin_enabled(&conn, &disabled.id, false).unwrap();
+
+        assert_eq!(calculate_xp(&conn, &subtask_id, 600, 0, 600, at("2025-08-01", 9)).unwrap(), 600);
+        assert!(get_plugin(&conn, &looping.id).unwrap().last_error.is_some());
+        assert!(get_plugin(&conn, &wrong_type.id).unwrap().last_error.unwrap().contains("must return a number"));
+        assert_eq!(get_plugin(&conn, &disabled.id).unwrap().last_error, None);
+
+        // The cached script is recompiled once the source changes
+        update_plugin_source(&conn, &wrong_type.id, "fn calculate_xp(event) { event.xp + 1 }").unwrap();
+        assert_eq!(calculate_xp(&conn, &subtask_id, 600, 0, 600, at("2025-08-01", 9)).unwrap(), 601);
+    }
+
+    #[test]
+    fn test_completion_applies_plugin_xp() {
+        let (conn, subtask_id) = setup("Code Review");
+        add_plugin(
+            &conn,
+            "Triple",
+            "fn calculate_xp(event) { event.xp * 3 }\nfn on_session_stop(event) { notify(event.reason, \"\") }",
+        )
+        .unwrap();
+
+        timer::start_subtask(&conn, &subtask_id).unwrap();
+        let completion = timer::complete_subtask(&conn, &subtask_id, 100).unwrap();
+        assert_eq!(completion.xp_gained, 300);
+
+        let notifications = take_notifications(&conn).unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].title, "complete");
+    }
+}
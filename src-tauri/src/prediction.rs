@@ -0,0 +1,294 @@
+use crate::models::*;
+use crate::profiles::active_profile_id;
+use rusqlite::{Connection, Result as SqlResult};
+use std::collections::HashSet;
+
+/// Neighbours considered when predicting from similar titles
+const MAX_NEIGHBOURS: usize = 5;
+
+/// Minimum title similarity (Jaccard) for a completed subtask to count as a neighbour
+const MIN_SIMILARITY: f64 = 0.2;
+
+/// Minimum completed subtasks in a category before its median is trusted
+const MIN_CATEGORY_SAMPLES: usize = 3;
+
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "de", "del", "el", "en", "for", "in", "la", "las", "los", "of", "on", "para",
+    "por", "the", "to", "un", "una", "with", "y",
+];
+
+/// A completed subtask used as training data
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub id: String,
+    pub title: String,
+    pub category_id: Option<String>,
+    pub duration_seconds: i64,
+    tokens: HashSet<String>,
+}
+
+impl Sample {
+    pub fn new(id: String, title: String, category_id: Option<String>, duration_seconds: i64) -> Self {
+        let tokens = tokenize(&title);
+        Sample {
+            id,
+            title,
+            category_id,
+            duration_seconds,
+            tokens,
+        }
+    }
+}
+
+/// Lowercase alphanumeric tokens of a title, without stopwords
+fn tokenize(title: &str) -> HashSet<String> {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.len() > 1 && !STOPWORDS.contains(token))
+        .map(str::to_string)
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let intersection = a.intersection(b).count() as f64;
+    let union = a.union(b).count() as f64;
+    intersection / union
+}
+
+/// Value at quantile `q` (0.0..=1.0) of an already sorted slice
+fn quantile(sorted: &[i64], q: f64) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let position = q * (sorted.len() - 1) as f64;
+    let lower = sorted[position.floor() as usize] as f64;
+    let upper = sorted[position.ceil() as usize] as f64;
+    (lower + (upper - lower) * position.fract()).round() as i64
+}
+
+/// Per-category median blended with the durations of completed subtasks
+/// whose titles share tokens with the new one
+pub struct DurationPredictor {
+    samples: Vec<Sample>,
+}
+
+impl DurationPredictor {
+    pub fn new(samples: Vec<Sample>) -> Self {
+        DurationPredictor {
+            samples: samples.into_iter().filter(|s| s.duration_seconds > 0).collect(),
+        }
+    }
+
+    pub fn predict(&self, title: &str, category_id: Option<&str>) -> Option<DurationPrediction> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        // Baseline: median of the category, or of everything if the category is too small
+        let mut category_durations: Vec<i64> = self
+            .samples
+            .iter()
+            .filter(|s| category_id.is_some() && s.category_id.as_deref() == category_id)
+            .map(|s| s.duration_seconds)
+            .collect();
+        let used_category = category_durations.len() >= MIN_CATEGORY_SAMPLES;
+        if !used_category {
+            category_durations = self.samples.iter().map(|s| s.duration_seconds).collect();
+        }
+        category_durations.sort_unstable();
+        let baseline = quantile(&category_durations, 0.5);
+
+        // Neighbours: most similar titles, with a small boost for the same category
+        let tokens = tokenize(title);
+        let mut neighbours: Vec<(&Sample, f64)> = self
+            .samples
+            .iter()
+            .map(|sample| {
+                let mut similarity = jaccard(&tokens, &sample.tokens);
+                if similarity > 0.0 && category_id.is_some() && sample.category_id.as_deref() == category_id {
+                    similarity = (similarity + 0.1).min(1.0);
+                }
+                (sample, similarity)
+            })
+            .filter(|(_, similarity)| *similarity >= MIN_SIMILARITY)
+            .collect();
+        neighbours.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+        neighbours.truncate(MAX_NEIGHBOURS);
+
+        // Blend in log space: the more (and closer) neighbours, the less the baseline weighs
+        let similarity_sum: f64 = neighbours.iter().map(|(_, similarity)| similarity).sum();
+        let predicted_seconds = if similarity_sum > 0.0 {
+            let neighbour_log = neighbours
+                .iter()
+                .map(|(sample, similarity)| similarity * (sample.duration_seconds as f64).ln())
+                .sum::<f64>()
+                / similarity_sum;
+            let weight = similarity_sum / (similarity_sum + 1.0);
+            (weight * neighbour_log + (1.0 - weight) * (baseline as f64).ln()).exp().round() as i64
+        } else {
+            baseline
+        };
+
+        let mut reference: Vec<i64> = if neighbours.len() >= 2 {
+            neighbours.iter().map(|(sample, _)| sample.duration_seconds).collect()
+        } else {
+            category_durations
+        };
+        reference.sort_unstable();
+
+        Some(DurationPrediction {
+            predicted_seconds,
+            low_seconds: quantile(&reference, 0.25).min(predicted_seconds),
+            high_seconds: quantile(&reference, 0.75).max(predicted_seconds),
+            sample_size: self.samples.len() as i64,
+            based_on_category: used_category,
+            similar_subtasks: neighbours
+                .into_iter()
+                .map(|(sample, similarity)| SimilarSubtask {
+                    id: sample.id.clone(),
+                    title: sample.title.clone(),
+                    duration_seconds: sample.duration_seconds,
+                    similarity,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Load the active profile's completed subtasks with their tracked time, oldest first
+pub fn load_samples(conn: &Connection) -> Result<Vec<Sample>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.title, s.category_id, COALESCE(SUM(ts.duration_seconds), 0) as total_time
+             FROM subtasks s
+             JOIN tasks t ON s.task_id = t.id
+             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+             WHERE s.status = 'done' AND t.profile_id = ?1
+             GROUP BY s.id
+             HAVING total_time > 0
+             ORDER BY s.completed_at",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let samples = stmt
+        .query_map([active_profile_id(conn)?], |row| {
+            Ok(Sample::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<Sample>>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Walk-forward backtest: predict each sample using only the samples before it.
+    /// Returns the mean absolute percentage error of the predictor and of a
+    /// global-median baseline.
+    fn backtest(samples: &[Sample], min_history: usize) -> Option<(f64, f64)> {
+        let mut predictor_error = 0.0;
+        let mut baseline_error = 0.0;
+        let mut evaluated = 0;
+
+        for index in min_history..samples.len() {
+            let history = samples[..index].to_vec();
+            let target = &samples[index];
+            let actual = target.duration_seconds as f64;
+
+            let mut durations: Vec<i64> = history.iter().map(|s| s.duration_seconds).collect();
+            durations.sort_unstable();
+            let baseline = quantile(&durations, 0.5) as f64;
+
+            let prediction = DurationPredictor::new(history).predict(&target.title, target.category_id.as_deref())?;
+
+            predictor_error += (prediction.predicted_seconds as f64 - actual).abs() / actual;
+            baseline_error += (baseline - actual).abs() / actual;
+            evaluated += 1;
+        }
+
+        if evaluated == 0 {
+            return None;
+        }
+
+        Some((predictor_error / evaluated as f64, baseline_error / evaluated as f64))
+    }
+
+    /// Deterministic pseudo-random generator so the dataset is reproducible
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    /// Titles combine a verb and an object; objects drive the duration and
+    /// categories scale it, with +/-30% noise
+    fn synthetic_dataset(size: usize) -> Vec<Sample> {
+        let verbs = ["fix", "implement", "refactor", "review", "test"];
+        let objects = [
+            ("login form", 900.0),
+            ("payment api", 5400.0),
+            ("navbar styles", 1200.0),
+            ("database migration", 3600.0),
+            ("search endpoint", 2700.0),
+            ("dark mode", 1800.0),
+        ];
+        let categories = [("frontend", 1.0), ("backend", 1.5), ("css", 0.7)];
+        let mut rng = Lcg(42);
+
+        (0..size)
+            .map(|i| {
+                let verb = verbs[(rng.next() * verbs.len() as f64) as usize];
+                let (object, base) = objects[(rng.next() * objects.len() as f64) as usize];
+                let (category, factor) = categories[(rng.next() * categories.len() as f64) as usize];
+                let noise = 0.7 + rng.next() * 0.6;
+                Sample::new(
+                    format!("s{}", i),
+                    format!("{} {}", verb, object),
+                    Some(category.to_string()),
+                    (base * factor * noise) as i64,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_backtest_beats_global_median() {
+        let samples = synthetic_dataset(400);
+        let (predictor_mape, baseline_mape) = backtest(&samples, 30).unwrap();
+
+        assert!(
+            predictor_mape < baseline_mape * 0.6,
+            "predictor MAPE {:.3} should clearly beat baseline MAPE {:.3}",
+            predictor_mape,
+            baseline_mape
+        );
+        assert!(predictor_mape < 0.35, "predictor MAPE too high: {:.3}", predictor_mape);
+    }
+
+    #[test]
+    fn test_predict_without_similar_titles_uses_category_median() {
+        let samples = vec![
+            Sample::new("a".into(), "alpha".into(), Some("backend".into()), 600),
+            Sample::new("b".into(), "beta".into(), Some("backend".into()), 1200),
+            Sample::new("c".into(), "gamma".into(), Some("backend".into()), 1800),
+            Sample::new("d".into(), "delta".into(), Some("css".into()), 60),
+        ];
+        let prediction = DurationPredictor::new(samples)
+            .predict("write docs", Some("backend"))
+            .unwrap();
+
+        assert_eq!(prediction.predicted_seconds, 1200);
+        assert!(prediction.based_on_category);
+        assert!(prediction.similar_subtasks.is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::db::ensure_category_experience;
+use crate::gamification::*;
+use crate::models::*;
+use crate::timer;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+/// The profile whose tasks, XP and streak the app currently shows
+pub fn active_profile_id(conn: &Connection) -> Result<String, String> {
+    conn.query_row("SELECT id FROM user_profile WHERE is_active = 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "No active profile".to_string())
+}
+
+/// The profile owning the subtask's task; XP and streaks go to it even while another profile is active
+pub fn profile_for_subtask(conn: &Connection, subtask_id: &str) -> Result<String, String> {
+    let profile_id: Option<String> = conn
+        .query_row(
+            "SELECT t.profile_id FROM subtasks s JOIN tasks t ON t.id = s.task_id WHERE s.id = ?1",
+            [subtask_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+
+    match profile_id {
+        Some(profile_id) => Ok(profile_id),
+        None => active_profile_id(conn),
+    }
+}
+
+fn profile_from_row(row: &rusqlite::Row) -> rusqlite::Result<UserProfile> {
+    let level: i64 = row.get(2)?;
+    let total_xp: i64 = row.get(3)?;
+    let xp_for_next = get_xp_for_next_global_level(level);
+    let xp_current_level = calculate_xp_for_global_level(level);
+    let xp_in_current = total_xp - xp_current_level;
+    let xp_needed = xp_for_next - xp_current_level;
+    let progress = if xp_needed > 0 {
+        ((xp_in_current as f64 / xp_needed as f64) * 100.0).clamp(0.0, 100.0)
+    } else {
+        100.0
+    };
+
+    Ok(UserProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        active: row.get(11)?,
+        level,
+        total_xp,
+        current_title: row.get(4)?,
+        current_streak: row.get(5)?,
+        longest_streak: row.get(6)?,
+        last_work_date: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        xp_for_next_level: xp_for_next,
+        progress_percentage: progress,
+        task_count: row.get(10)?,
+    })
+}
+
+const PROFILE_QUERY: &str = "SELECT p.id, p.name, p.level, p.total_xp, p.current_title, p.current_streak,
+            p.longest_streak, p.last_work_date, p.created_at, p.updated_at,
+            (SELECT COUNT(*) FROM tasks t WHERE t.profile_id = p.id), p.is_active
+     FROM user_profile p";
+
+pub fn get_profile(conn: &Connection, profile_id: &str) -> Result<UserProfile, String> {
+    conn.query_row(&format!("{} WHERE p.id = ?1", PROFILE_QUERY), [profile_id], profile_from_row)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Profile not found: {}", profile_id))
+}
+
+pub fn active_profile(conn: &Connection) -> Result<UserProfile, String> {
+    get_profile(conn, &active_profile_id(conn)?)
+}
+
+pub fn list_profiles(conn: &Connection) -> Result<Vec<UserProfile>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY p.created_at, p.id", PROFILE_QUERY))
+        .map_err(|e| e.to_string())?;
+    let profiles = stmt
+        .query_map([], profile_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(profiles)
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn map_name_conflict(name: &str) -> impl Fn(rusqlite::Error) -> String + '_ {
+    move |e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("A profile named {} already exists", name)
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Create an empty profile at level 1; the active profile doesn't change
+pub fn create_profile(conn: &Connection, name: &str) -> Result<UserProfile, String> {
+    let name = validate_name(name)?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+
+    conn.execute(
+        "INSERT INTO user_profile (id, name, is_active, level, total_xp, current_title, current_streak, longest_streak, created_at, updated_at)
+         VALUES (?1, ?2, 0, 1, 0, ?3, 0, 0, ?4, ?4)",
+        params![&id, &name, get_title_for_level(1), &now],
+    )
+    .map_err(map_name_conflict(&name))?;
+    ensure_category_experience(conn).map_err(|e| e.to_string())?;
+
+    get_profile(conn, &id)
+}
+
+pub fn rename_profile(conn: &Connection, profile_id: &str, name: &str) -> Result<UserProfile, String> {
+    let name = validate_name(name)?;
+    conn.execute(
+        "UPDATE user_profile SET name = ?1, updated_at = ?2 WHERE id = ?3",
+        params![&name, Utc::now().to_rfc3339(), profile_id],
+    )
+    .map_err(map_name_conflict(&name))?;
+
+    get_profile(conn, profile_id)
+}
+
+/// Make another profile active. Sessions running for the previous profile are paused so
+/// its timer doesn't keep counting while someone else works.
+pub fn switch_profile(conn: &Connection, profile_id: &str) -> Result<UserProfile, String> {
+    get_profile(conn, profile_id)?;
+    if active_profile_id(conn)? == profile_id {
+        return active_profile(conn);
+    }
+
+    for session in timer::list_active_sessions(conn, Utc::now())?.iter().filter(|s| s.running) {
+        timer::pause_subtask(conn, &session.subtask_id, session.tracked_seconds)?;
+    }
+
+    conn.execute(
+        "UPDATE user_profile SET is_active = (id = ?1)",
+        [profile_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    active_profile(conn)
+}
+
+/// Delete a profile with its tasks and XP. The active profile can't be deleted.
+pub fn delete_profile(conn: &Connection, profile_id: &str) -> Result<(), String> {
+    if active_profile_id(conn)? == profile_id {
+        return Err("Switch to another profile before deleting this one".to_string());
+    }
+    conn.execute("DELETE FROM user_profile WHERE id = ?1", [profile_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+
+    fn category_id(conn: &Connection, name: &str) -> String {
+        conn.query_row("SELECT id FROM categories WHERE name = ?1", [name], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_profiles_keep_separate_tasks_and_xp() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let first = active_profile(&conn).unwrap();
+        assert_eq!(first.name, "Default");
+
+        let task = timer::create_task(&conn, "Solo work".to_string(), None, None).unwrap();
+        let backend = category_id(&conn, "backend");
+        let subtask = timer::create_subtask(&conn, task.id, "API".to_string(), Some(backend.clone()), None).unwrap();
+        timer::start_subtask(&conn, &subtask.id).unwrap();
+
+        let pair = create_profile(&conn, "  Pairing ").unwrap();
+        assert_eq!(pair.name, "Pairing");
+        assert!(!pair.active);
+        assert!(create_profile(&conn, "Pairing").unwrap_err().contains("already exists"));
+
+        let switched = switch_profile(&conn, &pair.id).unwrap();
+        assert!(switched.active);
+        assert_eq!(switched.task_count, 0);
+        // The first profile's timer was paused on switch
+        assert!(timer::list_active_sessions(&conn, Utc::now()).unwrap().is_empty());
+
+        // Completing the first profile's subtask still credits the first profile
+        timer::complete_subtask(&conn, &subtask.id, 600).unwrap();
+        let xp = |profile_id: &str| -> i64 {
+            conn.query_row(
+                "SELECT total_xp FROM category_experience WHERE profile_id = ?1 AND category_id = ?2",
+                params![profile_id, &backend],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(xp(&first.id), 600);
+        assert_eq!(xp(&pair.id), 0);
+        assert_eq!(get_profile(&conn, &first.id).unwrap().total_xp, 600);
+        assert_eq!(get_profile(&conn, &first.id).unwrap().current_streak, 1);
+        assert_eq!(get_profile(&conn, &pair.id).unwrap().current_streak, 0);
+
+        timer::create_task(&conn, "Pair work".to_string(), None, None).unwrap();
+        let profiles = list_profiles(&conn).unwrap();
+        assert_eq!(profiles.iter().map(|p| p.task_count).collect::<Vec<_>>(), vec![1, 1]);
+
+        assert!(delete_profile(&conn, &pair.id).is_err());
+        delete_profile(&conn, &first.id).unwrap();
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn test_migrates_single_profile_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, description TEXT, status TEXT NOT NULL,
+                 created_at TEXT NOT NULL, updated_at TEXT NOT NULL, completed_at TEXT);
+             CREATE TABLE categories (id TEXT PRIMARY KEY, name TEXT UNIQUE NOT NULL, color TEXT NOT NULL, created_at TEXT NOT NULL);
+             CREATE TABLE category_experience (id TEXT PRIMARY KEY, category_id TEXT UNIQUE NOT NULL,
+                 total_xp INTEGER DEFAULT 0, level INTEGER DEFAULT 1, updated_at TEXT NOT NULL);
+             CREATE TABLE user_profile (id TEXT PRIMARY KEY, level INTEGER DEFAULT 1, total_xp INTEGER DEFAULT 0,
+                 current_title TEXT DEFAULT 'novice', current_streak INTEGER DEFAULT 0, longest_streak INTEGER DEFAULT 0,
+                 last_work_date TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL);
+             INSERT INTO user_profile (id, created_at, updated_at) VALUES ('me', '2025-01-01', '2025-01-01');
+             INSERT INTO tasks VALUES ('t1', 'Old task', NULL, 'todo', '2025-01-02', '2025-01-02', NULL);
+             INSERT INTO categories VALUES ('c1', 'backend', '#10b981', '2025-01-01');
+             INSERT INTO category_experience VALUES ('e1', 'c1', 700, 2, '2025-01-02');",
+        )
+        .unwrap();
+
+        create_tables(&conn).unwrap();
+
+        assert_eq!(active_profile_id(&conn).unwrap(), "me");
+        let profile_id: String = conn.query_row("SELECT profile_id FROM tasks WHERE id = 't1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(profile_id, "me");
+        let (owner, xp): (String, i64) = conn
+            .query_row("SELECT profile_id, total_xp FROM category_experience WHERE category_id = 'c1'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((owner.as_str(), xp), ("me", 700));
+    }
+}
@@ -0,0 +1,634 @@
+use crate::analytics::{aggregate_periods, load_activity, parse_date_range, timestamp_date};
+use crate::gamification::calculate_level;
+use crate::profiles::active_profile_id;
+use crate::settings;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rusqlite::{Connection, OptionalExtension, Result as SqlResult};
+use std::collections::HashMap;
+
+/// Tasks listed under "Tareas Principales Completadas"
+const MAX_TOP_TASKS: usize = 5;
+
+/// Task or subtask titles listed under each category or task
+const MAX_HIGHLIGHTS: usize = 3;
+
+/// Categories below this share of the total time are flagged as areas to improve
+const LOW_SHARE_THRESHOLD: f64 = 0.10;
+
+/// Everything the productivity report shows, gathered for a date range
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductivityReport {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub generated_at: NaiveDateTime,
+    pub global_level: i64,
+    pub title: String,
+    pub current_streak: i64,
+    pub focused_seconds: i64,
+    pub tasks_completed: i64,
+    pub subtasks_completed: i64,
+    pub efficient_subtasks: i64,
+    pub points: i64,
+    /// Change in worked time against the previous period of the same length
+    pub focused_change_percent: Option<f64>,
+    pub categories: Vec<ReportCategory>,
+    pub top_tasks: Vec<ReportTask>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportCategory {
+    pub name: String,
+    /// None for time tracked without a category
+    pub level: Option<i64>,
+    pub total_xp: i64,
+    pub xp_gained: i64,
+    /// Level reached during the period, if the category leveled up
+    pub level_up: Option<i64>,
+    pub focused_seconds: i64,
+    pub tasks: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportTask {
+    pub title: String,
+    pub subtasks_completed: i64,
+    pub seconds: i64,
+    pub subtasks: Vec<String>,
+}
+
+struct CompletedSubtask {
+    title: String,
+    task_id: String,
+    task_title: String,
+    category_id: Option<String>,
+    duration_seconds: i64,
+}
+
+/// Gather the report data for an inclusive `YYYY-MM-DD` date range
+pub fn build_report(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    generated_at: NaiveDateTime,
+) -> Result<ProductivityReport, String> {
+    let (start, end) = parse_date_range(start_date, end_date)?;
+    let profile_id = active_profile_id(conn)?;
+    let settings = settings::load_settings(conn)?;
+    let previous_end = start - Duration::days(1);
+    let previous_start = previous_end - (end - start);
+
+    let activity = load_activity(conn)?;
+    let mut periods = aggregate_periods(&activity, &[(previous_start, previous_end), (start, end)]);
+    let current = periods.pop().ok_or_else(|| "Failed to aggregate report period".to_string())?;
+    let previous = periods.pop().ok_or_else(|| "Failed to aggregate previous period".to_string())?;
+
+    let focused_change_percent = if previous.focused_seconds > 0 {
+        Some((current.focused_seconds - previous.focused_seconds) as f64 / previous.focused_seconds as f64 * 100.0)
+    } else {
+        None
+    };
+
+    // Subtasks completed within the range, longest first
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.title, s.task_id, t.title, s.category_id, s.completed_at,
+                    COALESCE(SUM(ts.duration_seconds), 0) as total_time, COALESCE(s.efficient, 0)
+             FROM subtasks s
+             JOIN tasks t ON s.task_id = t.id
+             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+             WHERE s.status = 'done' AND s.completed_at IS NOT NULL AND t.profile_id = ?1
+             GROUP BY s.id
+             ORDER BY total_time DESC, s.title",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([&profile_id], |row| {
+            Ok((
+                CompletedSubtask {
+                    title: row.get(0)?,
+                    task_id: row.get(1)?,
+                    task_title: row.get(2)?,
+                    category_id: row.get(3)?,
+                    duration_seconds: row.get(5)?,
+                },
+                row.get::<_, String>(4)?,
+                row.get::<_, bool>(6)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut efficient_subtasks = 0;
+    let mut completed = Vec::new();
+    for (subtask, completed_at, efficient) in rows {
+        let in_range = timestamp_date(&completed_at, &settings).is_some_and(|date| date >= start && date <= end);
+        if in_range {
+            if efficient {
+                efficient_subtasks += 1;
+            }
+            completed.push(subtask);
+        }
+    }
+
+    // XP gained per category during the range and after it, to tell the level at the end of the range
+    let mut stmt = conn
+        .prepare("SELECT category_id, xp, earned_at FROM xp_ledger WHERE profile_id = ?1")
+        .map_err(|e| e.to_string())?;
+
+    let ledger = stmt
+        .query_map([&profile_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut gained: HashMap<String, (i64, i64)> = HashMap::new(); // in range, after range
+    for (category_id, xp, earned_at) in ledger {
+        match timestamp_date(&earned_at, &settings) {
+            Some(date) if date >= start && date <= end => gained.entry(category_id).or_default().0 += xp,
+            Some(date) if date > end => gained.entry(category_id).or_default().1 += xp,
+            _ => {}
+        }
+    }
+
+    let focused_by_category: HashMap<Option<String>, i64> = current
+        .categories
+        .iter()
+        .map(|breakdown| (breakdown.category.as_ref().map(|c| c.id.clone()), breakdown.focused_seconds))
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.id, c.name, COALESCE(ce.total_xp, 0), COALESCE(ce.level, 1)
+             FROM categories c
+             LEFT JOIN category_experience ce ON c.id = ce.category_id AND ce.profile_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let category_rows = stmt
+        .query_map([&profile_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut categories: Vec<ReportCategory> = category_rows
+        .into_iter()
+        .filter_map(|(id, name, total_xp, level)| {
+            let focused_seconds = focused_by_category.get(&Some(id.clone())).copied().unwrap_or(0);
+            let (xp_gained, xp_after) = gained.get(&id).copied().unwrap_or((0, 0));
+            if focused_seconds == 0 && xp_gained == 0 {
+                return None;
+            }
+
+            let xp_at_end = total_xp - xp_after;
+            let level_at_end = calculate_level(xp_at_end);
+            let level_up = (level_at_end > calculate_level(xp_at_end - xp_gained)).then_some(level_at_end);
+
+            Some(ReportCategory {
+                tasks: task_highlights(&completed, Some(&id)),
+                name,
+                level: Some(level),
+                total_xp,
+                xp_gained,
+                level_up,
+                focused_seconds,
+            })
+        })
+        .collect();
+
+    if let Some(&focused_seconds) = focused_by_category.get(&None).filter(|seconds| **seconds > 0) {
+        categories.push(ReportCategory {
+            name: "Sin categoría".to_string(),
+            level: None,
+            total_xp: 0,
+            xp_gained: 0,
+            level_up: None,
+            focused_seconds,
+            tasks: task_highlights(&completed, None),
+        });
+    }
+
+    categories.sort_by(|a, b| {
+        b.focused_seconds
+            .cmp(&a.focused_seconds)
+            .then_with(|| b.xp_gained.cmp(&a.xp_gained))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let (global_level, title, current_streak) = conn
+        .query_row(
+            "SELECT level, current_title, current_streak FROM user_profile WHERE id = ?1",
+            [&profile_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or((1, "novice".to_string(), 0));
+
+    Ok(ProductivityReport {
+        start,
+        end,
+        generated_at,
+        global_level,
+        title,
+        current_streak,
+        focused_seconds: current.focused_seconds,
+        tasks_completed: current.tasks_completed,
+        subtasks_completed: current.subtasks_completed,
+        efficient_subtasks,
+        points: current.points,
+        focused_change_percent,
+        categories,
+        top_tasks: top_tasks(&completed),
+    })
+}
+
+/// Titles of the tasks with the most completed work in a category
+fn task_highlights(completed: &[CompletedSubtask], category_id: Option<&String>) -> Vec<String> {
+    let mut seconds_by_task: Vec<(&str, i64)> = Vec::new();
+    for subtask in completed.iter().filter(|s| s.category_id.as_ref() == category_id) {
+        match seconds_by_task.iter_mut().find(|(title, _)| *title == subtask.task_title) {
+            Some(entry) => entry.1 += subtask.duration_seconds,
+            None => seconds_by_task.push((&subtask.task_title, subtask.duration_seconds)),
+        }
+    }
+
+    seconds_by_task.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    seconds_by_task
+        .into_iter()
+        .take(MAX_HIGHLIGHTS)
+        .map(|(title, _)| title.to_string())
+        .collect()
+}
+
+/// Tasks with the most tracked time in subtasks completed during the range
+fn top_tasks(completed: &[CompletedSubtask]) -> Vec<ReportTask> {
+    let mut tasks: Vec<(&str, ReportTask)> = Vec::new();
+    for subtask in completed {
+        let index = match tasks.iter().position(|(task_id, _)| *task_id == subtask.task_id) {
+            Some(index) => index,
+            None => {
+                tasks.push((
+                    &subtask.task_id,
+                    ReportTask {
+                        title: subtask.task_title.clone(),
+                        subtasks_completed: 0,
+                        seconds: 0,
+                        subtasks: Vec::new(),
+                    },
+                ));
+                tasks.len() - 1
+            }
+        };
+
+        let task = &mut tasks[index].1;
+        task.subtasks_completed += 1;
+        task.seconds += subtask.duration_seconds;
+        // Subtasks arrive longest first
+        task.subtasks.push(subtask.title.clone());
+    }
+
+    let mut tasks: Vec<ReportTask> = tasks.into_iter().map(|(_, task)| task).collect();
+    tasks.sort_by(|a, b| {
+        b.seconds
+            .cmp(&a.seconds)
+            .then_with(|| b.subtasks_completed.cmp(&a.subtasks_completed))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    tasks.truncate(MAX_TOP_TASKS);
+    tasks
+}
+
+// ============================================================================
+// MARKDOWN RENDERING
+// ============================================================================
+
+fn format_hours(seconds: i64) -> String {
+    format!("{:.1}", seconds as f64 / 3600.0)
+}
+
+/// Integer with thousands separators, e.g. 2,340
+fn format_number(value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let mut formatted = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    if value < 0 {
+        formatted.insert(0, '-');
+    }
+    formatted
+}
+
+fn share_percent(part: i64, total: i64) -> i64 {
+    if total > 0 {
+        (part as f64 / total as f64 * 100.0).round() as i64
+    } else {
+        0
+    }
+}
+
+/// Render the report as Markdown, following the layout sketched in GAMIFICACION.md
+pub fn render_markdown(report: &ProductivityReport) -> String {
+    let mut lines: Vec<String> = vec![
+        "# DevFocus - Reporte de Productividad".to_string(),
+        String::new(),
+        format!("**Nivel Global:** {} ({})", report.global_level, report.title),
+        format!(
+            "**Período:** {} - {}",
+            report.start.format("%d/%m/%Y"),
+            report.end.format("%d/%m/%Y")
+        ),
+        format!("**Generado:** {}", report.generated_at.format("%d/%m/%Y %H:%M")),
+        String::new(),
+        "---".to_string(),
+        String::new(),
+    ];
+
+    let efficiency = share_percent(report.efficient_subtasks, report.subtasks_completed);
+
+    // Executive summary
+    lines.push("## 📊 Resumen Ejecutivo".to_string());
+    lines.push(String::new());
+    lines.push(format!("- **Horas Trabajadas:** {} horas", format_hours(report.focused_seconds)));
+    lines.push(format!("- **Tareas Completadas:** {} tareas", report.tasks_completed));
+    lines.push(format!("- **Subtareas Completadas:** {} subtareas", report.subtasks_completed));
+    lines.push(format!("- **Eficiencia Promedio:** {}%", efficiency));
+    lines.push(format!("- **Puntos Ganados:** {} puntos", format_number(report.points)));
+    lines.push(String::new());
+    lines.push(match report.focused_change_percent {
+        Some(change) => format!(
+            "**Tendencia:** {:+}% horas trabajadas vs período anterior",
+            change.round() as i64
+        ),
+        None => "**Tendencia:** sin actividad en el período anterior".to_string(),
+    });
+    lines.push(String::new());
+    lines.push("---".to_string());
+    lines.push(String::new());
+
+    // Categories
+    lines.push("## 🏆 Categorías y Habilidades".to_string());
+    lines.push(String::new());
+    if report.categories.is_empty() {
+        lines.push("_Sin actividad en este período._".to_string());
+        lines.push(String::new());
+    }
+    for category in &report.categories {
+        match category.level {
+            Some(level) => lines.push(format!("### {} (Nivel {})", category.name, level)),
+            None => lines.push(format!("### {}", category.name)),
+        }
+        if category.level.is_some() {
+            lines.push(format!(
+                "- **XP:** {} (+{} en el período)",
+                format_number(category.total_xp),
+                format_number(category.xp_gained)
+            ));
+        }
+        lines.push(format!(
+            "- **Tiempo:** {} horas ({}% del total)",
+            format_hours(category.focused_seconds),
+            share_percent(category.focused_seconds, report.focused_seconds)
+        ));
+        if !category.tasks.is_empty() {
+            lines.push(format!("- **Tareas:** {}", category.tasks.join(", ")));
+        }
+        lines.push(String::new());
+    }
+    lines.push("---".to_string());
+    lines.push(String::new());
+
+    // Top tasks
+    lines.push("## 📈 Tareas Principales Completadas".to_string());
+    lines.push(String::new());
+    if report.top_tasks.is_empty() {
+        lines.push("_Sin subtareas completadas en este período._".to_string());
+        lines.push(String::new());
+    }
+    for (index, task) in report.top_tasks.iter().enumerate() {
+        lines.push(format!(
+            "{}. **{}** ({} subtareas, {}h)",
+            index + 1,
+            task.title,
+            task.subtasks_completed,
+            format_hours(task.seconds)
+        ));
+        for subtask in task.subtasks.iter().take(MAX_HIGHLIGHTS) {
+            lines.push(format!("   - {}", subtask));
+        }
+        if task.subtasks.len() > MAX_HIGHLIGHTS {
+            lines.push(format!("   - … y {} más", task.subtasks.len() - MAX_HIGHLIGHTS));
+        }
+        lines.push(String::new());
+    }
+    lines.push("---".to_string());
+    lines.push(String::new());
+
+    // Insights
+    let mut strengths = Vec::new();
+    if let Some(top) = report.categories.first().filter(|c| c.focused_seconds > 0) {
+        strengths.push(format!(
+            "Mayor dedicación a {} ({}% del tiempo)",
+            top.name,
+            share_percent(top.focused_seconds, report.focused_seconds)
+        ));
+    }
+    if report.subtasks_completed > 0 && efficiency >= 50 {
+        strengths.push(format!("Alta eficiencia en subtareas ({}%)", efficiency));
+    }
+    if report.current_streak > 0 {
+        strengths.push(format!("Consistencia: {} días de racha", report.current_streak));
+    }
+
+    let mut improvements: Vec<String> = report
+        .categories
+        .iter()
+        .skip(1)
+        .filter(|c| {
+            c.focused_seconds > 0
+                && (c.focused_seconds as f64) < report.focused_seconds as f64 * LOW_SHARE_THRESHOLD
+        })
+        .map(|c| {
+            format!(
+                "{} (solo {}% del tiempo)",
+                c.name,
+                share_percent(c.focused_seconds, report.focused_seconds)
+            )
+        })
+        .collect();
+    if report.subtasks_completed > 0 && efficiency < 50 {
+        improvements.push(format!("Eficiencia por debajo del 50% ({}%): revisar estimaciones", efficiency));
+    }
+
+    if !strengths.is_empty() || !improvements.is_empty() {
+        lines.push("## 💡 Insights".to_string());
+        lines.push(String::new());
+        for (heading, items) in [("Fortalezas", &strengths), ("Áreas de Mejora", &improvements)] {
+            if items.is_empty() {
+                continue;
+            }
+            lines.push(format!("**{}:**", heading));
+            lines.extend(items.iter().map(|item| format!("- {}", item)));
+            lines.push(String::new());
+        }
+        lines.push("---".to_string());
+        lines.push(String::new());
+    }
+
+    // Achievements
+    let mut achievements: Vec<String> = report
+        .categories
+        .iter()
+        .filter_map(|c| c.level_up.map(|level| format!("🏅 Alcanzado nivel {} en {}", level, c.name)))
+        .collect();
+    if report.current_streak > 1 {
+        achievements.push(format!("🔥 Racha de {} días consecutivos", report.current_streak));
+    }
+    if report.efficient_subtasks > 0 {
+        achievements.push(format!(
+            "⚡ {} subtareas completadas de forma eficiente",
+            report.efficient_subtasks
+        ));
+    }
+
+    if !achievements.is_empty() {
+        lines.push("## 🎯 Logros Destacados".to_string());
+        lines.push(String::new());
+        lines.extend(achievements.iter().map(|achievement| format!("- {}", achievement)));
+        lines.push(String::new());
+        lines.push("---".to_string());
+        lines.push(String::new());
+    }
+
+    lines.push("*Generado automáticamente por DevFocus*".to_string());
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_tables, record_missing_points};
+    use crate::gamification::{record_xp_gain, ScoringRules};
+
+    fn generated_at() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 11, 1)
+            .unwrap()
+            .and_hms_opt(14, 30, 0)
+            .unwrap()
+    }
+
+    fn category_id(conn: &Connection, name: &str) -> String {
+        conn.query_row("SELECT id FROM categories WHERE name = ?1", [name], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn add_task(conn: &Connection, id: &str, title: &str, completed_at: Option<&str>) {
+        conn.execute(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at, completed_at)
+             VALUES (?1, ?2, ?3, '2025-09-01T09:00:00+00:00', '2025-09-01T09:00:00+00:00', ?4)",
+            rusqlite::params![id, title, if completed_at.is_some() { "done" } else { "in_progress" }, completed_at],
+        )
+        .unwrap();
+    }
+
+    /// Completed subtask with a single session that started one duration before `completed_at`
+    fn add_done_subtask(
+        conn: &Connection,
+        id: &str,
+        task_id: &str,
+        title: &str,
+        category: &str,
+        duration_seconds: i64,
+        completed_at: &str,
+    ) {
+        conn.execute(
+            "INSERT INTO subtasks (id, task_id, title, status, category_id, created_at, updated_at, completed_at)
+             VALUES (?1, ?2, ?3, 'done', ?4, '2025-09-01T09:00:00+00:00', ?5, ?5)",
+            rusqlite::params![id, task_id, title, category, completed_at],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+             VALUES (?1, ?2, ?3, ?3, ?4)",
+            rusqlite::params![format!("session-{}", id), id, completed_at, duration_seconds],
+        )
+        .unwrap();
+    }
+
+    fn seeded_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let frontend = category_id(&conn, "frontend");
+        let backend = category_id(&conn, "backend");
+
+        add_task(&conn, "t1", "Dashboard Analytics", Some("2025-10-20T18:00:00+00:00"));
+        add_task(&conn, "t2", "Refactor Authentication", None);
+
+        add_done_subtask(&conn, "s1", "t1", "Charts integration", &frontend, 7200, "2025-10-06T12:00:00+00:00");
+        add_done_subtask(&conn, "s2", "t1", "Real-time updates", &frontend, 1200, "2025-10-13T12:00:00+00:00");
+        add_done_subtask(&conn, "s3", "t2", "JWT implementation", &backend, 5400, "2025-10-15T12:00:00+00:00");
+        add_done_subtask(&conn, "s4", "t1", "Empty state", &backend, 600, "2025-10-20T12:00:00+00:00");
+        // Previous month, only counts towards the trend
+        add_done_subtask(&conn, "s0", "t2", "Session management", &backend, 7200, "2025-09-10T12:00:00+00:00");
+        record_missing_points(&conn, &ScoringRules::default()).unwrap();
+
+        for (category, xp, earned_at) in [
+            (&backend, 7200, "2025-09-10T12:00:00+00:00"),
+            (&frontend, 8400, "2025-10-13T12:00:00+00:00"),
+            (&backend, 6000, "2025-10-20T12:00:00+00:00"),
+        ] {
+            record_xp_gain(&conn, category, None, xp, earned_at).unwrap();
+        }
+        conn.execute(
+            "UPDATE category_experience SET total_xp = 8400, level = ?1 WHERE category_id = ?2",
+            rusqlite::params![calculate_level(8400), frontend],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE category_experience SET total_xp = 13200, level = ?1 WHERE category_id = ?2",
+            rusqlite::params![calculate_level(13200), backend],
+        )
+        .unwrap();
+        conn.execute("UPDATE user_profile SET level = 2, current_title = 'novice', current_streak = 4", [])
+            .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn test_report_snapshot() {
+        let conn = seeded_connection();
+        let report = build_report(&conn, "2025-10-01", "2025-10-31", generated_at()).unwrap();
+
+        assert_eq!(render_markdown(&report), include_str!("snapshots/report_october.md"));
+    }
+
+    #[test]
+    fn test_empty_report_snapshot() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let report = build_report(&conn, "2025-10-01", "2025-10-31", generated_at()).unwrap();
+
+        assert_eq!(render_markdown(&report), include_str!("snapshots/report_empty.md"));
+    }
+
+    #[test]
+    fn test_format_number_groups_thousands() {
+        assert_eq!(format_number(0), "0");
+        assert_eq!(format_number(2340), "2,340");
+        assert_eq!(format_number(-1234567), "-1,234,567");
+    }
+}
@@ -0,0 +1,312 @@
+use crate::backup::BackupSchedule;
+use crate::gamification::ScoringRules;
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Offset, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Timezone value meaning "whatever the operating system uses"
+pub const SYSTEM_TIMEZONE: &str = "system";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PomodoroSettings {
+    pub focus_minutes: i64,
+    pub short_break_minutes: i64,
+    pub long_break_minutes: i64,
+    pub sessions_before_long_break: i64,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        PomodoroSettings {
+            focus_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            sessions_before_long_break: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TraySettings {
+    // Closing the main window hides it to the tray instead of quitting
+    pub close_to_tray: bool,
+    pub start_minimized: bool,
+}
+
+/// User preferences kept in the `settings` table, one row per top-level field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Settings {
+    // `system` or a fixed UTC offset such as `+02:00`
+    pub timezone: String,
+    // Minutes without input before a running timer counts as idle; 0 turns detection off
+    pub idle_threshold_minutes: i64,
+    pub pomodoro: PomodoroSettings,
+    pub scoring: ScoringRules,
+    pub tray: TraySettings,
+    pub backup: BackupSchedule,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            timezone: SYSTEM_TIMEZONE.to_string(),
+            idle_threshold_minutes: 5,
+            pomodoro: PomodoroSettings::default(),
+            scoring: ScoringRules::default(),
+            tray: TraySettings::default(),
+            backup: BackupSchedule::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// UTC offset of the configured timezone at `at`
+    pub fn utc_offset(&self, at: DateTime<Utc>) -> FixedOffset {
+        match parse_timezone(&self.timezone) {
+            Ok(Some(offset)) => offset,
+            _ => at.with_timezone(&Local).offset().fix(),
+        }
+    }
+
+    /// Wall-clock time in the configured timezone
+    pub fn local_time(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        at.with_timezone(&self.utc_offset(at)).naive_local()
+    }
+}
+
+/// `system` gives None; otherwise `UTC`/`Z` or `+HH:MM`/`-HH:MM`
+pub fn parse_timezone(value: &str) -> Result<Option<FixedOffset>, String> {
+    let invalid = || format!("Invalid timezone: {} (use \"system\", \"UTC\" or an offset like +02:00)", value);
+    match value {
+        SYSTEM_TIMEZONE => return Ok(None),
+        "UTC" | "Z" => return Ok(FixedOffset::east_opt(0)),
+        _ => {}
+    }
+
+    let (sign, rest) = if let Some(rest) = value.strip_prefix('+') {
+        (1, rest)
+    } else if let Some(rest) = value.strip_prefix('-') {
+        (-1, rest)
+    } else {
+        return Err(invalid());
+    };
+    let (hours, minutes) = rest.split_once(':').ok_or_else(invalid)?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return Err(invalid());
+    }
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes > 59 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .map(Some)
+        .ok_or_else(invalid)
+}
+
+fn check_range(name: &str, value: i64, min: i64, max: i64) -> Result<(), String> {
+    if value < min || value > max {
+        return Err(format!("{} must be between {} and {}", name, min, max));
+    }
+    Ok(())
+}
+
+pub fn validate(settings: &Settings) -> Result<(), String> {
+    parse_timezone(&settings.timezone)?;
+    check_range("idleThresholdMinutes", settings.idle_threshold_minutes, 0, 480)?;
+
+    let pomodoro = &settings.pomodoro;
+    check_range("pomodoro.focusMinutes", pomodoro.focus_minutes, 1, 180)?;
+    check_range("pomodoro.shortBreakMinutes", pomodoro.short_break_minutes, 1, 60)?;
+    check_range("pomodoro.longBreakMinutes", pomodoro.long_break_minutes, 1, 120)?;
+    check_range("pomodoro.sessionsBeforeLongBreak", pomodoro.sessions_before_long_break, 1, 12)?;
+
+    let scoring = &settings.scoring;
+    check_range("scoring.basePoints", scoring.base_points, 0, 1000)?;
+    check_range("scoring.efficiencyBonusPoints", scoring.efficiency_bonus_points, 0, 1000)?;
+    check_range("scoring.efficiencyThresholdSeconds", scoring.efficiency_threshold_seconds, 60, 86_400)?;
+    check_range("scoring.complexityBonusPoints", scoring.complexity_bonus_points, 0, 1000)?;
+    check_range("scoring.complexityMinSubtasks", scoring.complexity_min_subtasks, 1, 100)?;
+
+    let backup = &settings.backup;
+    check_range("backup.intervalHours", backup.interval_hours, 1, 24 * 30)?;
+    check_range("backup.retention", backup.retention as i64, 1, 365)?;
+    Ok(())
+}
+
+/// Overlay `patch` onto `base`, recursing into objects so a patch can change one nested field
+fn merge(base: &mut Value, patch: Value) {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+fn defaults_value() -> Value {
+    serde_json::to_value(Settings::default()).expect("default settings serialize")
+}
+
+/// Stored settings on top of the defaults. Rows for fields this version doesn't know,
+/// or that no longer parse, are skipped so a bad value never blocks the app.
+pub fn load_settings(conn: &Connection) -> Result<Settings, String> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings ORDER BY key")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<(String, String)>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut value = defaults_value();
+    for (key, raw) in rows {
+        if value.get(&key).is_none() {
+            continue;
+        }
+        let Ok(stored) = serde_json::from_str::<Value>(&raw) else {
+            eprintln!("Ignoring unreadable setting {}", key);
+            continue;
+        };
+
+        let mut candidate = value.clone();
+        merge(&mut candidate, Value::Object(Map::from_iter([(key.clone(), stored)])));
+        match serde_json::from_value::<Settings>(candidate.clone()) {
+            Ok(_) => value = candidate,
+            Err(e) => eprintln!("Ignoring invalid setting {}: {}", key, e),
+        }
+    }
+
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Apply a partial update (any subset of fields, nested groups included), validate
+/// the result and persist the changed top-level fields
+pub fn update_settings(conn: &Connection, patch: Value, now: &str) -> Result<Settings, String> {
+    let Value::Object(patch) = patch else {
+        return Err("Settings update must be an object".to_string());
+    };
+
+    let defaults = defaults_value();
+    if let Some(key) = patch.keys().find(|key| defaults.get(key.as_str()).is_none()) {
+        return Err(format!("Unknown setting: {}", key));
+    }
+
+    let mut value = serde_json::to_value(load_settings(conn)?).map_err(|e| e.to_string())?;
+    let keys: Vec<String> = patch.keys().cloned().collect();
+    merge(&mut value, Value::Object(patch));
+    let settings: Settings = serde_json::from_value(value.clone()).map_err(|e| format!("Invalid settings: {}", e))?;
+    validate(&settings)?;
+
+    for key in keys {
+        let stored = serde_json::to_string(&value[key.as_str()]).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            params![&key, &stored, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(settings)
+}
+
+/// Wall-clock time of `at` in the user's timezone, falling back to the system zone
+pub fn local_time_at(conn: &Connection, at: DateTime<Utc>) -> NaiveDateTime {
+    load_settings(conn)
+        .map(|settings| settings.local_time(at))
+        .unwrap_or_else(|_| at.with_timezone(&Local).naive_local())
+}
+
+/// Current wall-clock time in the user's timezone
+pub fn local_now(conn: &Connection) -> NaiveDateTime {
+    local_time_at(conn, Utc::now())
+}
+
+pub fn scoring_rules(conn: &Connection) -> Result<ScoringRules, String> {
+    Ok(load_settings(conn)?.scoring)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use serde_json::json;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_partial_updates_persist_on_top_of_defaults() {
+        let conn = setup();
+        assert_eq!(load_settings(&conn).unwrap(), Settings::default());
+
+        let updated = update_settings(
+            &conn,
+            json!({ "pomodoro": { "focusMinutes": 50 }, "tray": { "closeToTray": true }, "timezone": "+02:00" }),
+            "2025-09-01T08:00:00+00:00",
+        )
+        .unwrap();
+        assert_eq!(updated.pomodoro.focus_minutes, 50);
+        assert_eq!(updated.pomodoro.short_break_minutes, 5);
+        assert!(updated.tray.close_to_tray);
+        assert_eq!(load_settings(&conn).unwrap(), updated);
+        let stored: Vec<String> = conn
+            .prepare("SELECT key FROM settings ORDER BY key")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(stored, vec!["pomodoro", "timezone", "tray"]);
+
+        let at = DateTime::parse_from_rfc3339("2025-09-01T23:30:00+00:00").unwrap().with_timezone(&Utc);
+        assert_eq!(updated.local_time(at).to_string(), "2025-09-02 01:30:00");
+
+        // A stored value that no longer parses falls back to its default
+        conn.execute("UPDATE settings SET value = '\"oops\"' WHERE key = 'pomodoro'", []).unwrap();
+        let loaded = load_settings(&conn).unwrap();
+        assert_eq!(loaded.pomodoro, PomodoroSettings::default());
+        assert!(loaded.tray.close_to_tray);
+    }
+
+    #[test]
+    fn test_rejects_invalid_and_unknown_settings() {
+        let conn = setup();
+        let now = "2025-09-01T08:00:00+00:00";
+
+        let error = update_settings(&conn, json!({ "pomodoro": { "focusMinutes": 0 } }), now).unwrap_err();
+        assert!(error.contains("pomodoro.focusMinutes"), "{}", error);
+        let error = update_settings(&conn, json!({ "idleThresholdMinutes": 481 }), now).unwrap_err();
+        assert!(error.contains("idleThresholdMinutes"), "{}", error);
+        assert!(update_settings(&conn, json!({ "theme": "dark" }), now).unwrap_err().contains("Unknown setting"));
+        assert!(update_settings(&conn, json!({ "tray": { "blink": true } }), now).is_err());
+        assert!(update_settings(&conn, json!({ "scoring": { "basePoints": "ten" } }), now).is_err());
+        assert!(update_settings(&conn, json!({ "timezone": "Europe/Madrid" }), now).is_err());
+        assert!(update_settings(&conn, json!(["timezone"]), now).is_err());
+
+        // Nothing was written by the rejected updates
+        assert_eq!(load_settings(&conn).unwrap(), Settings::default());
+
+        assert_eq!(parse_timezone("system").unwrap(), None);
+        assert_eq!(parse_timezone("UTC").unwrap(), FixedOffset::east_opt(0));
+        assert_eq!(parse_timezone("-05:30").unwrap(), FixedOffset::west_opt(5 * 3600 + 1800));
+        assert!(parse_timezone("+15:00").is_err());
+        assert!(parse_timezone("+2:00").is_err());
+    }
+}
@@ -0,0 +1,787 @@
+use crate::analytics::period_start;
+use crate::gamification::effective_streak;
+use crate::leaderboard;
+use crate::models::*;
+use crate::profiles;
+use crate::settings;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+// Each member appends to `devfocus-<profile id>.jsonl` in the shared folder
+const LOG_PREFIX: &str = "devfocus-";
+const LOG_SUFFIX: &str = ".jsonl";
+
+/// Kinds of records a member shares with the team
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamEntityKind {
+    Member,
+    Task,
+    Subtask,
+    Session,
+    Xp,
+}
+
+impl TeamEntityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TeamEntityKind::Member => "member",
+            TeamEntityKind::Task => "task",
+            TeamEntityKind::Subtask => "subtask",
+            TeamEntityKind::Session => "session",
+            TeamEntityKind::Xp => "xp",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "member" => Some(TeamEntityKind::Member),
+            "task" => Some(TeamEntityKind::Task),
+            "subtask" => Some(TeamEntityKind::Subtask),
+            "session" => Some(TeamEntityKind::Session),
+            "xp" => Some(TeamEntityKind::Xp),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a change log. Records are last-writer-wins registers keyed by the member and
+/// the record's id; the higher `(clock, id)` wins, so logs merge in any order with the same result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TeamChange {
+    id: String,
+    member_id: String,
+    clock: i64,
+    entity: TeamEntityKind,
+    entity_id: String,
+    // None deletes the record
+    data: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberRecord {
+    pub name: String,
+    pub level: i64,
+    pub total_xp: i64,
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    pub last_work_date: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRecord {
+    pub title: String,
+    pub status: String,
+    pub completed_at: Option<String>,
+    // Complexity bonus stored on completion; absent from members on older versions
+    #[serde(default)]
+    pub bonus_points: Option<i64>,
+}
+
+// Categories are shared by name; ids differ between databases
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtaskRecord {
+    pub task_id: String,
+    pub title: String,
+    pub status: String,
+    pub category: Option<String>,
+    pub estimate_seconds: Option<i64>,
+    pub completed_at: Option<String>,
+    #[serde(default)]
+    pub points: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecord {
+    pub subtask_id: String,
+    pub category: Option<String>,
+    pub started_at: String,
+    pub ended_at: String,
+    pub duration_seconds: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XpRecord {
+    pub category: String,
+    pub xp: i64,
+    pub earned_at: String,
+}
+
+struct TeamSyncConfig {
+    folder: String,
+    profile_id: String,
+    clock: i64,
+    last_synced_at: Option<String>,
+}
+
+fn load_config(conn: &Connection) -> Result<Option<TeamSyncConfig>, String> {
+    conn.query_row(
+        "SELECT folder, profile_id, clock, last_synced_at FROM team_sync_config WHERE id = 1",
+        [],
+        |row| {
+            Ok(TeamSyncConfig {
+                folder: row.get(0)?,
+                profile_id: row.get(1)?,
+                clock: row.get(2)?,
+                last_synced_at: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn log_path(folder: &str, member_id: &str) -> PathBuf {
+    Path::new(folder).join(format!("{}{}{}", LOG_PREFIX, member_id, LOG_SUFFIX))
+}
+
+fn clear_team_data(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "DELETE FROM team_sync_exported;
+         DELETE FROM team_log_cursors;
+         DELETE FROM team_entities;",
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn get_status(conn: &Connection) -> Result<TeamSyncStatus, String> {
+    let members: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM team_entities WHERE entity_type = 'member' AND data IS NOT NULL",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(match load_config(conn)? {
+        Some(config) => TeamSyncStatus {
+            folder: Some(config.folder),
+            member_id: Some(config.profile_id),
+            last_synced_at: config.last_synced_at,
+            members,
+        },
+        None => TeamSyncStatus {
+            folder: None,
+            member_id: None,
+            last_synced_at: None,
+            members,
+        },
+    })
+}
+
+/// Share the active profile through a folder every member can read and write (a synced
+/// drive, a network share…), or stop sharing with `None`. Changing the folder or the
+/// shared profile drops the merged view of the previous team and runs a first sync.
+pub fn configure(conn: &Connection, folder: Option<&str>, now: DateTime<Utc>) -> Result<TeamSyncStatus, String> {
+    let Some(folder) = folder else {
+        conn.execute("DELETE FROM team_sync_config", []).map_err(|e| e.to_string())?;
+        clear_team_data(conn)?;
+        return get_status(conn);
+    };
+
+    let path = Path::new(folder.trim());
+    if !path.is_dir() {
+        return Err(format!("Not a folder: {}", path.display()));
+    }
+    let folder = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?
+        .to_string_lossy()
+        .to_string();
+    let profile_id = profiles::active_profile_id(conn)?;
+
+    let unchanged = load_config(conn)?.is_some_and(|config| config.folder == folder && config.profile_id == profile_id);
+    if !unchanged {
+        clear_team_data(conn)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO team_sync_config (id, folder, profile_id, clock, last_synced_at)
+             VALUES (1, ?1, ?2, 0, NULL)",
+            params![&folder, &profile_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    sync(conn, now)?;
+    get_status(conn)
+}
+
+fn query_records<T: Serialize>(
+    conn: &Connection,
+    sql: &str,
+    profile_id: &str,
+    kind: TeamEntityKind,
+    map: impl Fn(&rusqlite::Row) -> SqlResult<(String, T)>,
+    out: &mut HashMap<(TeamEntityKind, String), String>,
+) -> Result<(), String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([profile_id], map)
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    for (id, record) in rows {
+        let data = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+        out.insert((kind, id), data);
+    }
+    Ok(())
+}
+
+/// Everything the shared profile publishes, as serialized records keyed by kind and id
+fn local_snapshot(conn: &Connection, profile_id: &str) -> Result<HashMap<(TeamEntityKind, String), String>, String> {
+    let mut records = HashMap::new();
+
+    query_records(
+        conn,
+        "SELECT id, name, level, total_xp, current_streak, longest_streak, last_work_date
+         FROM user_profile WHERE id = ?1",
+        profile_id,
+        TeamEntityKind::Member,
+        |row| {
+            Ok((
+                row.get(0)?,
+                MemberRecord {
+                    name: row.get(1)?,
+                    level: row.get(2)?,
+                    total_xp: row.get(3)?,
+                    current_streak: row.get(4)?,
+                    longest_streak: row.get(5)?,
+                    last_work_date: row.get(6)?,
+                },
+            ))
+        },
+        &mut records,
+    )?;
+
+    query_records(
+        conn,
+        "SELECT id, title, status, completed_at, bonus_points FROM tasks WHERE profile_id = ?1",
+        profile_id,
+        TeamEntityKind::Task,
+        |row| {
+            Ok((
+                row.get(0)?,
+                TaskRecord {
+                    title: row.get(1)?,
+                    status: row.get(2)?,
+                    completed_at: row.get(3)?,
+                    bonus_points: row.get(4)?,
+                },
+            ))
+        },
+        &mut records,
+    )?;
+
+    query_records(
+        conn,
+        "SELECT s.id, s.task_id, s.title, s.status, c.name, s.estimate_seconds, s.completed_at, s.points
+         FROM subtasks s
+         JOIN tasks t ON s.task_id = t.id
+         LEFT JOIN categories c ON s.category_id = c.id
+         WHERE t.profile_id = ?1",
+        profile_id,
+        TeamEntityKind::Subtask,
+        |row| {
+            Ok((
+                row.get(0)?,
+                SubtaskRecord {
+                    task_id: row.get(1)?,
+                    title: row.get(2)?,
+                    status: row.get(3)?,
+                    category: row.get(4)?,
+                    estimate_seconds: row.get(5)?,
+                    completed_at: row.get(6)?,
+                    points: row.get(7)?,
+                },
+            ))
+        },
+        &mut records,
+    )?;
+
+    // Only finished sessions; running ones are published once they end
+    query_records(
+        conn,
+        "SELECT ts.id, ts.subtask_id, c.name, ts.started_at, ts.ended_at, ts.duration_seconds
+         FROM time_sessions ts
+         JOIN subtasks s ON ts.subtask_id = s.id
+         JOIN tasks t ON s.task_id = t.id
+         LEFT JOIN categories c ON s.category_id = c.id
+         WHERE t.profile_id = ?1 AND ts.ended_at IS NOT NULL",
+        profile_id,
+        TeamEntityKind::Session,
+        |row| {
+            Ok((
+                row.get(0)?,
+                SessionRecord {
+                    subtask_id: row.get(1)?,
+                    category: row.get(2)?,
+                    started_at: row.get(3)?,
+                    ended_at: row.get(4)?,
+                    duration_seconds: row.get(5)?,
+                },
+            ))
+        },
+        &mut records,
+    )?;
+
+    query_records(
+        conn,
+        "SELECT x.id, c.name, x.xp, x.earned_at
+         FROM xp_ledger x
+         JOIN categories c ON x.category_id = c.id
+         WHERE x.profile_id = ?1 AND x.earned_at IS NOT NULL",
+        profile_id,
+        TeamEntityKind::Xp,
+        |row| {
+            Ok((
+                row.get(0)?,
+                XpRecord {
+                    category: row.get(1)?,
+                    xp: row.get(2)?,
+                    earned_at: row.get(3)?,
+                },
+            ))
+        },
+        &mut records,
+    )?;
+
+    Ok(records)
+}
+
+/// Append a change for every record that differs from what was last published, including
+/// deletions. Returns the number of changes written.
+fn export_changes(conn: &Connection, config: &TeamSyncConfig) -> Result<i64, String> {
+    let snapshot = local_snapshot(conn, &config.profile_id)?;
+
+    let mut stmt = conn
+        .prepare("SELECT entity_type, entity_id, data FROM team_sync_exported")
+        .map_err(|e| e.to_string())?;
+    let exported: HashMap<(TeamEntityKind, String), String> = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|(kind, id, data)| Some(((TeamEntityKind::from_str(&kind)?, id), data)))
+        .collect();
+
+    let mut changed: Vec<(&(TeamEntityKind, String), Option<&String>)> = snapshot
+        .iter()
+        .filter(|(key, data)| exported.get(*key) != Some(*data))
+        .map(|(key, data)| (key, Some(data)))
+        .chain(exported.keys().filter(|key| !snapshot.contains_key(*key)).map(|key| (key, None)))
+        .collect();
+    if changed.is_empty() {
+        return Ok(0);
+    }
+    // Parents before children keeps the log readable; the merge doesn't depend on it
+    changed.sort_by(|(a, _), (b, _)| (a.0 as u8, &a.1).cmp(&(b.0 as u8, &b.1)));
+
+    // Continue after the highest clock already in our own log, in case this database was reset
+    let own_clock: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(clock) FROM team_entities WHERE member_id = ?1",
+            [&config.profile_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut clock = config.clock.max(own_clock.unwrap_or(0));
+
+    let mut lines = String::new();
+    for ((kind, entity_id), data) in &changed {
+        clock += 1;
+        let change = TeamChange {
+            id: Uuid::new_v4().to_string(),
+            member_id: config.profile_id.clone(),
+            clock,
+            entity: *kind,
+            entity_id: entity_id.clone(),
+            data: data.map(|data| serde_json::from_str(data)).transpose().map_err(|e| e.to_string())?,
+        };
+        lines.push_str(&serde_json::to_string(&change).map_err(|e| e.to_string())?);
+        lines.push('\n');
+    }
+
+    let path = log_path(&config.folder, &config.profile_id);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    file.write_all(lines.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for ((kind, entity_id), data) in &changed {
+        match data {
+            Some(data) => tx.execute(
+                "INSERT OR REPLACE INTO team_sync_exported (entity_type, entity_id, data) VALUES (?1, ?2, ?3)",
+                params![kind.as_str(), entity_id, data],
+            ),
+            None => tx.execute(
+                "DELETE FROM team_sync_exported WHERE entity_type = ?1 AND entity_id = ?2",
+                params![kind.as_str(), entity_id],
+            ),
+        }
+        .map_err(|e| e.to_string())?;
+    }
+    tx.execute("UPDATE team_sync_config SET clock = ?1 WHERE id = 1", [clock])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(changed.len() as i64)
+}
+
+/// Read the complete lines appended to a log since the last merge
+fn read_new_lines(conn: &Connection, path: &Path, file_name: &str) -> Result<(String, u64), String> {
+    let offset: u64 = conn
+        .query_row("SELECT offset FROM team_log_cursors WHERE file_name = ?1", [file_name], |row| {
+            row.get::<_, i64>(0)
+        })
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(0) as u64;
+
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    // A shorter file was replaced rather than appended to; merging is idempotent, so start over
+    let offset = if len < offset { 0 } else { offset };
+
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_to_end(&mut bytes))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    // A trailing line without a newline may still be being written or synced
+    let complete = bytes.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    bytes.truncate(complete);
+    let text = String::from_utf8_lossy(&bytes).to_string();
+    Ok((text, offset + complete as u64))
+}
+
+/// Apply a change unless the stored record already has a later version; returns whether it applied
+fn apply_change(conn: &Connection, change: &TeamChange) -> Result<bool, String> {
+    let data = change
+        .data
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let applied = conn
+        .execute(
+            "INSERT INTO team_entities (member_id, entity_type, entity_id, clock, change_id, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(member_id, entity_type, entity_id) DO UPDATE SET
+                 clock = excluded.clock, change_id = excluded.change_id, data = excluded.data
+             WHERE (excluded.clock, excluded.change_id) > (team_entities.clock, team_entities.change_id)",
+            params![
+                &change.member_id,
+                change.entity.as_str(),
+                &change.entity_id,
+                change.clock,
+                &change.id,
+                data
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(applied > 0)
+}
+
+/// Merge every member's log into `team_entities`. Returns the number of changes applied from
+/// other members, plus one message per log that couldn't be read completely.
+fn merge_logs(conn: &Connection, config: &TeamSyncConfig) -> Result<(i64, Vec<String>), String> {
+    let mut merged = 0;
+    let mut errors = Vec::new();
+
+    let entries = fs::read_dir(&config.folder).map_err(|e| format!("Failed to read {}: {}", config.folder, e))?;
+    let mut logs: Vec<(String, String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let member_id = file_name.strip_prefix(LOG_PREFIX)?.strip_suffix(LOG_SUFFIX)?.to_string();
+            Some((file_name, member_id, entry.path()))
+        })
+        .collect();
+    logs.sort();
+
+    for (file_name, member_id, path) in logs {
+        let (text, offset) = match read_new_lines(conn, &path, &file_name) {
+            Ok(result) => result,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let mut skipped = 0;
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<TeamChange>(line) {
+                // A log may only change its own member's records
+                Ok(change) if change.member_id == member_id => {
+                    if apply_change(&tx, &change)? && member_id != config.profile_id {
+                        merged += 1;
+                    }
+                }
+                _ => skipped += 1,
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO team_log_cursors (file_name, offset) VALUES (?1, ?2)",
+            params![&file_name, offset as i64],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+
+        if skipped > 0 {
+            errors.push(format!("{}: skipped {} unreadable change(s)", file_name, skipped));
+        }
+    }
+
+    Ok((merged, errors))
+}
+
+/// Publish local changes and merge everyone's logs
+pub fn sync(conn: &Connection, now: DateTime<Utc>) -> Result<TeamSyncSummary, String> {
+    let config = load_config(conn)?.ok_or_else(|| "Team sync is not configured".to_string())?;
+    if !Path::new(&config.folder).is_dir() {
+        return Err(format!("Team folder not found: {}", config.folder));
+    }
+
+    // Merge first so our clock continues after anything already in our own log
+    let (merged_before, mut errors) = merge_logs(conn, &config)?;
+    let changes_written = export_changes(conn, &config)?;
+    let (merged_after, more_errors) = merge_logs(conn, &config)?;
+    errors.extend(more_errors);
+    errors.dedup();
+
+    conn.execute("UPDATE team_sync_config SET last_synced_at = ?1 WHERE id = 1", [now.to_rfc3339()])
+        .map_err(|e| e.to_string())?;
+
+    Ok(TeamSyncSummary {
+        changes_written,
+        changes_merged: merged_before + merged_after,
+        members: get_status(conn)?.members,
+        errors,
+    })
+}
+
+/// Live records of one kind from the merged view, as (member id, record id, record)
+pub fn load_records<T: DeserializeOwned>(conn: &Connection, kind: TeamEntityKind) -> Result<Vec<(String, String, T)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT member_id, entity_id, data FROM team_entities
+             WHERE entity_type = ?1 AND data IS NOT NULL
+             ORDER BY member_id, entity_id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([kind.as_str()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    // Records from newer app versions may not parse; they're left out rather than failing the view
+    Ok(rows
+        .into_iter()
+        .filter_map(|(member_id, id, data)| Some((member_id, id, serde_json::from_str(&data).ok()?)))
+        .collect())
+}
+
+/// Every member in the merged view, ourselves included
+pub fn list_members(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<TeamMember>, String> {
+    let self_id = load_config(conn)?.map(|config| config.profile_id);
+    let today = settings::local_time_at(conn, now).date();
+    let members = load_records::<MemberRecord>(conn, TeamEntityKind::Member)?;
+    let tasks = load_records::<TaskRecord>(conn, TeamEntityKind::Task)?;
+    let subtasks = load_records::<SubtaskRecord>(conn, TeamEntityKind::Subtask)?;
+    let sessions = load_records::<SessionRecord>(conn, TeamEntityKind::Session)?;
+
+    let mut result: Vec<TeamMember> = members
+        .into_iter()
+        .map(|(member_id, _, member)| {
+            let is_member = |owner: &String| *owner == member_id;
+            TeamMember {
+                tasks_completed: tasks.iter().filter(|(m, _, t)| is_member(m) && t.status == "done").count() as i64,
+                subtasks_completed: subtasks.iter().filter(|(m, _, s)| is_member(m) && s.status == "done").count() as i64,
+                tracked_seconds: sessions.iter().filter(|(m, _, _)| is_member(m)).map(|(_, _, s)| s.duration_seconds).sum(),
+                current_streak: effective_streak(member.current_streak, member.last_work_date.as_deref(), today),
+                is_self: self_id.as_ref() == Some(&member_id),
+                member_id,
+                name: member.name,
+                level: member.level,
+                total_xp: member.total_xp,
+                longest_streak: member.longest_streak,
+                last_work_date: member.last_work_date,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.member_id.cmp(&b.member_id)));
+    Ok(result)
+}
+
+/// Members ranked by this week's points, then total XP. Members with the same points and
+/// XP share a rank; they're listed by name.
+pub fn leaderboard(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<TeamLeaderboardEntry>, String> {
+    let week_start = period_start(settings::local_time_at(conn, now).date(), Granularity::Week);
+    let points: HashMap<String, i64> = leaderboard::load_participants(conn, LeaderboardSource::Team, now)?
+        .into_iter()
+        .map(|participant| (participant.id.clone(), participant.points_between(week_start, week_start + Duration::days(6), None)))
+        .collect();
+    let entries: Vec<TeamLeaderboardEntry> = list_members(conn, now)?
+        .into_iter()
+        .map(|member| TeamLeaderboardEntry {
+            rank: 0,
+            weekly_points: points.get(&member.member_id).copied().unwrap_or(0),
+            total_xp: member.total_xp,
+            current_streak: member.current_streak,
+            is_self: member.is_self,
+            member_id: member.member_id,
+            name: member.name,
+        })
+        .collect();
+
+    Ok(leaderboard::rank_by(
+        entries,
+        |entry| (entry.weekly_points, entry.total_xp),
+        |entry| (&entry.name, &entry.member_id),
+    )
+    .into_iter()
+    .map(|(rank, entry)| TeamLeaderboardEntry { rank, ..entry })
+    .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use crate::test_support::TempDir;
+    use crate::timer;
+
+    struct Client {
+        conn: Connection,
+    }
+
+    impl Client {
+        fn new(name: &str) -> Self {
+            let conn = Connection::open_in_memory().unwrap();
+            create_tables(&conn).unwrap();
+            conn.execute("UPDATE user_profile SET name = ?1", [name]).unwrap();
+            Client { conn }
+        }
+
+        fn complete(&self, title: &str, seconds: i64) -> String {
+            let task = timer::create_task(&self.conn, title.to_string(), None, None).unwrap();
+            let backend: String = self
+                .conn
+                .query_row("SELECT id FROM categories WHERE name = 'backend'", [], |row| row.get(0))
+                .unwrap();
+            let subtask = timer::create_subtask(&self.conn, task.id.clone(), title.to_string(), Some(backend), None).unwrap();
+            timer::start_subtask(&self.conn, &subtask.id).unwrap();
+            timer::complete_subtask(&self.conn, &subtask.id, seconds).unwrap();
+            task.id
+        }
+    }
+
+    #[test]
+    fn test_members_merge_each_others_logs() {
+        let folder = TempDir::new("team");
+        let ana = Client::new("Ana");
+        let ben = Client::new("Ben");
+        ana.complete("Login API", 3600);
+        ben.complete("Search", 1800);
+        ben.complete("Filters", 1200);
+
+        let now = Utc::now();
+        let folder_str = folder.path().to_str().unwrap();
+        configure(&ana.conn, Some(folder_str), now).unwrap();
+        configure(&ben.conn, Some(folder_str), now).unwrap();
+        let summary = sync(&ana.conn, now).unwrap();
+        assert_eq!(summary.changes_written, 0);
+        assert!(summary.changes_merged > 0);
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+
+        for client in [&ana, &ben] {
+            let members = list_members(&client.conn, now).unwrap();
+            assert_eq!(members.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["Ana", "Ben"]);
+            assert_eq!(members[1].subtasks_completed, 2);
+            assert_eq!(members[0].tracked_seconds, 3600);
+        }
+
+        let board = leaderboard(&ana.conn, now).unwrap();
+        assert_eq!(board.iter().map(|e| (e.rank, e.name.as_str())).collect::<Vec<_>>(), vec![(1, "Ben"), (2, "Ana")]);
+        // 10 + 15 for the subtask finished in under 25 minutes
+        assert_eq!(board[0].weekly_points, 25);
+        assert!(board[1].is_self);
+
+        // Deleting a task publishes tombstones for it and its subtasks and sessions
+        let filters: String = ben
+            .conn
+            .query_row("SELECT id FROM tasks WHERE title = 'Filters'", [], |row| row.get(0))
+            .unwrap();
+        ben.conn.execute("DELETE FROM tasks WHERE id = ?1", [&filters]).unwrap();
+        assert!(sync(&ben.conn, now).unwrap().changes_written >= 3);
+        sync(&ana.conn, now).unwrap();
+        let members = list_members(&ana.conn, now).unwrap();
+        assert_eq!(members[1].subtasks_completed, 1);
+
+        // Replaying every log from scratch gives the same view
+        let before = leaderboard(&ana.conn, now).unwrap();
+        ana.conn.execute_batch("DELETE FROM team_log_cursors; DELETE FROM team_entities;").unwrap();
+        sync(&ana.conn, now).unwrap();
+        let after = leaderboard(&ana.conn, now).unwrap();
+        assert_eq!(
+            before.iter().map(|e| (e.rank, e.weekly_points)).collect::<Vec<_>>(),
+            after.iter().map(|e| (e.rank, e.weekly_points)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_latest_version_and_ignores_partial_lines() {
+        let folder = TempDir::new("team");
+        let client = Client::new("Ana");
+        configure(&client.conn, Some(folder.path().to_str().unwrap()), Utc::now()).unwrap();
+        let config = load_config(&client.conn).unwrap().unwrap();
+
+        let change = |clock: i64, name: &str| {
+            serde_json::to_string(&TeamChange {
+                id: format!("change-{}", clock),
+                member_id: "peer".to_string(),
+                clock,
+                entity: TeamEntityKind::Member,
+                entity_id: "peer".to_string(),
+                data: Some(serde_json::json!({
+                    "name": name, "level": 1, "totalXp": 0, "currentStreak": 0, "longestStreak": 0, "lastWorkDate": null
+                })),
+            })
+            .unwrap()
+        };
+        let forged = change(99, "Forged").replace("\"memberId\":\"peer\"", "\"memberId\":\"other\"");
+        let path = log_path(&config.folder, "peer");
+        // Out of order, a forged member id and an unfinished line
+        fs::write(&path, format!("{}\n{}\n{}\n{}", change(2, "Newer"), change(1, "Older"), forged, "{\"id\"")).unwrap();
+
+        let summary = sync(&client.conn, Utc::now()).unwrap();
+        assert_eq!(summary.changes_merged, 1);
+        assert_eq!(summary.errors.len(), 1);
+        let names: Vec<String> = list_members(&client.conn, Utc::now()).unwrap().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["Ana", "Newer"]);
+    }
+}
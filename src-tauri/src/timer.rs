@@ -0,0 +1,1114 @@
+use crate::gamification::*;
+use crate::git;
+use crate::models::*;
+use crate::outbox;
+use crate::plugins;
+use crate::profiles;
+use crate::settings;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult, Transaction};
+use serde_json::json;
+use uuid::Uuid;
+
+/// Update a profile's streak when completing a subtask on `today`, the user's local date
+fn update_user_streak(conn: &Connection, profile_id: &str, today: NaiveDate) -> Result<i64, String> {
+    let work_date = today.format("%Y-%m-%d").to_string();
+
+    // Get current profile data
+    let (current_streak, longest_streak, last_work_date): (i64, i64, Option<String>) = conn
+        .query_row(
+            "SELECT current_streak, longest_streak, last_work_date FROM user_profile WHERE id = ?1",
+            [profile_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        )
+        .map_err(|e| format!("Failed to get streak data: {}", e))?;
+
+    let new_streak = if let Some(last_date) = last_work_date {
+        if last_date == work_date {
+            // Same day, maintain streak
+            current_streak
+        } else if let Ok(last_parsed) = NaiveDate::parse_from_str(&last_date, "%Y-%m-%d") {
+            let diff = today.signed_duration_since(last_parsed).num_days();
+
+            if diff == 1 {
+                // Consecutive day
+                current_streak + 1
+            } else {
+                // Streak broken
+                1
+            }
+        } else {
+            1
+        }
+    } else {
+        // First time
+        1
+    };
+
+    let new_longest = new_streak.max(longest_streak);
+
+    // Update in database
+    conn.execute(
+        "UPDATE user_profile SET current_streak = ?1, longest_streak = ?2, last_work_date = ?3, updated_at = ?4
+         WHERE id = ?5",
+        params![new_streak, new_longest, &work_date, &chrono::Utc::now().to_rfc3339(), profile_id],
+    )
+    .map_err(|e| format!("Failed to update streak: {}", e))?;
+
+    if new_streak != current_streak && STREAK_MILESTONES.contains(&new_streak) {
+        outbox::record_event(
+            conn,
+            OutboxEventType::StreakMilestone,
+            json!({ "profileId": profile_id, "streak": new_streak, "longestStreak": new_longest }),
+            &chrono::Utc::now().to_rfc3339(),
+        )?;
+    }
+
+    Ok(new_streak)
+}
+
+/// Open a running interval for a time session
+fn open_session_interval(conn: &Connection, session_id: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO session_intervals (id, session_id, started_at, ended_at) VALUES (?1, ?2, ?3, NULL)",
+        params![Uuid::new_v4().to_string(), session_id, now],
+    )
+    .map_err(|e| format!("Failed to open session interval: {}", e))?;
+
+    Ok(())
+}
+
+/// Close the running interval of the subtask's active session, if any
+fn close_session_intervals(conn: &Connection, subtask_id: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE session_intervals SET ended_at = ?1
+         WHERE ended_at IS NULL
+           AND session_id IN (SELECT id FROM time_sessions WHERE subtask_id = ?2 AND ended_at IS NULL)",
+        params![now, subtask_id],
+    )
+    .map_err(|e| format!("Failed to close session interval: {}", e))?;
+
+    Ok(())
+}
+
+/// Transaction for a timer operation; joins the caller's instead when one is already open
+fn begin(conn: &Connection) -> Result<Option<Transaction<'_>>, String> {
+    if !conn.is_autocommit() {
+        return Ok(None);
+    }
+    conn.unchecked_transaction().map(Some).map_err(|e| e.to_string())
+}
+
+fn commit(tx: Option<Transaction<'_>>) -> Result<(), String> {
+    match tx {
+        Some(tx) => tx.commit().map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+pub fn validate_estimate(estimate_seconds: Option<i64>) -> Result<(), String> {
+    if estimate_seconds.is_some_and(|estimate| estimate <= 0) {
+        return Err("Estimate must be a positive number of seconds".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_duration(duration_seconds: Option<i64>) -> Result<(), String> {
+    if duration_seconds.is_some_and(|duration| duration < 0) {
+        return Err("Duration can't be a negative number of seconds".to_string());
+    }
+    Ok(())
+}
+
+pub fn create_task(
+    conn: &Connection,
+    title: String,
+    description: Option<String>,
+    estimate_seconds: Option<i64>,
+) -> Result<Task, String> {
+    validate_estimate(estimate_seconds)?;
+    let now = Utc::now().to_rfc3339();
+    let task = Task {
+        id: Uuid::new_v4().to_string(),
+        title,
+        description,
+        status: TaskStatus::Todo,
+        created_at: now.clone(),
+        updated_at: now,
+        completed_at: None,
+        estimate_seconds,
+    };
+
+    conn.execute(
+        "INSERT INTO tasks (id, title, description, status, created_at, updated_at, completed_at, estimate_seconds, profile_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            &task.id,
+            &task.title,
+            &task.description,
+            task.status.as_str(),
+            &task.created_at,
+            &task.updated_at,
+            &task.completed_at,
+            &task.estimate_seconds,
+            profiles::active_profile_id(conn)?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(task)
+}
+
+pub fn create_subtask(
+    conn: &Connection,
+    task_id: String,
+    title: String,
+    category_id: Option<String>,
+    estimate_seconds: Option<i64>,
+) -> Result<Subtask, String> {
+    validate_estimate(estimate_seconds)?;
+    let now = Utc::now().to_rfc3339();
+
+    let subtask_id = Uuid::new_v4().to_string();
+
+    conn.execute(
+        "INSERT INTO subtasks (id, task_id, title, status, created_at, updated_at, completed_at, category_id, estimate_seconds)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            &subtask_id,
+            &task_id,
+            &title,
+            SubtaskStatus::Todo.as_str(),
+            &now,
+            &now,
+            None::<String>,
+            &category_id,
+            &estimate_seconds,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Fetch category if exists
+    let category = if let Some(ref cat_id) = category_id {
+        conn.query_row(
+            "SELECT id, name, color, created_at FROM categories WHERE id = ?1",
+            [cat_id],
+            |row| {
+                Ok(Category {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            },
+        )
+        .ok()
+    } else {
+        None
+    };
+
+    Ok(Subtask {
+        id: subtask_id,
+        task_id,
+        title,
+        status: SubtaskStatus::Todo,
+        created_at: now.clone(),
+        updated_at: now,
+        completed_at: None,
+        total_time_seconds: Some(0),
+        category_id,
+        category,
+        estimate_seconds,
+    })
+}
+
+fn task_from_row(row: &rusqlite::Row) -> SqlResult<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        status: TaskStatus::from_str(&row.get::<_, String>(3)?),
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        completed_at: row.get(6)?,
+        estimate_seconds: row.get(7)?,
+    })
+}
+
+fn category_from_row(row: &rusqlite::Row, first_column: usize) -> SqlResult<Option<Category>> {
+    if row.get::<_, Option<String>>(first_column)?.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Category {
+        id: row.get(first_column)?,
+        name: row.get(first_column + 1)?,
+        color: row.get(first_column + 2)?,
+        created_at: row.get(first_column + 3)?,
+    }))
+}
+
+/// One of the active profile's tasks; tasks of other profiles are not found
+pub fn get_task(conn: &Connection, task_id: &str) -> Result<Task, String> {
+    conn.query_row(
+        "SELECT id, title, description, status, created_at, updated_at, completed_at, estimate_seconds
+         FROM tasks WHERE id = ?1 AND profile_id = ?2",
+        params![task_id, profiles::active_profile_id(conn)?],
+        task_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Task not found: {}", task_id))
+}
+
+pub fn get_task_with_subtasks(conn: &Connection, task_id: &str) -> Result<TaskWithSubtasks, String> {
+    let task = get_task(conn, task_id)?;
+
+    // Get subtasks with total time (excluding active sessions)
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
+                    COALESCE(SUM(CASE WHEN ts.ended_at IS NOT NULL THEN ts.duration_seconds ELSE 0 END), 0) as total_time,
+                    s.category_id, c.id, c.name, c.color, c.created_at, s.estimate_seconds
+             FROM subtasks s
+             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+             LEFT JOIN categories c ON s.category_id = c.id
+             WHERE s.task_id = ?1
+             GROUP BY s.id
+             ORDER BY s.created_at"
+        )
+        .map_err(|e| e.to_string())?;
+
+    let subtasks = stmt
+        .query_map([task_id], |row| {
+            Ok(Subtask {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                title: row.get(2)?,
+                status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                completed_at: row.get(6)?,
+                total_time_seconds: Some(row.get(7)?),
+                category_id: row.get(8)?,
+                category: category_from_row(row, 9)?,
+                estimate_seconds: row.get(13)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<Subtask>>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(TaskWithSubtasks {
+        id: task.id,
+        title: task.title,
+        description: task.description,
+        status: task.status,
+        created_at: task.created_at,
+        updated_at: task.updated_at,
+        completed_at: task.completed_at,
+        estimate_seconds: task.estimate_seconds,
+        subtasks,
+    })
+}
+
+/// A task with its subtasks, their open sessions and the commits linked to them
+pub fn get_task_with_subtasks_and_sessions(conn: &Connection, task_id: &str) -> Result<TaskWithSubtasksAndSessions, String> {
+    let task = get_task(conn, task_id)?;
+
+    // Get subtasks with total time and active sessions
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
+                    COALESCE(SUM(CASE WHEN ts.ended_at IS NOT NULL THEN ts.duration_seconds ELSE 0 END), 0) as total_time,
+                    sess.id as session_id, sess.started_at, sess.paused_at, sess.resumed_at, sess.ended_at, sess.duration_seconds,
+                    s.category_id, c.id, c.name, c.color, c.created_at, s.estimate_seconds
+             FROM subtasks s
+             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+             LEFT JOIN time_sessions sess ON s.id = sess.subtask_id AND sess.ended_at IS NULL
+             LEFT JOIN categories c ON s.category_id = c.id
+             WHERE s.task_id = ?1
+             GROUP BY s.id, sess.id, sess.started_at, sess.paused_at, sess.resumed_at, sess.ended_at, sess.duration_seconds
+             ORDER BY s.created_at"
+        )
+        .map_err(|e| e.to_string())?;
+
+    let subtasks_with_sessions = stmt
+        .query_map([task_id], |row| {
+            let session = if row.get::<_, Option<String>>(8)?.is_some() {
+                Some(TimeSession {
+                    id: row.get(8)?,
+                    subtask_id: row.get(0)?,
+                    started_at: row.get(9)?,
+                    paused_at: row.get(10)?,
+                    resumed_at: row.get(11)?,
+                    ended_at: row.get(12)?,
+                    duration_seconds: row.get(13)?,
+                })
+            } else {
+                None
+            };
+
+            Ok(SubtaskWithSession {
+                commits: Vec::new(),
+                subtask: Subtask {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    title: row.get(2)?,
+                    status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                    completed_at: row.get(6)?,
+                    total_time_seconds: Some(row.get(7)?),
+                    category_id: row.get(14)?,
+                    category: category_from_row(row, 15)?,
+                    estimate_seconds: row.get(19)?,
+                },
+                session,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<SubtaskWithSession>>>()
+        .map_err(|e| e.to_string())?;
+
+    // Attach commits linked by the git scanner
+    let mut commit_links = git::commit_links_for_task(conn, task_id)?;
+    let subtasks_with_sessions = subtasks_with_sessions
+        .into_iter()
+        .map(|mut entry| {
+            entry.commits = commit_links.remove(&entry.subtask.id).unwrap_or_default();
+            entry
+        })
+        .collect();
+
+    Ok(TaskWithSubtasksAndSessions {
+        id: task.id,
+        title: task.title,
+        description: task.description,
+        status: task.status,
+        created_at: task.created_at,
+        updated_at: task.updated_at,
+        completed_at: task.completed_at,
+        estimate_seconds: task.estimate_seconds,
+        subtasks_with_sessions,
+    })
+}
+
+/// One of the active profile's subtasks with its open session
+pub fn get_subtask_with_session(conn: &Connection, subtask_id: &str) -> Result<(Subtask, Option<TimeSession>), String> {
+    // Get subtask with total time
+    let subtask = conn
+        .query_row(
+            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
+                    COALESCE(SUM(CASE WHEN ts.ended_at IS NOT NULL THEN ts.duration_seconds ELSE 0 END), 0) as total_time,
+                    s.category_id, c.id, c.name, c.color, c.created_at, s.estimate_seconds
+             FROM subtasks s
+             JOIN tasks t ON t.id = s.task_id
+             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+             LEFT JOIN categories c ON s.category_id = c.id
+             WHERE s.id = ?1 AND t.profile_id = ?2
+             GROUP BY s.id",
+            params![subtask_id, profiles::active_profile_id(conn)?],
+            |row| {
+                Ok(Subtask {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    title: row.get(2)?,
+                    status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                    completed_at: row.get(6)?,
+                    total_time_seconds: Some(row.get(7)?),
+                    category_id: row.get(8)?,
+                    category: category_from_row(row, 9)?,
+                    estimate_seconds: row.get(13)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Subtask not found: {}", subtask_id))?;
+
+    // Get active session if exists
+    let session = active_session(conn, subtask_id)?;
+
+    Ok((subtask, session))
+}
+
+pub fn update_task_status(conn: &Connection, task_id: &str, status: &str) -> Result<Task, String> {
+    let profile_id = profiles::active_profile_id(conn)?;
+    let now = Utc::now().to_rfc3339();
+    let completed_at = if status == "done" { Some(now.clone()) } else { None };
+    let previous_status: String = conn
+        .query_row(
+            "SELECT status FROM tasks WHERE id = ?1 AND profile_id = ?2",
+            params![task_id, &profile_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task not found: {}", task_id))?;
+
+    conn.execute(
+        "UPDATE tasks SET status = ?1, updated_at = ?2, completed_at = ?3 WHERE id = ?4 AND profile_id = ?5",
+        params![status, &now, &completed_at, task_id, &profile_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    if status == "done" && previous_status != "done" {
+        outbox::record_event(
+            conn,
+            OutboxEventType::TaskCompleted,
+            json!({ "taskId": task_id }),
+            &now,
+        )?;
+    }
+
+    get_task(conn, task_id)
+}
+
+pub fn update_task_estimate(conn: &Connection, task_id: &str, estimate_seconds: Option<i64>) -> Result<(), String> {
+    validate_estimate(estimate_seconds)?;
+
+    let changed = conn
+        .execute(
+            "UPDATE tasks SET estimate_seconds = ?1, updated_at = ?2 WHERE id = ?3 AND profile_id = ?4",
+            params![estimate_seconds, Utc::now().to_rfc3339(), task_id, profiles::active_profile_id(conn)?],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("Task not found: {}", task_id));
+    }
+    Ok(())
+}
+
+pub fn delete_task(conn: &Connection, task_id: &str) -> Result<(), String> {
+    let deleted = conn
+        .execute(
+            "DELETE FROM tasks WHERE id = ?1 AND profile_id = ?2",
+            params![task_id, profiles::active_profile_id(conn)?],
+        )
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Task not found: {}", task_id));
+    }
+    Ok(())
+}
+
+pub fn update_subtask_estimate(conn: &Connection, subtask_id: &str, estimate_seconds: Option<i64>) -> Result<(), String> {
+    validate_estimate(estimate_seconds)?;
+
+    let changed = conn
+        .execute(
+            "UPDATE subtasks SET estimate_seconds = ?1, updated_at = ?2
+             WHERE id = ?3 AND task_id IN (SELECT id FROM tasks WHERE profile_id = ?4)",
+            params![estimate_seconds, Utc::now().to_rfc3339(), subtask_id, profiles::active_profile_id(conn)?],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("Subtask not found: {}", subtask_id));
+    }
+    Ok(())
+}
+
+pub fn delete_subtask(conn: &Connection, subtask_id: &str) -> Result<(), String> {
+    let deleted = conn
+        .execute(
+            "DELETE FROM subtasks WHERE id = ?1 AND task_id IN (SELECT id FROM tasks WHERE profile_id = ?2)",
+            params![subtask_id, profiles::active_profile_id(conn)?],
+        )
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Subtask not found: {}", subtask_id));
+    }
+    Ok(())
+}
+
+/// The subtask's open (not yet completed) time session, if any
+pub fn active_session(conn: &Connection, subtask_id: &str) -> Result<Option<TimeSession>, String> {
+    conn.query_row(
+        "SELECT id, subtask_id, started_at, paused_at, resumed_at, ended_at, duration_seconds
+         FROM time_sessions WHERE subtask_id = ?1 AND ended_at IS NULL",
+        [subtask_id],
+        |row| {
+            Ok(TimeSession {
+                id: row.get(0)?,
+                subtask_id: row.get(1)?,
+                started_at: row.get(2)?,
+                paused_at: row.get(3)?,
+                resumed_at: row.get(4)?,
+                ended_at: row.get(5)?,
+                duration_seconds: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Seconds the session has been running, summing its intervals up to `now`
+pub fn tracked_seconds(conn: &Connection, session_id: &str, now: DateTime<Utc>) -> Result<i64, String> {
+    let mut stmt = conn
+        .prepare("SELECT started_at, ended_at FROM session_intervals WHERE session_id = ?1")
+        .map_err(|e| e.to_string())?;
+
+    let intervals = stmt
+        .query_map([session_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let parse = |value: &str| {
+        DateTime::parse_from_rfc3339(value)
+            .map(|parsed| parsed.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid interval time '{}': {}", value, e))
+    };
+
+    let mut total = 0;
+    for (started_at, ended_at) in intervals {
+        let start = parse(&started_at)?;
+        let end = match ended_at {
+            Some(ended_at) => parse(&ended_at)?,
+            None => now,
+        };
+        total += (end - start).num_seconds().max(0);
+    }
+    Ok(total)
+}
+
+pub fn start_subtask(conn: &Connection, subtask_id: &str) -> Result<TimeSession, String> {
+    let now = Utc::now().to_rfc3339();
+    let tx = begin(conn)?;
+
+    // Update subtask status
+    conn.execute(
+        "UPDATE subtasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
+        params!["in_progress", &now, subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Create time session
+    let session = TimeSession {
+        id: Uuid::new_v4().to_string(),
+        subtask_id: subtask_id.to_string(),
+        started_at: now,
+        paused_at: None,
+        resumed_at: None,
+        ended_at: None,
+        duration_seconds: 0,
+    };
+
+    conn.execute(
+        "INSERT INTO time_sessions (id, subtask_id, started_at, paused_at, resumed_at, ended_at, duration_seconds)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            &session.id,
+            &session.subtask_id,
+            &session.started_at,
+            &session.paused_at,
+            &session.resumed_at,
+            &session.ended_at,
+            &session.duration_seconds,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    open_session_interval(conn, &session.id, &session.started_at)?;
+
+    plugins::on_session_start(conn, subtask_id, false, settings::local_now(conn))?;
+
+    commit(tx)?;
+    Ok(session)
+}
+
+pub fn pause_subtask(conn: &Connection, subtask_id: &str, duration_seconds: i64) -> Result<TimeSession, String> {
+    validate_duration(Some(duration_seconds))?;
+    let now = Utc::now().to_rfc3339();
+    let tx = begin(conn)?;
+
+    // Update subtask status
+    conn.execute(
+        "UPDATE subtasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
+        params!["paused", &now, subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    close_session_intervals(conn, subtask_id, &now)?;
+
+    // Update time session
+    conn.execute(
+        "UPDATE time_sessions SET paused_at = ?1, duration_seconds = ?2
+         WHERE subtask_id = ?3 AND ended_at IS NULL",
+        params![&now, duration_seconds, subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let session = active_session(conn, subtask_id)?
+        .ok_or_else(|| format!("No active session for subtask {}", subtask_id))?;
+
+    plugins::on_session_stop(conn, subtask_id, "pause", duration_seconds, settings::local_now(conn))?;
+
+    commit(tx)?;
+    Ok(session)
+}
+
+pub fn resume_subtask(conn: &Connection, subtask_id: &str) -> Result<TimeSession, String> {
+    let now = Utc::now().to_rfc3339();
+    let tx = begin(conn)?;
+
+    // Update subtask status
+    conn.execute(
+        "UPDATE subtasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
+        params!["in_progress", &now, subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Update time session
+    conn.execute(
+        "UPDATE time_sessions SET resumed_at = ?1
+         WHERE subtask_id = ?2 AND ended_at IS NULL",
+        params![&now, subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let session = active_session(conn, subtask_id)?
+        .ok_or_else(|| format!("No active session for subtask {}", subtask_id))?;
+
+    // Guard against a double resume leaving two open intervals
+    close_session_intervals(conn, subtask_id, &now)?;
+    open_session_interval(conn, &session.id, &now)?;
+
+    plugins::on_session_start(conn, subtask_id, true, settings::local_now(conn))?;
+
+    commit(tx)?;
+    Ok(session)
+}
+
+/// Store the complexity bonus on the subtask's task once all of its subtasks are done
+fn record_complexity_bonus(conn: &Connection, subtask_id: &str, rules: &ScoringRules) -> Result<(), String> {
+    conn.execute(
+        "UPDATE tasks SET bonus_points = ?1
+         WHERE id = (SELECT task_id FROM subtasks WHERE id = ?2) AND bonus_points IS NULL
+           AND (SELECT COUNT(*) FROM subtasks WHERE task_id = tasks.id) >= ?3
+           AND NOT EXISTS (SELECT 1 FROM subtasks WHERE task_id = tasks.id AND status != 'done')",
+        params![rules.complexity_bonus_points, subtask_id, rules.complexity_min_subtasks],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn complete_subtask(
+    conn: &Connection,
+    subtask_id: &str,
+    duration_seconds: i64,
+) -> Result<SubtaskCompletion, String> {
+    validate_duration(Some(duration_seconds))?;
+    let now = Utc::now().to_rfc3339();
+    let tx = begin(conn)?;
+
+    // Get category_id and estimate before updating subtask
+    let (category_id, estimate_seconds): (Option<String>, Option<i64>) = conn
+        .query_row(
+            "SELECT category_id, estimate_seconds FROM subtasks WHERE id = ?1",
+            [subtask_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap_or((None, None));
+
+    let local_time = settings::local_now(conn);
+    let had_session = active_session(conn, subtask_id)?.is_some();
+    let profile_id = profiles::profile_for_subtask(conn, subtask_id)?;
+
+    // Update streak when completing subtask
+    let current_streak = update_user_streak(conn, &profile_id, local_time.date())?;
+
+    // Update subtask status
+    conn.execute(
+        "UPDATE subtasks SET status = ?1, updated_at = ?2, completed_at = ?3 WHERE id = ?4",
+        params!["done", &now, &now, subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    close_session_intervals(conn, subtask_id, &now)?;
+
+    // Update time session
+    conn.execute(
+        "UPDATE time_sessions SET ended_at = ?1, duration_seconds = ?2
+         WHERE subtask_id = ?3 AND ended_at IS NULL",
+        params![&now, duration_seconds, subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Score the subtask's total tracked time now and keep the result, so later changes
+    // to the scoring rules only affect new completions
+    let rules = settings::scoring_rules(conn)?;
+    let total_seconds: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(duration_seconds), 0) FROM time_sessions WHERE subtask_id = ?1",
+            [subtask_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let points = calculate_subtask_points(&rules, total_seconds, estimate_seconds);
+    conn.execute(
+        "UPDATE subtasks SET points = ?1, efficient = ?2 WHERE id = ?3",
+        params![points, is_efficient(&rules, total_seconds, estimate_seconds), subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+    record_complexity_bonus(conn, subtask_id, &rules)?;
+
+    // Calculate XP with streak bonus from the same tracked time the points use
+    let base_xp = total_seconds;
+    let streak_bonus = calculate_streak_bonus(current_streak);
+    let bonus_xp = (base_xp as f64 * streak_bonus) as i64;
+    let xp_gained = plugins::calculate_xp(
+        conn,
+        subtask_id,
+        total_seconds,
+        current_streak,
+        base_xp + bonus_xp,
+        local_time,
+    )?;
+
+    // Update category experience if category exists
+    let category = if let Some(cat_id) = category_id {
+        // Get current XP
+        let current_xp: i64 = conn
+            .query_row(
+                "SELECT total_xp FROM category_experience WHERE profile_id = ?1 AND category_id = ?2",
+                params![&profile_id, &cat_id],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+
+        let new_xp = current_xp + xp_gained;
+        let new_level = calculate_level(new_xp);
+
+        // Update experience
+        conn.execute(
+            "UPDATE category_experience SET total_xp = ?1, level = ?2, updated_at = ?3
+             WHERE profile_id = ?4 AND category_id = ?5",
+            params![new_xp, new_level, &now, &profile_id, &cat_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        record_xp_gain(conn, &cat_id, Some(subtask_id), xp_gained, &now)?;
+
+        let previous_level = calculate_level(current_xp);
+        if new_level > previous_level {
+            outbox::record_event(
+                conn,
+                OutboxEventType::LevelUp,
+                json!({
+                    "scope": "category",
+                    "profileId": &profile_id,
+                    "categoryId": &cat_id,
+                    "level": new_level,
+                    "previousLevel": previous_level,
+                }),
+                &now,
+            )?;
+        }
+
+        // Update global level after category XP change
+        let profile_level = || -> Result<i64, String> {
+            conn.query_row("SELECT level FROM user_profile WHERE id = ?1", [&profile_id], |row| row.get(0))
+                .map_err(|e| e.to_string())
+        };
+        let previous_profile_level = profile_level()?;
+        update_user_profile_level(conn, &profile_id)?;
+        let profile_level = profile_level()?;
+        if profile_level > previous_profile_level {
+            outbox::record_event(
+                conn,
+                OutboxEventType::LevelUp,
+                json!({
+                    "scope": "profile",
+                    "profileId": &profile_id,
+                    "level": profile_level,
+                    "previousLevel": previous_profile_level,
+                }),
+                &now,
+            )?;
+        }
+
+        // Fetch category info
+        conn.query_row(
+            "SELECT id, name, color, created_at FROM categories WHERE id = ?1",
+            [&cat_id],
+            |row| {
+                Ok(Category {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            },
+        )
+        .ok()
+    } else {
+        None
+    };
+
+    // Get subtask with total time and category
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.task_id, s.title, s.status, s.created_at, s.updated_at, s.completed_at,
+                    COALESCE(SUM(ts.duration_seconds), 0) as total_time, s.category_id, s.estimate_seconds
+             FROM subtasks s
+             LEFT JOIN time_sessions ts ON s.id = ts.subtask_id
+             WHERE s.id = ?1
+             GROUP BY s.id"
+        )
+        .map_err(|e| e.to_string())?;
+
+    let subtask = stmt
+        .query_row([subtask_id], |row| {
+            Ok(Subtask {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                title: row.get(2)?,
+                status: SubtaskStatus::from_str(&row.get::<_, String>(3)?),
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                completed_at: row.get(6)?,
+                total_time_seconds: Some(row.get(7)?),
+                category_id: row.get(8)?,
+                category: category.clone(),
+                estimate_seconds: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    if had_session {
+        plugins::on_session_stop(conn, subtask_id, "complete", duration_seconds, local_time)?;
+    }
+    plugins::on_subtask_completed(conn, subtask_id, total_seconds, xp_gained, points, local_time)?;
+
+    outbox::record_event(
+        conn,
+        OutboxEventType::SubtaskCompleted,
+        json!({
+            "subtaskId": &subtask.id,
+            "taskId": &subtask.task_id,
+            "title": &subtask.title,
+            "categoryId": &subtask.category_id,
+            "timeSpentSeconds": total_seconds,
+            "pointsEarned": points,
+            "xpGained": xp_gained,
+        }),
+        &now,
+    )?;
+
+    commit(tx)?;
+    Ok(SubtaskCompletion {
+        subtask,
+        points_earned: points,
+        time_spent_seconds: total_seconds,
+        xp_gained,
+        category,
+        current_streak,
+        streak_bonus_percentage: streak_bonus,
+        bonus_xp,
+    })
+}
+
+/// Every open session of the active profile with its subtask and task, running or paused
+pub fn list_active_sessions(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<ActiveSession>, String> {
+    let profile_id = profiles::active_profile_id(conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT ts.id, ts.subtask_id, s.title, s.task_id, t.title, ts.started_at,
+                    EXISTS (SELECT 1 FROM session_intervals si WHERE si.session_id = ts.id AND si.ended_at IS NULL)
+             FROM time_sessions ts
+             JOIN subtasks s ON ts.subtask_id = s.id
+             JOIN tasks t ON s.task_id = t.id
+             WHERE ts.ended_at IS NULL AND t.profile_id = ?1
+             ORDER BY ts.started_at",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([&profile_id], |row| {
+            Ok(ActiveSession {
+                session_id: row.get(0)?,
+                subtask_id: row.get(1)?,
+                subtask_title: row.get(2)?,
+                task_id: row.get(3)?,
+                task_title: row.get(4)?,
+                started_at: row.get(5)?,
+                running: row.get(6)?,
+                tracked_seconds: 0,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    rows.into_iter()
+        .map(|mut session| {
+            session.tracked_seconds = tracked_seconds(conn, &session.session_id, now)?;
+            Ok(session)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+
+    #[test]
+    fn test_timer_lifecycle_tracks_intervals() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        assert!(create_task(&conn, "Empty".to_string(), None, Some(0)).is_err());
+        let task = create_task(&conn, "API".to_string(), None, None).unwrap();
+        assert!(create_subtask(&conn, task.id.clone(), "Negative".to_string(), None, Some(-60)).is_err());
+        let subtask = create_subtask(&conn, task.id.clone(), "Routes".to_string(), None, None).unwrap();
+
+        // A failed pause rolls back the status change
+        assert!(pause_subtask(&conn, &subtask.id, 10).is_err());
+        let status: String = conn
+            .query_row("SELECT status FROM subtasks WHERE id = ?1", [&subtask.id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(status, "todo");
+
+        let session = start_subtask(&conn, &subtask.id).unwrap();
+        let active = list_active_sessions(&conn, Utc::now()).unwrap();
+        assert_eq!(active.len(), 1);
+        assert!(active[0].running);
+        assert_eq!(active[0].task_title, "API");
+
+        pause_subtask(&conn, &subtask.id, 10).unwrap();
+        assert!(!list_active_sessions(&conn, Utc::now()).unwrap()[0].running);
+
+        let resumed = resume_subtask(&conn, &subtask.id).unwrap();
+        assert_eq!(resumed.id, session.id);
+        let intervals: i64 = conn
+            .query_row("SELECT COUNT(*) FROM session_intervals WHERE session_id = ?1", [&session.id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(intervals, 2);
+
+        let completion = complete_subtask(&conn, &subtask.id, 30).unwrap();
+        assert!(matches!(completion.subtask.status, SubtaskStatus::Done));
+        let stored: (i64, bool) = conn
+            .query_row("SELECT points, efficient FROM subtasks WHERE id = ?1", [&subtask.id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(stored, (completion.points_earned, true));
+        // One subtask is below the complexity threshold
+        let bonus: Option<i64> = conn
+            .query_row("SELECT bonus_points FROM tasks WHERE id = ?1", [&task.id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(bonus, None);
+        assert!(active_session(&conn, &subtask.id).unwrap().is_none());
+        assert!(list_active_sessions(&conn, Utc::now()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_other_profiles_tasks_are_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let owner = profiles::active_profile_id(&conn).unwrap();
+        let task = create_task(&conn, "Private".to_string(), None, None).unwrap();
+        let subtask = create_subtask(&conn, task.id.clone(), "Notes".to_string(), None, None).unwrap();
+        assert_eq!(get_task_with_subtasks(&conn, &task.id).unwrap().subtasks.len(), 1);
+
+        let other = profiles::create_profile(&conn, "Side project").unwrap();
+        profiles::switch_profile(&conn, &other.id).unwrap();
+        let not_found = |result: Result<(), String>| result.unwrap_err().contains("not found");
+        assert!(not_found(get_task_with_subtasks(&conn, &task.id).map(|_| ())));
+        assert!(not_found(get_task_with_subtasks_and_sessions(&conn, &task.id).map(|_| ())));
+        assert!(not_found(get_subtask_with_session(&conn, &subtask.id).map(|_| ())));
+        assert!(not_found(update_task_status(&conn, &task.id, "done").map(|_| ())));
+        assert!(not_found(update_task_estimate(&conn, &task.id, Some(600))));
+        assert!(not_found(update_subtask_estimate(&conn, &subtask.id, Some(600))));
+        assert!(not_found(delete_subtask(&conn, &subtask.id)));
+        assert!(not_found(delete_task(&conn, &task.id)));
+
+        // Nothing changed for the owner, who can still edit them
+        let stored: (String, Option<i64>) = conn
+            .query_row("SELECT status, estimate_seconds FROM tasks WHERE id = ?1", [&task.id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(stored, ("todo".to_string(), None));
+        let subtasks: i64 = conn.query_row("SELECT COUNT(*) FROM subtasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(subtasks, 1);
+        profiles::switch_profile(&conn, &owner).unwrap();
+        update_subtask_estimate(&conn, &subtask.id, Some(600)).unwrap();
+        assert!(matches!(update_task_status(&conn, &task.id, "done").unwrap().status, TaskStatus::Done));
+        delete_task(&conn, &task.id).unwrap();
+    }
+
+    #[test]
+    fn test_streak_counts_days_in_the_configured_timezone() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let settings = settings::update_settings(&conn, json!({ "timezone": "+14:00" }), &Utc::now().to_rfc3339()).unwrap();
+        let local_yesterday = settings.local_time(Utc::now()).date() - chrono::Duration::days(1);
+        let profile_id = profiles::active_profile_id(&conn).unwrap();
+        conn.execute(
+            "UPDATE user_profile SET current_streak = 3, last_work_date = ?1 WHERE id = ?2",
+            params![local_yesterday.format("%Y-%m-%d").to_string(), &profile_id],
+        )
+        .unwrap();
+
+        // Yesterday in the user's timezone may already be today in UTC
+        let task = create_task(&conn, "API".to_string(), None, None).unwrap();
+        let subtask = create_subtask(&conn, task.id, "Routes".to_string(), None, None).unwrap();
+        start_subtask(&conn, &subtask.id).unwrap();
+        let completion = complete_subtask(&conn, &subtask.id, 60).unwrap();
+        assert_eq!(completion.current_streak, 4);
+    }
+
+    #[test]
+    fn test_completion_scores_xp_and_points_from_all_tracked_sessions() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let task = create_task(&conn, "API".to_string(), None, None).unwrap();
+        let subtask = create_subtask(&conn, task.id, "Routes".to_string(), None, None).unwrap();
+        start_subtask(&conn, &subtask.id).unwrap();
+        complete_subtask(&conn, &subtask.id, 1200).unwrap();
+
+        // Reopened and worked on again with a pause in between
+        conn.execute(
+            "UPDATE subtasks SET status = 'todo', completed_at = NULL, points = NULL, efficient = NULL WHERE id = ?1",
+            [&subtask.id],
+        )
+        .unwrap();
+        start_subtask(&conn, &subtask.id).unwrap();
+        pause_subtask(&conn, &subtask.id, 20).unwrap();
+        resume_subtask(&conn, &subtask.id).unwrap();
+        let completion = complete_subtask(&conn, &subtask.id, 60).unwrap();
+
+        assert_eq!(completion.time_spent_seconds, 1260);
+        assert_eq!(completion.subtask.total_time_seconds, Some(1260));
+        assert_eq!(completion.xp_gained, 1260 + completion.bonus_xp);
+        let rules = settings::scoring_rules(&conn).unwrap();
+        assert_eq!(completion.points_earned, calculate_subtask_points(&rules, 1260, None));
+    }
+
+    #[test]
+    fn test_tracked_seconds_counts_open_interval_until_now() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at)
+             VALUES ('t1', 'API', 'in_progress', '2025-05-01T08:00:00+00:00', '2025-05-01T08:00:00+00:00');
+             INSERT INTO subtasks (id, task_id, title, status, created_at, updated_at)
+             VALUES ('s1', 't1', 'Routes', 'in_progress', '2025-05-01T08:00:00+00:00', '2025-05-01T08:00:00+00:00');
+             INSERT INTO time_sessions (id, subtask_id, started_at, duration_seconds)
+             VALUES ('ts1', 's1', '2025-05-01T09:00:00+00:00', 0);
+             INSERT INTO session_intervals (id, session_id, started_at, ended_at)
+             VALUES ('i1', 'ts1', '2025-05-01T09:00:00+00:00', '2025-05-01T09:20:00+00:00'),
+                    ('i2', 'ts1', '2025-05-01T10:00:00+00:00', NULL);",
+        )
+        .unwrap();
+
+        let now = DateTime::parse_from_rfc3339("2025-05-01T10:05:00+00:00").unwrap().with_timezone(&Utc);
+        assert_eq!(tracked_seconds(&conn, "ts1", now).unwrap(), 1500);
+    }
+}
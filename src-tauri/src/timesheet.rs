@@ -0,0 +1,295 @@
+use crate::analytics::{load_running_intervals, parse_date_range};
+use crate::models::*;
+use crate::settings;
+use chrono::{NaiveDate, SecondsFormat};
+use rusqlite::{Connection, Result as SqlResult};
+use std::collections::{BTreeMap, HashMap};
+
+/// Rounding increments accepted for timesheet durations, in minutes
+pub const ROUNDING_INCREMENTS: [i64; 2] = [6, 15];
+
+/// Which running intervals go into a timesheet
+#[derive(Debug, Clone, Default)]
+pub struct TimesheetFilter {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub category_id: Option<String>,
+    /// Top-level task the subtasks belong to
+    pub task_id: Option<String>,
+}
+
+struct SubtaskInfo {
+    title: String,
+    task_id: String,
+    task_title: String,
+    category_id: Option<String>,
+    category_name: Option<String>,
+}
+
+/// Round to the nearest multiple of `increment_minutes`, halves rounding up
+fn round_duration(seconds: i64, increment_minutes: i64) -> i64 {
+    let increment = increment_minutes * 60;
+    (seconds + increment / 2) / increment * increment
+}
+
+/// One entry per running interval, dated by the day the interval started in the user's timezone
+pub fn build_timesheet(
+    conn: &Connection,
+    filter: &TimesheetFilter,
+    rounding_minutes: Option<i64>,
+    daily_totals: bool,
+) -> Result<Timesheet, String> {
+    if let Some(minutes) = rounding_minutes {
+        if !ROUNDING_INCREMENTS.contains(&minutes) {
+            return Err(format!("Invalid rounding increment: {} minutes (expected 6 or 15)", minutes));
+        }
+    }
+
+    let parse_date = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let (range_start, range_end) = match (filter.start_date.as_deref(), filter.end_date.as_deref()) {
+        (Some(start), Some(end)) => parse_date_range(start, end)?,
+        (Some(start), None) => (parse_date(start)?, NaiveDate::MAX),
+        (None, Some(end)) => (NaiveDate::MIN, parse_date(end)?),
+        (None, None) => (NaiveDate::MIN, NaiveDate::MAX),
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.title, s.task_id, t.title, s.category_id, c.name
+             FROM subtasks s
+             JOIN tasks t ON s.task_id = t.id
+             LEFT JOIN categories c ON s.category_id = c.id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let subtasks: HashMap<String, SubtaskInfo> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                SubtaskInfo {
+                    title: row.get(1)?,
+                    task_id: row.get(2)?,
+                    task_title: row.get(3)?,
+                    category_id: row.get(4)?,
+                    category_name: row.get(5)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<HashMap<_, _>>>()
+        .map_err(|e| e.to_string())?;
+
+    let settings = settings::load_settings(conn)?;
+    let mut intervals = load_running_intervals(conn)?;
+    intervals.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.session_id.cmp(&b.session_id)));
+
+    let mut entries = Vec::new();
+    let mut totals: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+
+    for interval in intervals {
+        let Some(subtask) = subtasks.get(&interval.subtask_id) else {
+            continue;
+        };
+        let date = settings.local_time(interval.start).date();
+
+        if date < range_start
+            || date > range_end
+            || filter.category_id.as_ref().is_some_and(|id| subtask.category_id.as_ref() != Some(id))
+            || filter.task_id.as_ref().is_some_and(|id| &subtask.task_id != id)
+        {
+            continue;
+        }
+
+        let seconds = (interval.end - interval.start).num_seconds();
+        let duration_seconds = match rounding_minutes {
+            Some(minutes) => round_duration(seconds, minutes),
+            None => seconds,
+        };
+        *totals.entry(date).or_default() += duration_seconds;
+
+        entries.push(TimesheetEntry {
+            date: date.format("%Y-%m-%d").to_string(),
+            task_id: subtask.task_id.clone(),
+            task: subtask.task_title.clone(),
+            subtask_id: interval.subtask_id.clone(),
+            subtask: subtask.title.clone(),
+            category: subtask.category_name.clone(),
+            start: interval.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+            end: interval.end.to_rfc3339_opts(SecondsFormat::Secs, true),
+            duration_seconds,
+        });
+    }
+
+    let total_seconds = totals.values().sum();
+    let daily_totals = if daily_totals {
+        totals
+            .into_iter()
+            .map(|(date, duration_seconds)| TimesheetDayTotal {
+                date: date.format("%Y-%m-%d").to_string(),
+                duration_seconds,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Timesheet {
+        entries,
+        daily_totals,
+        total_seconds,
+    })
+}
+
+/// Render a timesheet as CSV. Per-day totals, if present, follow the last entry of each day.
+pub fn to_csv(timesheet: &Timesheet) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["date", "task", "subtask", "category", "start", "end", "duration_seconds", "duration_hours"])
+        .map_err(|e| e.to_string())?;
+
+    let hours = |seconds: i64| format!("{:.2}", seconds as f64 / 3600.0);
+    let mut totals = timesheet.daily_totals.iter().peekable();
+
+    for (index, entry) in timesheet.entries.iter().enumerate() {
+        writer
+            .write_record([
+                entry.date.as_str(),
+                entry.task.as_str(),
+                entry.subtask.as_str(),
+                entry.category.as_deref().unwrap_or(""),
+                entry.start.as_str(),
+                entry.end.as_str(),
+                &entry.duration_seconds.to_string(),
+                &hours(entry.duration_seconds),
+            ])
+            .map_err(|e| e.to_string())?;
+
+        let last_of_day = timesheet
+            .entries
+            .get(index + 1)
+            .map_or(true, |next| next.date != entry.date);
+        if let Some(total) = totals.next_if(|total| last_of_day && total.date == entry.date) {
+            writer
+                .write_record([
+                    total.date.as_str(),
+                    "Total",
+                    "",
+                    "",
+                    "",
+                    "",
+                    &total.duration_seconds.to_string(),
+                    &hours(total.duration_seconds),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+
+    fn seeded_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let backend: String = conn
+            .query_row("SELECT id FROM categories WHERE name = 'backend'", [], |row| row.get(0))
+            .unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at)
+             VALUES ('t1', 'Billing', 'in_progress', '2025-03-01T08:00:00+00:00', '2025-03-01T08:00:00+00:00'),
+                    ('t2', 'Docs', 'in_progress', '2025-03-01T08:00:00+00:00', '2025-03-01T08:00:00+00:00');",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO subtasks (id, task_id, title, status, category_id, created_at, updated_at)
+             VALUES ('s1', 't1', 'Invoices, PDF', 'done', ?1, '2025-03-01T08:00:00+00:00', '2025-03-01T08:00:00+00:00'),
+                    ('s2', 't2', 'README', 'done', NULL, '2025-03-01T08:00:00+00:00', '2025-03-01T08:00:00+00:00')",
+            [&backend],
+        )
+        .unwrap();
+        conn.execute_batch(
+            "INSERT INTO time_sessions (id, subtask_id, started_at, ended_at, duration_seconds)
+             VALUES ('ts1', 's1', '2025-03-03T09:00:00+00:00', '2025-03-03T11:00:00+00:00', 5820),
+                    ('ts2', 's2', '2025-03-04T14:00:00+00:00', '2025-03-04T14:20:00+00:00', 1200);
+             INSERT INTO session_intervals (id, session_id, started_at, ended_at)
+             VALUES ('i1', 'ts1', '2025-03-03T09:00:00+00:00', '2025-03-03T09:50:00+00:00'),
+                    ('i2', 'ts1', '2025-03-03T10:13:00+00:00', '2025-03-03T11:00:00+00:00');",
+        )
+        .unwrap();
+        crate::settings::update_settings(&conn, serde_json::json!({ "timezone": "UTC" }), "2025-03-01T08:00:00+00:00")
+            .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn test_timesheet_rows_follow_running_intervals() {
+        let conn = seeded_connection();
+        let timesheet = build_timesheet(&conn, &TimesheetFilter::default(), None, true).unwrap();
+
+        let durations: Vec<i64> = timesheet.entries.iter().map(|e| e.duration_seconds).collect();
+        assert_eq!(durations, vec![3000, 2820, 1200]);
+        assert_eq!(timesheet.entries[1].start, "2025-03-03T10:13:00Z");
+        assert_eq!(timesheet.entries[2].category, None);
+        assert_eq!(timesheet.daily_totals.len(), 2);
+        assert_eq!(timesheet.daily_totals[0].duration_seconds, 5820);
+        assert_eq!(timesheet.total_seconds, 7020);
+    }
+
+    #[test]
+    fn test_timesheet_dates_follow_the_configured_timezone() {
+        let conn = seeded_connection();
+        crate::settings::update_settings(&conn, serde_json::json!({ "timezone": "+10:00" }), "2025-03-01T08:00:00+00:00")
+            .unwrap();
+        let timesheet = build_timesheet(&conn, &TimesheetFilter::default(), None, true).unwrap();
+
+        // 14:00 UTC on the 4th is midnight of the 5th ten hours east
+        let dates: Vec<&str> = timesheet.entries.iter().map(|e| e.date.as_str()).collect();
+        assert_eq!(dates, vec!["2025-03-03", "2025-03-03", "2025-03-05"]);
+        assert_eq!(timesheet.daily_totals[1].date, "2025-03-05");
+    }
+
+    #[test]
+    fn test_timesheet_filters_and_rounding() {
+        let conn = seeded_connection();
+        let filter = TimesheetFilter {
+            start_date: Some("2025-03-03".to_string()),
+            end_date: Some("2025-03-03".to_string()),
+            task_id: Some("t1".to_string()),
+            ..Default::default()
+        };
+        let timesheet = build_timesheet(&conn, &filter, Some(15), false).unwrap();
+
+        // 50 min -> 45 min, 47 min -> 45 min
+        let durations: Vec<i64> = timesheet.entries.iter().map(|e| e.duration_seconds).collect();
+        assert_eq!(durations, vec![2700, 2700]);
+        assert!(timesheet.daily_totals.is_empty());
+        assert_eq!(round_duration(1260, 6), 1440); // 21 min -> 24 min
+        assert!(build_timesheet(&conn, &filter, Some(10), false).is_err());
+    }
+
+    #[test]
+    fn test_timesheet_csv_quotes_fields_and_appends_day_totals() {
+        let conn = seeded_connection();
+        let timesheet = build_timesheet(&conn, &TimesheetFilter::default(), None, true).unwrap();
+        let csv = to_csv(&timesheet).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "date,task,subtask,category,start,end,duration_seconds,duration_hours");
+        assert_eq!(
+            lines[1],
+            "2025-03-03,Billing,\"Invoices, PDF\",backend,2025-03-03T09:00:00Z,2025-03-03T09:50:00Z,3000,0.83"
+        );
+        assert_eq!(lines[3], "2025-03-03,Total,,,,,5820,1.62");
+        assert_eq!(lines[5], "2025-03-04,Total,,,,,1200,0.33");
+        assert_eq!(lines.len(), 6);
+    }
+}
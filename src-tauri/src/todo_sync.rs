@@ -0,0 +1,629 @@
+use crate::models::*;
+use crate::timer;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+/// A `- [ ] text` line of the markdown file
+#[derive(Debug, Clone, PartialEq)]
+struct ChecklistItem {
+    line: usize,
+    // Byte offset of the character between the brackets
+    mark: usize,
+    text: String,
+    checked: bool,
+}
+
+#[derive(Debug, Clone)]
+struct TodoDocument {
+    lines: Vec<String>,
+    items: Vec<ChecklistItem>,
+    crlf: bool,
+}
+
+fn parse_checklist_line(line: &str) -> Option<(usize, String, bool)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let rest = ["- [", "* [", "+ ["].iter().find_map(|bullet| trimmed.strip_prefix(bullet))?;
+
+    let mut chars = rest.chars();
+    let checked = match chars.next()? {
+        ' ' => false,
+        'x' | 'X' => true,
+        _ => return None,
+    };
+    let text = chars.as_str().strip_prefix(']')?;
+    if !text.is_empty() && !text.starts_with(' ') {
+        return None;
+    }
+
+    Some((indent + 3, text.trim().to_string(), checked))
+}
+
+fn parse_document(content: &str) -> TodoDocument {
+    let lines: Vec<String> = content.lines().map(|line| line.trim_end_matches('\r').to_string()).collect();
+    let items = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            parse_checklist_line(text).map(|(mark, text, checked)| ChecklistItem { line, mark, text, checked })
+        })
+        .filter(|item| !item.text.is_empty())
+        .collect();
+
+    TodoDocument {
+        lines,
+        items,
+        crlf: content.contains("\r\n"),
+    }
+}
+
+/// Edits to apply to the document once every item is reconciled
+#[derive(Default)]
+struct DocumentEdits {
+    marks: HashMap<usize, bool>,
+    removed: HashSet<usize>,
+    appended: Vec<(String, bool)>,
+}
+
+impl DocumentEdits {
+    fn is_empty(&self) -> bool {
+        self.marks.is_empty() && self.removed.is_empty() && self.appended.is_empty()
+    }
+}
+
+/// New items go after the last checklist line, reusing its indentation and bullet
+fn render_document(doc: &TodoDocument, edits: &DocumentEdits) -> String {
+    let last_item = doc.items.last();
+    let prefix = last_item.map_or("- [".to_string(), |item| doc.lines[item.line][..item.mark].to_string());
+    let appended: Vec<String> = edits
+        .appended
+        .iter()
+        .map(|(text, checked)| format!("{}{}] {}", prefix, if *checked { 'x' } else { ' ' }, text))
+        .collect();
+
+    let mut output = Vec::with_capacity(doc.lines.len() + appended.len());
+    for (index, line) in doc.lines.iter().enumerate() {
+        if !edits.removed.contains(&index) {
+            match doc.items.iter().find(|item| item.line == index).and_then(|item| {
+                edits.marks.get(&index).map(|checked| (item.mark, *checked))
+            }) {
+                Some((mark, checked)) => {
+                    let mut line = line.clone();
+                    line.replace_range(mark..mark + 1, if checked { "x" } else { " " });
+                    output.push(line);
+                }
+                None => output.push(line.clone()),
+            }
+        }
+        if last_item.is_some_and(|item| item.line == index) {
+            output.extend(appended.iter().cloned());
+        }
+    }
+    if last_item.is_none() {
+        output.extend(appended);
+    }
+
+    let newline = if doc.crlf { "\r\n" } else { "\n" };
+    let mut content = output.join(newline);
+    content.push_str(newline);
+    content
+}
+
+/// Syncs retried by `sync_todo_file` while the file keeps being edited under it
+const SYNC_ATTEMPTS: usize = 3;
+
+fn read_if_exists(path: &Path) -> Result<Option<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Replace the file through a temporary sibling so editors never see a half-written file.
+/// Returns false and leaves the file alone when it no longer holds `expected` (None when it
+/// didn't exist), i.e. it was edited while the sync ran.
+fn write_atomically(path: &Path, expected: Option<&str>, content: &str) -> Result<bool, String> {
+    let tmp = path.with_extension("devfocus-tmp");
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    let unchanged = read_if_exists(path).map(|current| current.as_deref() == expected);
+    if unchanged != Ok(true) {
+        std::fs::remove_file(&tmp).ok();
+        return unchanged;
+    }
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+fn todo_file_from_row(row: &rusqlite::Row) -> SqlResult<TodoFile> {
+    let conflicts: String = row.get(4)?;
+    Ok(TodoFile {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        path: row.get(2)?,
+        last_synced_at: row.get(3)?,
+        conflicts: serde_json::from_str(&conflicts).unwrap_or_default(),
+    })
+}
+
+pub fn get_todo_file(conn: &Connection, file_id: &str) -> Result<TodoFile, String> {
+    conn.query_row(
+        "SELECT id, task_id, path, last_synced_at, conflicts FROM todo_files WHERE id = ?1",
+        [file_id],
+        todo_file_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("TODO file not found: {}", file_id))
+}
+
+pub fn list_todo_files(conn: &Connection, task_id: Option<&str>) -> Result<Vec<TodoFile>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, task_id, path, last_synced_at, conflicts FROM todo_files
+             WHERE ?1 IS NULL OR task_id = ?1
+             ORDER BY path",
+        )
+        .map_err(|e| e.to_string())?;
+    let files = stmt
+        .query_map([task_id], todo_file_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(files)
+}
+
+/// Link a markdown checklist to a task and run the first sync. The file is created if missing;
+/// items and subtasks with the same title are paired instead of duplicated.
+pub fn link_todo_file(conn: &Connection, task_id: &str, path: &Path, now: DateTime<Utc>) -> Result<TodoFile, String> {
+    if path.is_dir() {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    let path = match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        Some(parent) => parent
+            .canonicalize()
+            .map_err(|e| format!("Folder not found for {}: {}", path.display(), e))?
+            .join(path.file_name().unwrap_or_default()),
+        None => path.to_path_buf(),
+    };
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO todo_files (id, task_id, path, last_synced_at, conflicts) VALUES (?1, ?2, ?3, NULL, '[]')",
+        params![&id, task_id, path.to_string_lossy()],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("{} is already linked to a task", path.display())
+        }
+        e => e.to_string(),
+    })?;
+
+    sync_todo_file(conn, &id, None, now)?;
+    get_todo_file(conn, &id)
+}
+
+struct AppSubtask {
+    title: String,
+    done: bool,
+    tracked: bool,
+}
+
+fn load_subtasks(conn: &Connection, task_id: &str) -> Result<Vec<(String, AppSubtask)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.title, s.status,
+                    EXISTS (SELECT 1 FROM time_sessions ts WHERE ts.subtask_id = s.id)
+             FROM subtasks s WHERE s.task_id = ?1
+             ORDER BY s.created_at, s.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let subtasks = stmt
+        .query_map([task_id], |row| {
+            Ok((
+                row.get(0)?,
+                AppSubtask {
+                    title: row.get(1)?,
+                    done: row.get::<_, String>(2)? == SubtaskStatus::Done.as_str(),
+                    tracked: row.get(3)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqlResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(subtasks)
+}
+
+/// Complete the subtask, crediting the time of its open session if it has one
+fn complete(conn: &Connection, subtask_id: &str, now: DateTime<Utc>) -> Result<(), String> {
+    let duration = match timer::active_session(conn, subtask_id)? {
+        Some(session) => timer::tracked_seconds(conn, &session.id, now)?,
+        None => 0,
+    };
+    timer::complete_subtask(conn, subtask_id, duration)?;
+    Ok(())
+}
+
+/// Reopen the subtask; its points and the task's complexity bonus are earned again on completion
+fn reopen(conn: &Connection, subtask_id: &str, now: DateTime<Utc>) -> Result<(), String> {
+    conn.execute(
+        "UPDATE subtasks SET status = ?1, completed_at = NULL, updated_at = ?2, points = NULL, efficient = NULL
+         WHERE id = ?3",
+        params![SubtaskStatus::Todo.as_str(), now.to_rfc3339(), subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE tasks SET bonus_points = NULL WHERE id = (SELECT task_id FROM subtasks WHERE id = ?1)",
+        [subtask_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn create_from_item(
+    conn: &Connection,
+    task_id: &str,
+    text: &str,
+    checked: bool,
+    now: DateTime<Utc>,
+    report: &mut TodoSyncReport,
+) -> Result<String, String> {
+    let subtask = timer::create_subtask(conn, task_id.to_string(), text.to_string(), None, None)?;
+    report.subtasks_created += 1;
+    if checked {
+        complete(conn, &subtask.id, now)?;
+    }
+    Ok(subtask.id)
+}
+
+/// Three-way merge of the file, the task's subtasks and the snapshot taken at the last sync.
+///
+/// A checkbox changed on one side is applied to the other. Removing an item deletes its subtask
+/// and deleting a subtask removes its item, unless the other side also changed it since the last
+/// sync; those are reported as conflicts and left alone until `prefer` picks a side.
+/// A file edited while the sync runs is synced again from its new content.
+pub fn sync_todo_file(
+    conn: &Connection,
+    file_id: &str,
+    prefer: Option<TodoSyncSide>,
+    now: DateTime<Utc>,
+) -> Result<TodoSyncReport, String> {
+    for _ in 0..SYNC_ATTEMPTS {
+        if let Some(report) = sync_once(conn, file_id, prefer, now)? {
+            return Ok(report);
+        }
+    }
+    let file = get_todo_file(conn, file_id)?;
+    Err(format!("{} kept changing during the sync", file.path))
+}
+
+/// One merge in a transaction; rolled back and None when the file changed before it was written
+fn sync_once(
+    conn: &Connection,
+    file_id: &str,
+    prefer: Option<TodoSyncSide>,
+    now: DateTime<Utc>,
+) -> Result<Option<TodoSyncReport>, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let file = get_todo_file(conn, file_id)?;
+    let path = Path::new(&file.path);
+    let content = read_if_exists(path)?;
+    let doc = parse_document(content.as_deref().unwrap_or(""));
+
+    let snapshot: Vec<(String, String, bool)> = {
+        let mut stmt = conn
+            .prepare("SELECT subtask_id, text, checked FROM todo_items WHERE file_id = ?1 ORDER BY position")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([file_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<SqlResult<Vec<_>>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+    // A vanished file (moved, branch switched) must not read as every item removed
+    if content.is_none() && !snapshot.is_empty() {
+        return Err(format!("{} no longer exists", path.display()));
+    }
+    let subtasks = load_subtasks(conn, &file.task_id)?;
+    let app: HashMap<&str, &AppSubtask> = subtasks.iter().map(|(id, subtask)| (id.as_str(), subtask)).collect();
+
+    let mut report = TodoSyncReport::default();
+    let mut edits = DocumentEdits::default();
+    let mut matched = vec![false; doc.items.len()];
+    let mut next_snapshot: Vec<(String, String, bool)> = Vec::new();
+
+    let take_item = |text: &str, matched: &mut Vec<bool>| {
+        let index = doc.items.iter().enumerate().position(|(i, item)| !matched[i] && item.text == text)?;
+        matched[index] = true;
+        Some(&doc.items[index])
+    };
+
+    for (subtask_id, text, base) in &snapshot {
+        match (take_item(text, &mut matched), app.get(subtask_id.as_str())) {
+            (Some(item), Some(subtask)) => {
+                // Booleans can't diverge: if both sides changed they agree
+                let target = if item.checked != *base { item.checked } else { subtask.done };
+                if subtask.done != target {
+                    if target {
+                        complete(conn, subtask_id, now)?;
+                        report.subtasks_completed += 1;
+                    } else {
+                        reopen(conn, subtask_id, now)?;
+                        report.subtasks_reopened += 1;
+                    }
+                }
+                if item.checked != target {
+                    edits.marks.insert(item.line, target);
+                }
+                next_snapshot.push((subtask_id.clone(), text.clone(), target));
+            }
+            (None, Some(subtask)) => {
+                let changed_in_app = subtask.done != *base || subtask.tracked;
+                match (changed_in_app, prefer) {
+                    (false, _) | (true, Some(TodoSyncSide::File)) => {
+                        conn.execute("DELETE FROM subtasks WHERE id = ?1", [subtask_id])
+                            .map_err(|e| e.to_string())?;
+                        report.subtasks_deleted += 1;
+                    }
+                    (true, Some(TodoSyncSide::App)) => {
+                        edits.appended.push((subtask.title.clone(), subtask.done));
+                        next_snapshot.push((subtask_id.clone(), subtask.title.clone(), subtask.done));
+                    }
+                    (true, None) => {
+                        report.conflicts.push(TodoConflict {
+                            subtask_id: subtask_id.clone(),
+                            text: text.clone(),
+                            kind: TodoConflictKind::RemovedInFile,
+                        });
+                        next_snapshot.push((subtask_id.clone(), text.clone(), *base));
+                    }
+                }
+            }
+            (Some(item), None) => {
+                let changed_in_file = item.checked != *base;
+                match (changed_in_file, prefer) {
+                    (false, _) | (true, Some(TodoSyncSide::App)) => {
+                        edits.removed.insert(item.line);
+                    }
+                    (true, Some(TodoSyncSide::File)) => {
+                        let id = create_from_item(conn, &file.task_id, text, item.checked, now, &mut report)?;
+                        next_snapshot.push((id, text.clone(), item.checked));
+                    }
+                    (true, None) => {
+                        report.conflicts.push(TodoConflict {
+                            subtask_id: subtask_id.clone(),
+                            text: text.clone(),
+                            kind: TodoConflictKind::DeletedInApp,
+                        });
+                        next_snapshot.push((subtask_id.clone(), text.clone(), *base));
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // Items added to the file: pair with an unlinked subtask of the same title or create one
+    let mut linked: HashSet<String> = snapshot.iter().map(|(id, _, _)| id.clone()).collect();
+    for (index, item) in doc.items.iter().enumerate().filter(|(i, _)| !matched[*i]) {
+        let existing = subtasks
+            .iter()
+            .find(|(id, subtask)| !linked.contains(id) && subtask.title == item.text);
+        let (id, checked) = match existing {
+            Some((id, subtask)) => {
+                // On first pairing a completion on either side wins
+                if item.checked && !subtask.done {
+                    complete(conn, id, now)?;
+                    report.subtasks_completed += 1;
+                } else if subtask.done && !item.checked {
+                    edits.marks.insert(doc.items[index].line, true);
+                }
+                (id.clone(), item.checked || subtask.done)
+            }
+            None => (create_from_item(conn, &file.task_id, &item.text, item.checked, now, &mut report)?, item.checked),
+        };
+        linked.insert(id.clone());
+        next_snapshot.push((id, item.text.clone(), checked));
+    }
+
+    // Subtasks added in the app
+    for (id, subtask) in subtasks.iter().filter(|(id, _)| !linked.contains(id)) {
+        edits.appended.push((subtask.title.clone(), subtask.done));
+        next_snapshot.push((id.clone(), subtask.title.clone(), subtask.done));
+    }
+
+    // The file is written before the snapshot; a failed or skipped write rolls the merge back
+    if !edits.is_empty() || (content.is_none() && !next_snapshot.is_empty()) {
+        if !write_atomically(path, content.as_deref(), &render_document(&doc, &edits))? {
+            return Ok(None);
+        }
+        report.file_updated = true;
+    }
+
+    // Background syncs run every few seconds; skip the writes when nothing moved
+    let unchanged = next_snapshot == snapshot
+        && !report.file_updated
+        && !report.changed_app()
+        && report.conflicts == file.conflicts;
+    if unchanged && file.last_synced_at.is_some() {
+        tx.commit().map_err(|e| e.to_string())?;
+        return Ok(Some(report));
+    }
+
+    conn.execute("DELETE FROM todo_items WHERE file_id = ?1", [file_id])
+        .map_err(|e| e.to_string())?;
+    for (position, (subtask_id, text, checked)) in next_snapshot.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO todo_items (id, file_id, subtask_id, text, checked, position) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![Uuid::new_v4().to_string(), file_id, subtask_id, text, checked, position as i64],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "UPDATE todo_files SET last_synced_at = ?1, conflicts = ?2 WHERE id = ?3",
+        params![
+            now.to_rfc3339(),
+            serde_json::to_string(&report.conflicts).map_err(|e| e.to_string())?,
+            file_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(Some(report))
+}
+
+/// A linked file as it was before the sync, with the sync's outcome
+pub type FileSyncResult = (TodoFile, Result<TodoSyncReport, String>);
+
+/// Sync every linked file without picking sides. Returns each file's report;
+/// a file that can't be read is reported as an error without stopping the others,
+/// and one being edited meanwhile is left for the next round.
+pub fn sync_all(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<FileSyncResult>, String> {
+    Ok(list_todo_files(conn, None)?
+        .into_iter()
+        .filter_map(|file| {
+            let report = sync_once(conn, &file.id, None, now).transpose()?;
+            Some((file, report))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+    use crate::test_support::TempDir;
+
+    struct Fixture {
+        conn: Connection,
+        dir: TempDir,
+        task_id: String,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let conn = Connection::open_in_memory().unwrap();
+            create_tables(&conn).unwrap();
+            let task = timer::create_task(&conn, "Release".to_string(), None, None).unwrap();
+            Fixture { conn, dir: TempDir::new("todo"), task_id: task.id }
+        }
+
+        fn path(&self) -> std::path::PathBuf {
+            self.dir.path().join("TODO.md")
+        }
+
+        fn write(&self, content: &str) {
+            std::fs::write(self.path(), content).unwrap();
+        }
+
+        fn read(&self) -> String {
+            std::fs::read_to_string(self.path()).unwrap()
+        }
+
+        fn subtask(&self, title: &str) -> (String, String) {
+            self.conn
+                .query_row(
+                    "SELECT id, status FROM subtasks WHERE task_id = ?1 AND title = ?2",
+                    params![&self.task_id, title],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_write_skips_a_file_edited_meanwhile() {
+        let fx = Fixture::new();
+        fx.write("- [ ] Tag\n");
+        assert!(!write_atomically(&fx.path(), Some("- [ ] Old\n"), "- [x] Tag\n").unwrap());
+        assert_eq!(fx.read(), "- [ ] Tag\n");
+        assert!(!fx.path().with_extension("devfocus-tmp").exists());
+
+        assert!(write_atomically(&fx.path(), Some("- [ ] Tag\n"), "- [x] Tag\n").unwrap());
+        assert_eq!(fx.read(), "- [x] Tag\n");
+    }
+
+    #[test]
+    fn test_parse_checklist_lines() {
+        let doc = parse_document("# Release\n\n- [ ] Tag\n  * [x] Changelog\n- [] nope\n- [ ]\nplain text\r\n");
+        assert!(doc.crlf);
+        let items: Vec<(&str, bool)> = doc.items.iter().map(|item| (item.text.as_str(), item.checked)).collect();
+        assert_eq!(items, vec![("Tag", false), ("Changelog", true)]);
+        assert_eq!(&doc.lines[3][doc.items[1].mark..doc.items[1].mark + 1], "x");
+    }
+
+    #[test]
+    fn test_two_way_checkbox_sync() {
+        let fx = Fixture::new();
+        timer::create_subtask(&fx.conn, fx.task_id.clone(), "Publish".to_string(), None, None).unwrap();
+        fx.write("# Release\n\n- [ ] Tag\n- [x] Changelog\n\nNotes stay put.\n");
+
+        let file = link_todo_file(&fx.conn, &fx.task_id, &fx.path(), Utc::now()).unwrap();
+        assert_eq!(fx.subtask("Changelog").1, "done");
+        assert_eq!(fx.read(), "# Release\n\n- [ ] Tag\n- [x] Changelog\n- [ ] Publish\n\nNotes stay put.\n");
+
+        // Checked in the file → completed in the app
+        fx.write("# Release\n\n- [x] Tag\n- [x] Changelog\n- [ ] Publish\n\nNotes stay put.\n");
+        let report = sync_todo_file(&fx.conn, &file.id, None, Utc::now()).unwrap();
+        assert_eq!(report.subtasks_completed, 1);
+        assert!(!report.file_updated);
+        assert_eq!(fx.subtask("Tag").1, "done");
+
+        // Completed in the app → rewritten in the file
+        let publish = fx.subtask("Publish").0;
+        timer::complete_subtask(&fx.conn, &publish, 0).unwrap();
+        let report = sync_todo_file(&fx.conn, &file.id, None, Utc::now()).unwrap();
+        assert!(report.file_updated);
+        assert_eq!(fx.read(), "# Release\n\n- [x] Tag\n- [x] Changelog\n- [x] Publish\n\nNotes stay put.\n");
+
+        // Unchecked in the file → reopened
+        fx.write("# Release\n\n- [x] Tag\n- [ ] Changelog\n- [x] Publish\n\nNotes stay put.\n");
+        let report = sync_todo_file(&fx.conn, &file.id, None, Utc::now()).unwrap();
+        assert_eq!(report.subtasks_reopened, 1);
+        assert_eq!(fx.subtask("Changelog").1, "todo");
+
+        // A deleted file is an error, not a removal of every item
+        std::fs::remove_file(fx.path()).unwrap();
+        assert!(sync_todo_file(&fx.conn, &file.id, None, Utc::now()).is_err());
+        assert_eq!(fx.subtask("Tag").1, "done");
+    }
+
+    #[test]
+    fn test_removals_and_conflicts() {
+        let fx = Fixture::new();
+        fx.write("- [ ] Tag\n- [ ] Changelog\n- [ ] Publish\n");
+        let file = link_todo_file(&fx.conn, &fx.task_id, &fx.path(), Utc::now()).unwrap();
+
+        // Untouched on the other side: removals propagate
+        let (publish, _) = fx.subtask("Publish");
+        fx.conn.execute("DELETE FROM subtasks WHERE id = ?1", [&publish]).unwrap();
+        fx.write("- [ ] Changelog\n- [ ] Publish\n");
+        let report = sync_todo_file(&fx.conn, &file.id, None, Utc::now()).unwrap();
+        assert_eq!(report.subtasks_deleted, 1);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(fx.read(), "- [ ] Changelog\n");
+
+        // Removed from the file after being completed in the app
+        let (changelog, _) = fx.subtask("Changelog");
+        timer::complete_subtask(&fx.conn, &changelog, 0).unwrap();
+        fx.write("");
+        let report = sync_todo_file(&fx.conn, &file.id, None, Utc::now()).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kind, TodoConflictKind::RemovedInFile);
+        assert_eq!(get_todo_file(&fx.conn, &file.id).unwrap().conflicts, report.conflicts);
+        // Left alone until resolved
+        assert_eq!(fx.subtask("Changelog").1, "done");
+        assert_eq!(sync_todo_file(&fx.conn, &file.id, None, Utc::now()).unwrap().conflicts.len(), 1);
+
+        let report = sync_todo_file(&fx.conn, &file.id, Some(TodoSyncSide::App), Utc::now()).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(fx.read(), "- [x] Changelog\n");
+        assert!(get_todo_file(&fx.conn, &file.id).unwrap().conflicts.is_empty());
+    }
+}
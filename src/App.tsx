@@ -285,6 +285,28 @@ function App() {
     };
   }, [handleBack, refetch, refreshCurrentTask]);
 
+  useEffect(() => {
+    if (!isTauri) return;
+
+    let unlisten: (() => void) | undefined;
+
+    // Emitted when the local HTTP API or the devfocus CLI changes tasks or timers
+    listen('timer-changed', async () => {
+      await refetch();
+      await refreshCurrentTask();
+    })
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch((error) => {
+        console.error('Failed to register timer listener', error);
+      });
+
+    return () => {
+      unlisten?.();
+    };
+  }, [isTauri, refetch, refreshCurrentTask]);
+
   useEffect(() => {
     if (!isTauri) return;
 
@@ -0,0 +1,119 @@
+import React, { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { Button } from '../../../shared/components/Button';
+import { Input } from '../../../shared/components/Input';
+import * as commands from '../../../lib/tauri/commands';
+
+interface DatabaseLockGateProps {
+  children: React.ReactNode;
+}
+
+type LockState = 'checking' | 'locked' | 'unlocked';
+
+// Asks for the passphrase of an encrypted database before rendering the app
+export const DatabaseLockGate: React.FC<DatabaseLockGateProps> = ({ children }) => {
+  const [lockState, setLockState] = useState<LockState>('checking');
+  const [passphrase, setPassphrase] = useState('');
+  const [remember, setRemember] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [submitting, setSubmitting] = useState(false);
+  const isTauri =
+    typeof window !== 'undefined' &&
+    Boolean((window as unknown as { __TAURI_INTERNALS__?: unknown }).__TAURI_INTERNALS__);
+
+  useEffect(() => {
+    if (!isTauri) {
+      setLockState('unlocked');
+      return;
+    }
+
+    commands
+      .getDatabaseEncryptionStatus()
+      .then((status) => setLockState(status.locked ? 'locked' : 'unlocked'))
+      .catch((statusError) => {
+        console.error('Error reading database encryption status:', statusError);
+        setLockState('unlocked');
+      });
+  }, [isTauri]);
+
+  useEffect(() => {
+    if (!isTauri) return;
+
+    let unlisten: (() => void) | undefined;
+
+    // Emitted by the backend once the passphrase opened the database
+    listen('database-unlocked', () => {
+      setLockState('unlocked');
+    })
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch((listenError) => {
+        console.error('Failed to register database unlock listener', listenError);
+      });
+
+    return () => {
+      unlisten?.();
+    };
+  }, [isTauri]);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!passphrase) return;
+
+    setSubmitting(true);
+    setError(null);
+    try {
+      const status = await commands.unlockDatabase(passphrase, remember);
+      setPassphrase('');
+      if (!status.locked) {
+        setLockState('unlocked');
+      }
+    } catch (unlockError) {
+      setError(String(unlockError));
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  if (lockState === 'unlocked') {
+    return <>{children}</>;
+  }
+
+  return (
+    <div className="h-screen w-screen relative flex items-center justify-center overflow-hidden font-sans">
+      <div className="fixed inset-0 z-0 bg-black/65" />
+      {lockState === 'locked' && (
+        <form onSubmit={handleSubmit} className="glass-panel p-6 space-y-4 w-full max-w-sm relative z-10">
+          <div>
+            <h1 className="text-2xl font-bold text-white">Database locked</h1>
+            <p className="text-white/60 mt-2 text-sm">Enter the passphrase to open your DevFocus data.</p>
+          </div>
+
+          <Input
+            label="Passphrase"
+            type="password"
+            value={passphrase}
+            onChange={(e) => setPassphrase(e.target.value)}
+            error={error ?? undefined}
+            required
+            autoFocus
+          />
+
+          <label className="flex items-center gap-2 text-sm text-white/80">
+            <input type="checkbox" checked={remember} onChange={(e) => setRemember(e.target.checked)} />
+            Remember on this device
+          </label>
+
+          <div className="flex justify-end">
+            <Button type="submit" variant="primary" disabled={!passphrase || submitting}>
+              {submitting ? 'Unlocking...' : 'Unlock'}
+            </Button>
+          </div>
+        </form>
+      )}
+    </div>
+  );
+};
+
+export default DatabaseLockGate;
@@ -7,6 +7,7 @@ import { getGeneralMetrics, getAllCategoryStats } from '../../../lib/tauri/comma
 import type { GeneralMetrics, CategoryStats } from '../../../shared/types/common.types';
 import { formatTimeVerbose } from '../../../shared/utils/timeFormatter';
 import { CategoryCard } from '../../categories/components/CategoryCard';
+import { useSettings } from '../../settings/hooks/useSettings';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import {
   ResponsiveContainer,
@@ -42,6 +43,8 @@ const GeneralSummaryWindow: React.FC = () => {
   const [categoryStats, setCategoryStats] = useState<CategoryStats[]>([]);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const { settings } = useSettings();
+  const timezone = settings?.timezone;
 
   const fetchMetrics = useCallback(async () => {
     try {
@@ -64,9 +67,10 @@ const GeneralSummaryWindow: React.FC = () => {
     await getCurrentWindow().close();
   }, []);
 
+  // Daily buckets follow the configured timezone, so reload when it changes
   useEffect(() => {
     fetchMetrics();
-  }, [fetchMetrics]);
+  }, [fetchMetrics, timezone]);
 
   useEffect(() => {
     document.title = 'General Summary | DevFocus';
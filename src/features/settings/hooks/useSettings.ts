@@ -0,0 +1,54 @@
+import { useCallback, useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { getSettings } from '../../../lib/tauri/commands';
+import type { Settings } from '../../../shared/types/common.types';
+import { useSettingsStore } from '../store/settingsStore';
+
+export const useSettings = () => {
+  const { settings, setSettings } = useSettingsStore();
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const isTauri =
+    typeof window !== 'undefined' &&
+    Boolean((window as unknown as { __TAURI_INTERNALS__?: unknown }).__TAURI_INTERNALS__);
+
+  const fetchSettings = useCallback(async () => {
+    try {
+      setLoading(true);
+      setError(null);
+      setSettings(await getSettings());
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to fetch settings');
+    } finally {
+      setLoading(false);
+    }
+  }, [setSettings]);
+
+  useEffect(() => {
+    if (!isTauri) return;
+    fetchSettings();
+  }, [isTauri, fetchSettings]);
+
+  useEffect(() => {
+    if (!isTauri) return;
+
+    let unlisten: (() => void) | undefined;
+
+    // Emitted to every window with the full settings after update_settings
+    listen<Settings>('settings-changed', (event) => {
+      setSettings(event.payload);
+    })
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch((listenError) => {
+        console.error('Failed to register settings listener', listenError);
+      });
+
+    return () => {
+      unlisten?.();
+    };
+  }, [isTauri, setSettings]);
+
+  return { settings, loading, error, refetch: fetchSettings };
+};
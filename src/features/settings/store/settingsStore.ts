@@ -0,0 +1,12 @@
+import { create } from 'zustand';
+import type { Settings } from '../../../shared/types/common.types';
+
+interface SettingsStore {
+  settings: Settings | null;
+  setSettings: (settings: Settings) => void;
+}
+
+export const useSettingsStore = create<SettingsStore>((set) => ({
+  settings: null,
+  setSettings: (settings) => set({ settings }),
+}));
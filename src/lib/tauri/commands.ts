@@ -13,14 +13,54 @@ import type {
   CategoryExperience,
   CategoryStats,
   UserProfile,
+  Granularity,
+  XpTimeline,
+  RangeMetrics,
+  PeriodComparison,
+  FocusHeatmap,
+  EstimationAccuracy,
+  DurationPrediction,
+  TimesheetFormat,
+  BackupInfo,
+  DatabaseEncryptionStatus,
+  DatabaseLocation,
+  ImportMode,
+  ExportSummary,
+  ImportSummary,
+  ImportSource,
+  TrackerImportReport,
+  HttpApiStatus,
+  GitRepository,
+  GitScanSummary,
+  BranchWatcher,
+  TodoFile,
+  TodoSyncReport,
+  TodoSyncSide,
+  WebhookEndpoint,
+  OutboxEntry,
+  OutboxEventType,
+  Plugin,
+  TeamSyncStatus,
+  TeamSyncSummary,
+  TeamMember,
+  TeamLeaderboardEntry,
+  LeaderboardSource,
+  LeaderboardMetric,
+  LeaderboardEntry,
+  Challenge,
+  ChallengeMetric,
+  ChallengeStandings,
+  Settings,
+  SettingsPatch,
 } from '../../shared/types/common.types';
 
 // Task Commands
 export const createTask = async (
   title: string,
-  description?: string
+  description?: string,
+  estimateSeconds?: number
 ): Promise<Task> => {
-  return await invoke('create_task', { title, description });
+  return await invoke('create_task', { title, description, estimateSeconds });
 };
 
 export const listTasksWithActiveSubtasks = async (
@@ -48,6 +88,13 @@ export const updateTaskStatus = async (
   return await invoke('update_task_status', { taskId, status });
 };
 
+export const updateTaskEstimate = async (
+  taskId: string,
+  estimateSeconds: number | null
+): Promise<void> => {
+  return await invoke('update_task_estimate', { taskId, estimateSeconds });
+};
+
 export const deleteTask = async (taskId: string): Promise<void> => {
   return await invoke('delete_task', { taskId });
 };
@@ -56,9 +103,24 @@ export const deleteTask = async (taskId: string): Promise<void> => {
 export const createSubtask = async (
   taskId: string,
   title: string,
-  categoryId?: string
+  categoryId?: string,
+  estimateSeconds?: number
 ): Promise<Subtask> => {
-  return await invoke('create_subtask', { taskId, title, categoryId });
+  return await invoke('create_subtask', { taskId, title, categoryId, estimateSeconds });
+};
+
+export const updateSubtaskEstimate = async (
+  subtaskId: string,
+  estimateSeconds: number | null
+): Promise<void> => {
+  return await invoke('update_subtask_estimate', { subtaskId, estimateSeconds });
+};
+
+export const predictSubtaskDuration = async (
+  title: string,
+  categoryId?: string
+): Promise<DurationPrediction | null> => {
+  return await invoke('predict_subtask_duration', { title, categoryId });
 };
 
 export const deleteSubtask = async (subtaskId: string): Promise<void> => {
@@ -138,11 +200,350 @@ export const getAllCategoryStats = async (): Promise<CategoryStats[]> => {
   return await invoke('get_all_category_stats');
 };
 
+// Analytics Commands
+export const getXpTimeline = async (
+  startDate: string,
+  endDate: string,
+  granularity: Granularity
+): Promise<XpTimeline> => {
+  return await invoke('get_xp_timeline', { startDate, endDate, granularity });
+};
+
+export const getMetricsForRange = async (
+  startDate: string,
+  endDate: string,
+  granularity: Granularity
+): Promise<RangeMetrics> => {
+  return await invoke('get_metrics_for_range', { startDate, endDate, granularity });
+};
+
+export const comparePeriods = async (
+  currentStart: string,
+  currentEnd: string,
+  previousStart: string,
+  previousEnd: string
+): Promise<PeriodComparison> => {
+  return await invoke('compare_periods', {
+    currentStart,
+    currentEnd,
+    previousStart,
+    previousEnd,
+  });
+};
+
+export const getFocusHeatmap = async (
+  categoryId?: string,
+  startDate?: string,
+  endDate?: string
+): Promise<FocusHeatmap> => {
+  return await invoke('get_focus_heatmap', { categoryId, startDate, endDate });
+};
+
+export const getEstimationAccuracy = async (
+  startDate: string,
+  endDate: string,
+  granularity: Granularity
+): Promise<EstimationAccuracy> => {
+  return await invoke('get_estimation_accuracy', { startDate, endDate, granularity });
+};
+
+export const exportReport = async (
+  startDate: string,
+  endDate: string,
+  outputPath: string
+): Promise<string> => {
+  return await invoke('export_report', { startDate, endDate, outputPath });
+};
+
+export const exportTimesheet = async (
+  format: TimesheetFormat,
+  options: {
+    startDate?: string;
+    endDate?: string;
+    categoryId?: string;
+    taskId?: string;
+    roundingMinutes?: 6 | 15;
+    dailyTotals?: boolean;
+    outputPath?: string;
+  } = {}
+): Promise<string> => {
+  return await invoke('export_timesheet', { format, ...options, dailyTotals: options.dailyTotals ?? false });
+};
+
+export const exportIcs = async (
+  startDate: string,
+  endDate: string,
+  outputPath: string
+): Promise<string> => {
+  return await invoke('export_ics', { startDate, endDate, outputPath });
+};
+
+// Backup Commands
+export const createBackup = async (): Promise<BackupInfo> => {
+  return await invoke('create_backup');
+};
+
+export const listBackups = async (): Promise<BackupInfo[]> => {
+  return await invoke('list_backups');
+};
+
+export const restoreBackup = async (path: string): Promise<BackupInfo> => {
+  return await invoke('restore_backup', { path });
+};
+
+// Database Location Commands
+export const getDatabaseLocation = async (): Promise<DatabaseLocation> => {
+  return await invoke('get_database_location');
+};
+
+export const moveDatabase = async (targetPath: string): Promise<DatabaseLocation> => {
+  return await invoke('move_database', { targetPath });
+};
+
+// Database Encryption Commands
+export const getDatabaseEncryptionStatus = async (): Promise<DatabaseEncryptionStatus> => {
+  return await invoke('get_database_encryption_status');
+};
+
+export const unlockDatabase = async (passphrase: string, remember: boolean): Promise<DatabaseEncryptionStatus> => {
+  return await invoke('unlock_database', { passphrase, remember });
+};
+
+export const encryptDatabase = async (passphrase: string, remember: boolean): Promise<DatabaseEncryptionStatus> => {
+  return await invoke('encrypt_database', { passphrase, remember });
+};
+
+export const rekeyDatabase = async (passphrase: string): Promise<DatabaseEncryptionStatus> => {
+  return await invoke('rekey_database', { passphrase });
+};
+
+export const decryptDatabase = async (): Promise<DatabaseEncryptionStatus> => {
+  return await invoke('decrypt_database');
+};
+
+// Import / Export Commands
+export const exportData = async (outputPath: string): Promise<ExportSummary> => {
+  return await invoke('export_data', { outputPath });
+};
+
+export const importData = async (inputPath: string, mode: ImportMode): Promise<ImportSummary> => {
+  return await invoke('import_data', { inputPath, mode });
+};
+
+export const importFromTracker = async (
+  source: ImportSource,
+  inputPath: string,
+  dryRun: boolean
+): Promise<TrackerImportReport> => {
+  return await invoke('import_from_tracker', { source, inputPath, dryRun });
+};
+
+// Git Integration Commands
+export const addGitRepository = async (taskId: string, path: string): Promise<GitRepository> => {
+  return await invoke('add_git_repository', { taskId, path });
+};
+
+export const listGitRepositories = async (taskId?: string): Promise<GitRepository[]> => {
+  return await invoke('list_git_repositories', { taskId });
+};
+
+export const removeGitRepository = async (repositoryId: string): Promise<void> => {
+  return await invoke('remove_git_repository', { repositoryId });
+};
+
+export const scanGitRepositories = async (taskId?: string): Promise<GitScanSummary> => {
+  return await invoke('scan_git_repositories', { taskId });
+};
+
+// Branch Watcher Commands
+export const addBranchWatcher = async (path: string, switchTimer: boolean): Promise<BranchWatcher> => {
+  return await invoke('add_branch_watcher', { path, switchTimer });
+};
+
+export const listBranchWatchers = async (): Promise<BranchWatcher[]> => {
+  return await invoke('list_branch_watchers');
+};
+
+export const setBranchWatcherSwitchTimer = async (
+  watcherId: string,
+  switchTimer: boolean
+): Promise<BranchWatcher> => {
+  return await invoke('set_branch_watcher_switch_timer', { watcherId, switchTimer });
+};
+
+export const removeBranchWatcher = async (watcherId: string): Promise<void> => {
+  return await invoke('remove_branch_watcher', { watcherId });
+};
+
+// TODO File Sync Commands
+export const linkTodoFile = async (taskId: string, path: string): Promise<TodoFile> => {
+  return await invoke('link_todo_file', { taskId, path });
+};
+
+export const listTodoFiles = async (taskId?: string): Promise<TodoFile[]> => {
+  return await invoke('list_todo_files', { taskId });
+};
+
+export const unlinkTodoFile = async (fileId: string): Promise<void> => {
+  return await invoke('unlink_todo_file', { fileId });
+};
+
+export const syncTodoFile = async (fileId: string, prefer?: TodoSyncSide): Promise<TodoSyncReport> => {
+  return await invoke('sync_todo_file', { fileId, prefer });
+};
+
+// Webhook Outbox Commands
+export const addWebhookEndpoint = async (
+  url: string,
+  eventTypes: OutboxEventType[] = []
+): Promise<WebhookEndpoint> => {
+  return await invoke('add_webhook_endpoint', { url, eventTypes });
+};
+
+export const listWebhookEndpoints = async (): Promise<WebhookEndpoint[]> => {
+  return await invoke('list_webhook_endpoints');
+};
+
+export const setWebhookEndpointEnabled = async (
+  endpointId: string,
+  enabled: boolean
+): Promise<WebhookEndpoint> => {
+  return await invoke('set_webhook_endpoint_enabled', { endpointId, enabled });
+};
+
+export const removeWebhookEndpoint = async (endpointId: string): Promise<void> => {
+  return await invoke('remove_webhook_endpoint', { endpointId });
+};
+
+export const listOutbox = async (limit?: number): Promise<OutboxEntry[]> => {
+  return await invoke('list_outbox', { limit });
+};
+
+export const retryOutboxEntry = async (entryId: string): Promise<void> => {
+  return await invoke('retry_outbox_entry', { entryId });
+};
+
+// Plugin Commands
+export const addPlugin = async (name: string, source: string): Promise<Plugin> => {
+  return await invoke('add_plugin', { name, source });
+};
+
+export const listPlugins = async (): Promise<Plugin[]> => {
+  return await invoke('list_plugins');
+};
+
+export const updatePluginSource = async (pluginId: string, source: string): Promise<Plugin> => {
+  return await invoke('update_plugin_source', { pluginId, source });
+};
+
+export const setPluginEnabled = async (pluginId: string, enabled: boolean): Promise<Plugin> => {
+  return await invoke('set_plugin_enabled', { pluginId, enabled });
+};
+
+export const removePlugin = async (pluginId: string): Promise<void> => {
+  return await invoke('remove_plugin', { pluginId });
+};
+
+// Team Sync Commands
+export const getTeamSyncStatus = async (): Promise<TeamSyncStatus> => {
+  return await invoke('get_team_sync_status');
+};
+
+export const setTeamSyncFolder = async (folder: string | null): Promise<TeamSyncStatus> => {
+  return await invoke('set_team_sync_folder', { folder });
+};
+
+export const syncTeamNow = async (): Promise<TeamSyncSummary> => {
+  return await invoke('sync_team_now');
+};
+
+export const listTeamMembers = async (): Promise<TeamMember[]> => {
+  return await invoke('list_team_members');
+};
+
+export const getTeamLeaderboard = async (): Promise<TeamLeaderboardEntry[]> => {
+  return await invoke('get_team_leaderboard');
+};
+
+// Leaderboard & Challenge Commands
+export const getLeaderboard = async (
+  source: LeaderboardSource,
+  metric: LeaderboardMetric,
+  category?: string
+): Promise<LeaderboardEntry[]> => {
+  return await invoke('get_leaderboard', { source, metric, category });
+};
+
+export const createChallenge = async (
+  name: string,
+  metric: ChallengeMetric,
+  source: LeaderboardSource,
+  startDate: string,
+  endDate: string,
+  category?: string
+): Promise<Challenge> => {
+  return await invoke('create_challenge', { name, metric, category, source, startDate, endDate });
+};
+
+export const listChallenges = async (): Promise<Challenge[]> => {
+  return await invoke('list_challenges');
+};
+
+export const getChallengeStandings = async (challengeId: string): Promise<ChallengeStandings> => {
+  return await invoke('get_challenge_standings', { challengeId });
+};
+
+export const deleteChallenge = async (challengeId: string): Promise<void> => {
+  return await invoke('delete_challenge', { challengeId });
+};
+
+// Settings Commands
+export const getSettings = async (): Promise<Settings> => {
+  return await invoke('get_settings');
+};
+
+export const updateSettings = async (patch: SettingsPatch): Promise<Settings> => {
+  return await invoke('update_settings', { patch });
+};
+
+// HTTP API Commands
+export const getHttpApiStatus = async (): Promise<HttpApiStatus> => {
+  return await invoke('get_http_api_status');
+};
+
+export const setHttpApiEnabled = async (enabled: boolean, port?: number): Promise<HttpApiStatus> => {
+  return await invoke('set_http_api_enabled', { enabled, port });
+};
+
+export const regenerateHttpApiToken = async (): Promise<HttpApiStatus> => {
+  return await invoke('regenerate_http_api_token');
+};
+
 // User Profile Commands
 export const getUserProfile = async (): Promise<UserProfile> => {
   return await invoke('get_user_profile');
 };
 
+export const listProfiles = async (): Promise<UserProfile[]> => {
+  return await invoke('list_profiles');
+};
+
+export const createProfile = async (name: string): Promise<UserProfile> => {
+  return await invoke('create_profile', { name });
+};
+
+export const renameProfile = async (profileId: string, name: string): Promise<UserProfile> => {
+  return await invoke('rename_profile', { profileId, name });
+};
+
+export const switchProfile = async (profileId: string): Promise<UserProfile> => {
+  return await invoke('switch_profile', { profileId });
+};
+
+export const deleteProfile = async (profileId: string): Promise<void> => {
+  return await invoke('delete_profile', { profileId });
+};
+
 // Tray Icon Commands
 export const minimizeToTray = async (): Promise<void> => {
   return await invoke('minimize_to_tray');
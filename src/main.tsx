@@ -6,6 +6,7 @@ import GeneralSummaryWindow from './features/metrics/components/GeneralSummaryWi
 import TaskSummaryWindow from './features/metrics/components/TaskSummaryWindow.tsx';
 import SubtaskTrackerWindow from './features/timer/components/SubtaskTrackerWindow.tsx';
 import SplashScreen from './features/splash/components/SplashScreen.tsx';
+import DatabaseLockGate from './features/encryption/components/DatabaseLockGate.tsx';
 import { ThemeProvider } from './shared/contexts/ThemeContext.tsx';
 
 const params = new URLSearchParams(window.location.search);
@@ -21,8 +22,14 @@ const viewComponents: Record<string, ReactElement> = {
   splashscreen: <SplashScreen />,
 };
 
+const mainApp = (
+  <DatabaseLockGate>
+    <App />
+  </DatabaseLockGate>
+);
+
 createRoot(rootElement).render(
   <ThemeProvider>
-    {view ? viewComponents[view] ?? <App /> : <App />}
+    {view ? viewComponents[view] ?? mainApp : mainApp}
   </ThemeProvider>
 );
@@ -9,6 +9,7 @@ export interface Task {
   createdAt: string;
   updatedAt: string;
   completedAt?: string;
+  estimateSeconds?: number | null;
 }
 
 export interface Category {
@@ -45,6 +46,7 @@ export interface Subtask {
   totalTimeSeconds?: number;
   categoryId?: string;
   category?: Category;
+  estimateSeconds?: number | null;
 }
 
 export interface TimeSession {
@@ -65,6 +67,7 @@ export interface TaskWithSubtasks {
   createdAt: string;
   updatedAt: string;
   completedAt?: string;
+  estimateSeconds?: number | null;
   subtasks: Subtask[];
 }
 
@@ -93,6 +96,7 @@ export interface TaskMetrics {
   taskId: string;
   taskTitle: string;
   totalTimeSeconds: number;
+  estimateSeconds?: number | null;
   totalPoints: number;
   subtasksCompleted: number;
   subtasksTotal: number;
@@ -116,6 +120,7 @@ export interface SubtaskCompletion {
 export interface SubtaskWithSession {
   subtask: Subtask;
   session?: TimeSession | null;
+  commits: CommitLink[];
 }
 
 export interface TaskWithSubtasksAndSessions {
@@ -126,6 +131,7 @@ export interface TaskWithSubtasksAndSessions {
   createdAt: string;
   updatedAt: string;
   completedAt?: string;
+  estimateSeconds?: number | null;
   subtasksWithSessions: SubtaskWithSession[];
 }
 
@@ -148,6 +154,8 @@ export interface GeneralMetrics {
 
 export interface UserProfile {
   id: string;
+  name: string;
+  active: boolean;
   level: number;
   totalXp: number;
   currentTitle: string;
@@ -158,4 +166,471 @@ export interface UserProfile {
   updatedAt: string;
   xpForNextLevel: number;
   progressPercentage: number;
+  taskCount: number;
+}
+
+export type Granularity = 'day' | 'week' | 'month' | 'year';
+
+export interface XpTimelinePoint {
+  periodStart: string;
+  periodEnd: string;
+  xpGained: number;
+  totalXp: number;
+  level: number;
+}
+
+export interface CategoryXpSeries {
+  category: Category;
+  points: XpTimelinePoint[];
+}
+
+export interface XpTimeline {
+  startDate: string;
+  endDate: string;
+  granularity: Granularity;
+  global: XpTimelinePoint[];
+  categories: CategoryXpSeries[];
+}
+
+export interface CategoryBreakdown {
+  category?: Category | null;
+  focusedSeconds: number;
+  points: number;
+  subtasksCompleted: number;
+}
+
+export interface PeriodMetrics {
+  periodStart: string;
+  periodEnd: string;
+  points: number;
+  subtasksCompleted: number;
+  tasksCompleted: number;
+  focusedSeconds: number;
+  averageCompletionTimeSeconds: number;
+  categories: CategoryBreakdown[];
+}
+
+export interface RangeMetrics {
+  startDate: string;
+  endDate: string;
+  granularity: Granularity;
+  periods: PeriodMetrics[];
+  totals: PeriodMetrics;
+}
+
+export interface MetricDelta {
+  current: number;
+  previous: number;
+  delta: number;
+  percentChange: number | null;
+}
+
+export interface CategoryTimeDelta {
+  category?: Category | null;
+  focusedSeconds: MetricDelta;
+}
+
+export interface PeriodComparison {
+  current: PeriodMetrics;
+  previous: PeriodMetrics;
+  focusedSeconds: MetricDelta;
+  points: MetricDelta;
+  subtasksCompleted: MetricDelta;
+  tasksCompleted: MetricDelta;
+  averageCompletionTimeSeconds: MetricDelta;
+  categories: CategoryTimeDelta[];
+}
+
+export interface FocusHeatmap {
+  // cells[weekday][hour] in seconds, weekday 0 = Monday, hours in UTC
+  cells: number[][];
+  totalSeconds: number;
+  categoryId?: string | null;
+  startDate?: string | null;
+  endDate?: string | null;
+}
+
+export interface EstimationStats {
+  samples: number;
+  estimatedSeconds: number;
+  actualSeconds: number;
+  // > 1 means work took longer than estimated
+  actualToEstimateRatio: number;
+  underestimatedRatio: number;
+  overestimatedRatio: number;
+}
+
+export interface CategoryEstimation {
+  category?: Category | null;
+  stats: EstimationStats;
+}
+
+export interface EstimationPeriod {
+  periodStart: string;
+  periodEnd: string;
+  overall: EstimationStats;
+  categories: CategoryEstimation[];
+}
+
+export interface EstimationAccuracy {
+  startDate: string;
+  endDate: string;
+  granularity: Granularity;
+  overall: EstimationStats;
+  categories: CategoryEstimation[];
+  periods: EstimationPeriod[];
+}
+
+export interface SimilarSubtask {
+  id: string;
+  title: string;
+  durationSeconds: number;
+  similarity: number;
+}
+
+export interface DurationPrediction {
+  predictedSeconds: number;
+  lowSeconds: number;
+  highSeconds: number;
+  sampleSize: number;
+  basedOnCategory: boolean;
+  similarSubtasks: SimilarSubtask[];
+}
+
+export type TimesheetFormat = 'csv' | 'json';
+
+// Shape of the JSON produced by exportTimesheet
+export interface TimesheetEntry {
+  date: string;
+  taskId: string;
+  task: string;
+  subtaskId: string;
+  subtask: string;
+  category: string | null;
+  start: string;
+  end: string;
+  durationSeconds: number;
+}
+
+export interface TimesheetDayTotal {
+  date: string;
+  durationSeconds: number;
+}
+
+export interface Timesheet {
+  entries: TimesheetEntry[];
+  dailyTotals: TimesheetDayTotal[];
+  totalSeconds: number;
+}
+
+export type BackupKind = 'manual' | 'automatic' | 'pre_restore';
+
+export interface BackupInfo {
+  fileName: string;
+  path: string;
+  kind: BackupKind;
+  createdAt: string;
+  sizeBytes: number;
+  schemaVersion: number | null;
+}
+
+export interface DatabaseEncryptionStatus {
+  available: boolean;
+  encrypted: boolean;
+  locked: boolean;
+  keyFile: string | null;
+}
+
+export type DatabaseLocationSource = 'cli_flag' | 'env' | 'portable' | 'settings_file' | 'default';
+
+export interface DatabaseLocation {
+  path: string;
+  source: DatabaseLocationSource;
+  locationFile: string;
+  warnings: string[];
+}
+
+export type ImportMode = 'replace' | 'merge';
+
+export interface ExportSummary {
+  path: string;
+  tasks: number;
+  subtasks: number;
+  categories: number;
+  sessions: number;
+}
+
+export interface ImportSummary {
+  tasksImported: number;
+  subtasksImported: number;
+  sessionsImported: number;
+  categoriesCreated: number;
+  categoriesRemapped: number;
+  duplicatesSkipped: number;
+}
+
+export type ImportSource = 'toggl' | 'clockify' | 'todo_txt';
+
+export interface TrackerImportReport {
+  source: ImportSource;
+  dryRun: boolean;
+  tasksCreated: number;
+  subtasksCreated: number;
+  sessionsCreated: number;
+  categoriesCreated: number;
+  duplicatesSkipped: number;
+  trackedSeconds: number;
+  xpAwarded: number;
+  warnings: string[];
+}
+
+export interface ActiveSession {
+  sessionId: string;
+  subtaskId: string;
+  subtaskTitle: string;
+  taskId: string;
+  taskTitle: string;
+  startedAt: string;
+  running: boolean;
+  trackedSeconds: number;
+}
+
+export interface HttpApiStatus {
+  enabled: boolean;
+  running: boolean;
+  port: number;
+  token: string;
+}
+
+export interface GitRepository {
+  id: string;
+  taskId: string;
+  path: string;
+  createdAt: string;
+  lastScannedAt: string | null;
+}
+
+export type CommitLinkReason = 'session' | 'reference';
+
+export interface CommitLink {
+  subtaskId: string;
+  repositoryId: string;
+  repositoryPath: string;
+  commitHash: string;
+  summary: string;
+  author: string;
+  committedAt: string;
+  reason: CommitLinkReason;
+}
+
+export interface GitScanSummary {
+  repositoriesScanned: number;
+  commitsScanned: number;
+  linksCreated: number;
+  errors: string[];
+}
+
+export interface BranchWatcher {
+  id: string;
+  path: string;
+  switchTimer: boolean;
+  lastBranch?: string | null;
+  createdAt: string;
+}
+
+export interface BranchSwitch {
+  watcherId: string;
+  branch: string;
+  taskId: string;
+  taskCreated: boolean;
+  runningSubtaskId?: string | null;
+}
+
+export type TodoSyncSide = 'file' | 'app';
+
+export type TodoConflictKind = 'removed_in_file' | 'deleted_in_app';
+
+export interface TodoConflict {
+  subtaskId: string;
+  text: string;
+  kind: TodoConflictKind;
+}
+
+export interface TodoFile {
+  id: string;
+  taskId: string;
+  path: string;
+  lastSyncedAt?: string | null;
+  conflicts: TodoConflict[];
+}
+
+export interface TodoSyncReport {
+  subtasksCreated: number;
+  subtasksCompleted: number;
+  subtasksReopened: number;
+  subtasksDeleted: number;
+  fileUpdated: boolean;
+  conflicts: TodoConflict[];
+}
+
+export type OutboxEventType = 'subtask_completed' | 'task_completed' | 'level_up' | 'streak_milestone';
+
+export interface WebhookEndpoint {
+  id: string;
+  url: string;
+  eventTypes: OutboxEventType[];
+  enabled: boolean;
+  createdAt: string;
+}
+
+export interface OutboxEntry {
+  id: string;
+  endpointId: string;
+  url: string;
+  eventType: OutboxEventType;
+  payload: Record<string, unknown>;
+  createdAt: string;
+  attempts: number;
+  nextAttemptAt: string;
+  deliveredAt?: string | null;
+  lastError?: string | null;
+  abandoned: boolean;
+}
+
+export type PluginHook = 'on_session_start' | 'on_session_stop' | 'on_subtask_completed' | 'calculate_xp';
+
+export interface Plugin {
+  id: string;
+  name: string;
+  source: string;
+  enabled: boolean;
+  hooks: PluginHook[];
+  lastError?: string | null;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface PluginNotification {
+  id: string;
+  pluginId: string;
+  pluginName: string;
+  title: string;
+  body: string;
+  createdAt: string;
+}
+
+export interface TeamSyncStatus {
+  folder?: string;
+  memberId?: string;
+  lastSyncedAt?: string;
+  members: number;
+}
+
+export interface TeamSyncSummary {
+  changesWritten: number;
+  changesMerged: number;
+  members: number;
+  errors: string[];
+}
+
+export interface TeamMember {
+  memberId: string;
+  name: string;
+  level: number;
+  totalXp: number;
+  currentStreak: number;
+  longestStreak: number;
+  lastWorkDate?: string;
+  tasksCompleted: number;
+  subtasksCompleted: number;
+  trackedSeconds: number;
+  isSelf: boolean;
+}
+
+export interface TeamLeaderboardEntry {
+  rank: number;
+  memberId: string;
+  name: string;
+  weeklyPoints: number;
+  totalXp: number;
+  currentStreak: number;
+  isSelf: boolean;
+}
+
+export type LeaderboardSource = 'profiles' | 'team';
+
+export type LeaderboardMetric = 'weekly_points' | 'category_xp' | 'streak';
+
+export type ChallengeMetric = 'focus_seconds' | 'points' | 'subtasks_completed' | 'xp';
+
+export interface LeaderboardEntry {
+  rank: number;
+  participantId: string;
+  name: string;
+  value: number;
+  isSelf: boolean;
+}
+
+export interface Challenge {
+  id: string;
+  name: string;
+  metric: ChallengeMetric;
+  category?: string;
+  source: LeaderboardSource;
+  startDate: string;
+  endDate: string;
+  createdAt: string;
+}
+
+export type ChallengeStatus = 'upcoming' | 'active' | 'finished';
+
+export interface ChallengeStandings {
+  challenge: Challenge;
+  status: ChallengeStatus;
+  entries: LeaderboardEntry[];
+  winners: string[];
+}
+
+export interface PomodoroSettings {
+  focusMinutes: number;
+  shortBreakMinutes: number;
+  longBreakMinutes: number;
+  sessionsBeforeLongBreak: number;
+}
+
+export interface ScoringRules {
+  basePoints: number;
+  efficiencyBonusPoints: number;
+  efficiencyThresholdSeconds: number;
+  complexityBonusPoints: number;
+  complexityMinSubtasks: number;
+}
+
+export interface TraySettings {
+  closeToTray: boolean;
+  startMinimized: boolean;
+}
+
+export interface BackupSchedule {
+  enabled: boolean;
+  intervalHours: number;
+  retention: number;
+}
+
+export interface Settings {
+  timezone: string;
+  idleThresholdMinutes: number;
+  pomodoro: PomodoroSettings;
+  scoring: ScoringRules;
+  tray: TraySettings;
+  backup: BackupSchedule;
+}
+
+export interface SettingsPatch {
+  timezone?: string;
+  idleThresholdMinutes?: number;
+  pomodoro?: Partial<PomodoroSettings>;
+  scoring?: Partial<ScoringRules>;
+  tray?: Partial<TraySettings>;
+  backup?: Partial<BackupSchedule>;
 }